@@ -0,0 +1,205 @@
+//! `npcforge batch spec.toml`: several groups of NPCs, each with its own options and count,
+//! generated in one run.
+//!
+//! ```toml
+//! [defaults]          # optional; applies to every entry that doesn't say otherwise
+//! role = "random"
+//!
+//! [[entry]]
+//! folder = "guards"   # optional; defaults to a numbered name built from the options
+//! count = 3
+//! role = "Town Guard"
+//! low = 2
+//! high = 4
+//!
+//! [[entry]]
+//! name = "Vex"
+//! class = "Warlock"
+//! level = 9
+//! alignment = "Lawful Evil"
+//! ```
+//!
+//! Entries take the same options as an `--input` line, and the command-line flags fill in
+//! whatever neither the entry nor `[defaults]` sets. Every entry is checked before anything is
+//! generated, so a typo in the last entry doesn't surface an hour into the run.
+
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::schema;
+use crate::types::Alignment;
+use crate::{Constraints, GenerateArgs};
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Spec {
+    #[serde(default)]
+    defaults: Constraints,
+    /// Read as plain tables so `folder` can come out before the rest is checked as options
+    #[serde(default, rename = "entry")]
+    entries: Vec<toml::Table>,
+}
+
+/// One entry of a spec, ready to generate.
+pub struct BatchEntry {
+    pub folder: String,
+    pub args: GenerateArgs,
+}
+
+/// A short folder name from the options: the NPC's name, or its species, class and role.
+fn describe(args: &GenerateArgs) -> String {
+    if let Some(name) = &args.name {
+        return name.clone();
+    }
+    let mut parts: Vec<String> = Vec::new();
+    parts.extend(args.race.clone());
+    parts.extend(args.class.as_ref().map(|c| c.replace(',', "-")));
+    if !args.role.eq_ignore_ascii_case("mercenary") && !args.role.eq_ignore_ascii_case("random") {
+        parts.push(args.role.clone());
+    }
+    if parts.is_empty() { "random".to_string() } else { parts.join(" ") }
+}
+
+/// What is wrong with an entry's options, if anything.
+fn check(args: &GenerateArgs) -> Option<String> {
+    if args.name.is_none() && !(1..=25).contains(&args.count) {
+        return Some(format!("count must be between 1 and 25 (got {})", args.count));
+    }
+    if let Some(alignment) = &args.alignment
+        && matches!(alignment.parse::<Alignment>(), Ok(Alignment::Other(_)) | Err(_))
+    {
+        return Some(format!("unrecognized alignment \"{}\" (use e.g. \"CG\", \"LN\" or \"Neutral Evil\")", alignment));
+    }
+    if let Some(from) = &args.from
+        && let Err(e) = schema::load_npc_file(from)
+    {
+        return Some(format!("--from: {:#}", e));
+    }
+    crate::check_levels(args).err()
+}
+
+/// Read the spec at `path` and combine each entry with `[defaults]` and the command-line options
+/// in `args`. Fails, listing every problem, if any entry can't be generated.
+pub fn load(path: &Path, args: &GenerateArgs) -> Result<Vec<BatchEntry>> {
+    let text = fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    let spec: Spec = toml::from_str(&text).context(format!("{} is not a valid batch spec", path.display()))?;
+    if spec.entries.is_empty() {
+        bail!("{} has no [[entry]] tables", path.display());
+    }
+
+    let base = spec.defaults.apply(args);
+    let width = spec.entries.len().to_string().len().max(2);
+    let mut entries = Vec::new();
+    let mut problems = Vec::new();
+    for (i, mut table) in spec.entries.into_iter().enumerate() {
+        let folder = match table.remove("folder") {
+            Some(toml::Value::String(folder)) => Some(folder),
+            Some(_) => {
+                problems.push(format!("entry {}: folder must be a string", i + 1));
+                None
+            }
+            None => None,
+        };
+        let options: Constraints = match toml::Value::Table(table).try_into() {
+            Ok(options) => options,
+            Err(e) => {
+                problems.push(format!("entry {}: {}", i + 1, e.to_string().trim()));
+                continue;
+            }
+        };
+        let args = options.apply(&base);
+        let folder = folder.unwrap_or_else(|| format!("{:0width$}-{}", i + 1, describe(&args)));
+        if let Some(problem) = check(&args) {
+            problems.push(format!("entry {} ({}): {}", i + 1, folder, problem.replace('\n', "\n  ")));
+        }
+        entries.push(BatchEntry { folder, args });
+    }
+    if !problems.is_empty() {
+        bail!("{} has problems; nothing was generated:\n  {}", path.display(), problems.join("\n  "));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use std::path::PathBuf;
+
+    /// Write `spec` to a temporary file named after the test and load it with `flags`.
+    fn load_spec(test: &str, spec: &str, flags: &[&str]) -> Result<Vec<BatchEntry>> {
+        let path: PathBuf = std::env::temp_dir().join(format!("npcforge-batch-{}-{}.toml", test, std::process::id()));
+        fs::write(&path, spec).unwrap();
+        let args = crate::Cli::try_parse_from(std::iter::once("npcforge").chain(flags.iter().copied())).unwrap().generate;
+        let entries = load(&path, &args);
+        let _ = fs::remove_file(&path);
+        entries
+    }
+
+    #[test]
+    fn entries_combine_with_defaults_and_flags() {
+        let spec = r#"
+            [defaults]
+            role = "random"
+
+            [[entry]]
+            folder = "guards"
+            count = 3
+            role = "Town Guard"
+            low = 2
+            high = 4
+
+            [[entry]]
+            name = "Vex"
+            class = "Warlock"
+            level = 9
+
+            [[entry]]
+            class = ["Fighter", "Rogue"]
+            lvl1 = 3
+            lvl2 = 2
+        "#;
+        let entries = load_spec("combine", spec, &["--alignment", "LN", "--ranged"]).unwrap();
+        let folders: Vec<&str> = entries.iter().map(|e| e.folder.as_str()).collect();
+        assert_eq!(folders, ["guards", "02-Vex", "03-Fighter-Rogue"]);
+
+        let guards = &entries[0].args;
+        assert_eq!((guards.count, guards.low, guards.high, guards.role.as_str()), (3, 2, 4, "Town Guard"));
+        assert_eq!(entries[1].args.level, Some(9));
+        assert_eq!(entries[1].args.role, "random");
+        let split = &entries[2].args;
+        assert_eq!((split.class.as_deref(), split.lvl1, split.lvl2), (Some("Fighter,Rogue"), Some(3), Some(2)));
+        // Flags fill in what neither the entry nor the defaults set
+        assert!(entries.iter().all(|e| e.args.alignment.as_deref() == Some("LN") && e.args.ranged));
+    }
+
+    #[test]
+    fn every_bad_entry_is_reported() {
+        let spec = r#"
+            [[entry]]
+            count = 30
+
+            [[entry]]
+            low = 8
+            high = 3
+
+            [[entry]]
+            class = "Fighter,Rogue"
+            lvl1 = 3
+
+            [[entry]]
+            alignment = "Chaotic Stupid"
+
+            [[entry]]
+            lvel = 3
+        "#;
+        let message = format!("{:#}", load_spec("problems", spec, &[]).err().unwrap());
+        assert!(message.contains("entry 1 (01-random): count must be between 1 and 25 (got 30)"), "{}", message);
+        assert!(message.contains("entry 2 (02-random): --low (8) cannot be greater than --high (3)"), "{}", message);
+        assert!(message.contains("entry 3 (03-Fighter-Rogue): Level distribution count (1) doesn't match class count (2)"), "{}", message);
+        assert!(message.contains("entry 4 (04-random): unrecognized alignment"), "{}", message);
+        assert!(message.contains("entry 5: unknown field `lvel`"), "{}", message);
+    }
+}
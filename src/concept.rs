@@ -0,0 +1,231 @@
+//! `--concept "a grumpy dwarf blacksmith who lost his son to a dragon"`: turn a free-text
+//! character idea into generation constraints.
+//!
+//! Species, classes, role, alignment and level are picked out of the text either by keyword
+//! matching or by a short model call. They only fill in options the user didn't give, and the
+//! concept text itself goes into the main prompt so the backstory and personality honor it.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::fmt::Write;
+
+use crate::ollama;
+use crate::types::{Alignment, Class, normalize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, ValueEnum)]
+pub enum Extraction {
+    /// Match species, class, occupation and alignment words in the text
+    #[default]
+    Local,
+    /// Ask the model (falls back to local matching if that fails)
+    Model,
+}
+
+/// Species the text may name, longest first so "half-elf" wins over "elf".
+pub const SPECIES: [&str; 27] = [
+    "Half-Elf", "Half-Orc", "Dragonborn", "Lizardfolk", "Hobgoblin", "Halfling", "Aasimar", "Firbolg",
+    "Tiefling", "Goliath", "Bugbear", "Tabaxi", "Tortle", "Triton", "Yuan-ti", "Genasi", "Goblin",
+    "Kobold", "Warforged", "Changeling", "Kenku", "Dwarf", "Gnome", "Human", "Elf", "Orc", "Owlin",
+];
+
+/// Everyday words for a class.
+const CLASS_WORDS: [(&str, Class); 16] = [
+    ("mage", Class::Wizard),
+    ("wizard", Class::Wizard),
+    ("sorceress", Class::Sorcerer),
+    ("witch", Class::Warlock),
+    ("priest", Class::Cleric),
+    ("priestess", Class::Cleric),
+    ("thief", Class::Rogue),
+    ("assassin", Class::Rogue),
+    ("burglar", Class::Rogue),
+    ("minstrel", Class::Bard),
+    ("knight", Class::Fighter),
+    ("archer", Class::Ranger),
+    ("hunter", Class::Ranger),
+    ("berserker", Class::Barbarian),
+    ("tinkerer", Class::Artificer),
+    ("shaman", Class::Druid),
+];
+
+/// Occupations that make a good `--role`.
+const ROLES: [&str; 36] = [
+    "blacksmith", "merchant", "innkeeper", "tavern keeper", "bartender", "guard", "soldier", "mercenary",
+    "farmer", "fisherman", "sailor", "pirate", "smuggler", "scholar", "librarian", "sage", "noble",
+    "priest", "priestess", "hermit", "hunter", "trapper", "thief", "spy", "assassin", "bandit",
+    "healer", "herbalist", "alchemist", "jeweler", "miner", "carpenter", "cook", "beggar", "bard",
+    "diplomat",
+];
+
+/// What a concept says about the character. Every field is optional.
+#[derive(Debug, Default, Deserialize)]
+pub struct ConceptConstraints {
+    #[serde(default, alias = "race")]
+    pub species: Option<String>,
+    /// Comma-separated, as for `--class`
+    #[serde(default)]
+    pub class: Option<String>,
+    #[serde(default)]
+    pub role: Option<String>,
+    #[serde(default)]
+    pub alignment: Option<String>,
+    #[serde(default)]
+    pub level: Option<u8>,
+}
+
+impl ConceptConstraints {
+    /// One line per constraint found, for showing before generation.
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        let fields = [
+            ("Species", self.species.clone()),
+            ("Class", self.class.clone()),
+            ("Role", self.role.clone()),
+            ("Alignment", self.alignment.clone()),
+            ("Level", self.level.map(|l| l.to_string())),
+        ];
+        for (label, value) in fields {
+            let _ = writeln!(out, "  {}: {}", label, value.as_deref().unwrap_or("(open)"));
+        }
+        out
+    }
+
+    /// Drop values that can't be used: unknown alignments and levels outside 1-20.
+    fn cleaned(mut self) -> ConceptConstraints {
+        let blank = |value: &Option<String>| value.as_deref().is_none_or(|v| v.trim().is_empty() || v.eq_ignore_ascii_case("null"));
+        if blank(&self.species) {
+            self.species = None;
+        }
+        if blank(&self.class) {
+            self.class = None;
+        }
+        if blank(&self.role) {
+            self.role = None;
+        }
+        self.alignment = match self.alignment.as_deref().map(str::parse::<Alignment>) {
+            Some(Ok(Alignment::Other(_))) | Some(Err(_)) | None => None,
+            Some(Ok(alignment)) => Some(alignment.to_string()),
+        };
+        self.level = self.level.filter(|l| (1..=20).contains(l));
+        self
+    }
+}
+
+/// Whole words of `text`, lowercased, with hyphens kept so "half-elf" stays one word.
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '\''))
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// "level 5", "lvl 5", "5th-level" or "5th level".
+fn find_level(words: &[String]) -> Option<u8> {
+    for (i, word) in words.iter().enumerate() {
+        if (word == "level" || word == "lvl")
+            && let Some(n) = words.get(i + 1).and_then(|n| n.parse().ok())
+        {
+            return Some(n);
+        }
+        let (number, rest) = word.split_at(word.find(|c: char| !c.is_ascii_digit()).unwrap_or(word.len()));
+        let suffix_ok = ["st-level", "nd-level", "rd-level", "th-level"].contains(&rest)
+            || (["st", "nd", "rd", "th"].contains(&rest) && words.get(i + 1).is_some_and(|w| w == "level"));
+        if suffix_ok && let Ok(n) = number.parse() {
+            return Some(n);
+        }
+    }
+    None
+}
+
+/// Pick constraints out of the text by keyword.
+pub fn extract_local(concept: &str) -> ConceptConstraints {
+    let words = words(concept);
+    let text = format!(" {} ", words.join(" "));
+    let has = |phrase: &str| text.contains(&format!(" {} ", phrase.to_lowercase()));
+    let has_plural = |phrase: &str| has(phrase) || has(&format!("{}s", phrase));
+
+    let species = SPECIES.iter().find(|s| has_plural(s) || (**s == "Dwarf" && has("dwarves"))).map(|s| s.to_string());
+
+    let mut classes: Vec<Class> = Vec::new();
+    for class in Class::ALL {
+        if has_plural(class.name()) && !classes.contains(&class) {
+            classes.push(class);
+        }
+    }
+    for (word, class) in CLASS_WORDS {
+        if has_plural(word) && !classes.contains(&class) {
+            classes.push(class);
+        }
+    }
+    classes.truncate(3);
+    let class = (!classes.is_empty()).then(|| classes.iter().map(Class::name).collect::<Vec<_>>().join(","));
+
+    let role = ROLES.iter().find(|r| has(r)).map(|r| {
+        let mut chars = r.chars();
+        chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+    });
+
+    // Only the full two-word alignments; "evil" on its own says too little
+    let squashed = normalize(concept);
+    let alignment = Alignment::ALL
+        .into_iter()
+        .filter(|a| squashed.contains(&normalize(a.name())))
+        .max_by_key(|a| a.name().len())
+        .map(|a| a.to_string());
+
+    ConceptConstraints { species, class, role, alignment, level: find_level(&words) }.cleaned()
+}
+
+/// Ask the model to pick the constraints out of the text.
+pub async fn extract_with_model(concept: &str) -> Result<ConceptConstraints> {
+    let client = ollama::client()?;
+    let prompt = format!(
+        "Read this D&D character concept and state what it says about the character. Use null for \
+        anything it does not clearly say; do not guess.\n\nConcept: \"{}\"\n\n\
+        Output ONLY valid JSON in this structure (no additional text):\n\
+        {{\"species\": \"Dwarf or null\", \"class\": \"official D&D class(es), comma-separated, or null\", \
+        \"role\": \"occupation or null\", \"alignment\": \"one of the nine alignments or null\", \"level\": 5}}",
+        concept
+    );
+    let output = ollama::generate(&client, &prompt, true).await?;
+    let constraints: ConceptConstraints =
+        serde_json::from_str(&output.text).context("The model's answer was not the expected JSON")?;
+    Ok(constraints.cleaned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn species_and_role_come_from_keywords() {
+        let constraints = extract_local("a grumpy dwarf blacksmith who lost his son to a dragon");
+        assert_eq!(constraints.species.as_deref(), Some("Dwarf"));
+        assert_eq!(constraints.role.as_deref(), Some("Blacksmith"));
+        assert_eq!((constraints.class, constraints.alignment, constraints.level), (None, None, None));
+    }
+
+    #[test]
+    fn classes_alignment_and_level_come_from_keywords() {
+        let constraints = extract_local("A Chaotic-Good half-elf minstrel, once a thief, now 5th-level");
+        assert_eq!(constraints.species.as_deref(), Some("Half-Elf"));
+        assert_eq!(constraints.class.as_deref(), Some("Rogue,Bard"));
+        assert_eq!(constraints.alignment.as_deref(), Some("Chaotic Good"));
+        assert_eq!(constraints.level, Some(5));
+        assert_eq!(extract_local("an evil priest of level 12").level, Some(12));
+        // "evil" alone is no alignment, and 25 is no level
+        let constraints = extract_local("an evil priest of level 25");
+        assert_eq!((constraints.alignment, constraints.level), (None, None));
+    }
+
+    #[test]
+    fn unusable_model_answers_are_dropped() {
+        let constraints: ConceptConstraints =
+            serde_json::from_str(r#"{"race": "null", "class": " ", "role": "Spy", "alignment": "evil", "level": 30}"#).unwrap();
+        let constraints = constraints.cleaned();
+        assert_eq!((constraints.species, constraints.class), (None, None));
+        assert_eq!(constraints.role.as_deref(), Some("Spy"));
+        assert_eq!((constraints.alignment, constraints.level), (None, None));
+    }
+}
@@ -0,0 +1,192 @@
+//! Fantasy Grounds (5E ruleset) NPC record, as found under `<npc>` in a module's `db.xml`.
+//!
+//! Attacks are written in stat block wording ("Melee Weapon Attack: +6 to hit, ... Hit: 7
+//! (1d8 + 3) slashing damage.") because that is what Fantasy Grounds parses into rollable
+//! actions; spellcasting is a "Spellcasting" trait plus the spell list and slots.
+
+use std::fmt::Write;
+
+use super::{
+    average_damage, html, is_melee, paragraphs, range_feet, signed, spaced_dice, spell_level_label, spellcasting_intro,
+};
+use crate::types::{Ability, Size};
+use crate::{Attack, NPC};
+
+/// `<name type="string">value</name>` at `depth` tabs.
+fn field(out: &mut String, depth: usize, name: &str, kind: &str, value: &str) {
+    let _ = writeln!(out, "{}<{} type=\"{}\">{}</{}>", "\t".repeat(depth), name, kind, html::escape(value), name);
+}
+
+fn open(out: &mut String, depth: usize, name: &str) {
+    let _ = writeln!(out, "{}<{}>", "\t".repeat(depth), name);
+}
+
+fn close(out: &mut String, depth: usize, name: &str) {
+    let _ = writeln!(out, "{}</{}>", "\t".repeat(depth), name);
+}
+
+fn id(n: usize) -> String {
+    format!("id-{:05}", n)
+}
+
+/// Stat block wording for an attack, e.g. "Melee Weapon Attack: +6 to hit, reach 5 ft., one
+/// target. Hit: 7 (1d8 + 3) slashing damage." Attacks named after a known spell are spell attacks.
+fn attack_description(npc: &NPC, attack: &Attack) -> String {
+    let reach = match range_feet(attack) {
+        Some((normal, Some(long))) => format!("range {}/{} ft.", normal, long),
+        Some((normal, None)) => format!("range {} ft.", normal),
+        None => "reach 5 ft.".to_string(),
+    };
+    let kind = if is_melee(attack) { "Melee" } else { "Ranged" };
+    let source = if npc.knows_spell(&attack.name) { "Spell" } else { "Weapon" };
+    let hit = match average_damage(&attack.damage) {
+        Some(average) => format!("{} ({})", average, spaced_dice(&attack.damage)),
+        None => attack.damage.clone(),
+    };
+    format!(
+        "{} {} Attack: {} to hit, {}, one target. Hit: {} {} damage.",
+        kind,
+        source,
+        signed(attack.attack_bonus),
+        reach,
+        hit,
+        attack.damage_type
+    )
+}
+
+pub fn render(npc: &NPC) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    out.push_str("<root version=\"4.1\" dataversion=\"20210708\" release=\"8.1|CoreRPG:4.1\">\n");
+    open(&mut out, 1, "npc");
+    open(&mut out, 2, &id(1));
+    record(&mut out, npc);
+    close(&mut out, 2, &id(1));
+    close(&mut out, 1, "npc");
+    out.push_str("</root>\n");
+    out
+}
+
+fn record(out: &mut String, npc: &NPC) {
+    let d = 3;
+    let size = npc.size.unwrap_or_else(|| Size::for_race(&npc.race));
+
+    field(out, d, "name", "string", &npc.name);
+    field(out, d, "size", "string", size.name());
+    field(out, d, "type", "string", &format!("humanoid ({})", npc.race.to_lowercase()));
+    field(out, d, "alignment", "string", &npc.alignment.to_string().to_lowercase());
+    field(out, d, "ac", "number", &npc.armor_class.to_string());
+    if !npc.equipment.armor.is_empty() {
+        field(out, d, "actext", "string", &format!("({})", npc.equipment.armor.join(", ").to_lowercase()));
+    }
+    field(out, d, "hp", "number", &npc.hit_points.max.to_string());
+    field(out, d, "hd", "string", &format!("({})", npc.hit_points.hit_dice));
+    field(out, d, "speed", "string", &format!("{} ft.", npc.speed));
+
+    open(out, d, "abilities");
+    for ability in Ability::ALL {
+        let name = ability.name().to_lowercase();
+        open(out, d + 1, &name);
+        field(out, d + 2, "score", "number", &npc.ability_score(ability).to_string());
+        field(out, d + 2, "bonus", "number", &npc.ability_mod(ability).to_string());
+        close(out, d + 1, &name);
+    }
+    close(out, d, "abilities");
+
+    let saves: Vec<String> = npc
+        .saving_throws
+        .iter()
+        .filter(|s| s.proficient)
+        .map(|s| {
+            let (first, rest) = s.ability.abbr().split_at(1);
+            format!("{}{} {}", first, rest.to_lowercase(), signed(s.modifier))
+        })
+        .collect();
+    field(out, d, "savingthrows", "string", &saves.join(", "));
+    let skills: Vec<String> = npc
+        .skills
+        .iter()
+        .filter(|s| s.proficiency.is_proficient())
+        .map(|s| format!("{} {}", s.name, signed(s.modifier)))
+        .collect();
+    field(out, d, "skills", "string", &skills.join(", "));
+    field(out, d, "senses", "string", &format!("passive Perception {}", npc.passive_perception));
+    field(out, d, "languages", "string", &npc.languages.join(", "));
+    field(out, d, "cr", "string", "");
+    field(out, d, "xp", "number", "0");
+
+    let mut traits: Vec<(String, String)> =
+        npc.features.iter().map(|f| (f.name.clone(), f.description.clone())).collect();
+    if let Some(spellcasting) = spellcasting_trait(npc) {
+        traits.push(("Spellcasting".to_string(), spellcasting));
+    }
+    if !traits.is_empty() {
+        open(out, d, "traits");
+        for (i, (name, desc)) in traits.iter().enumerate() {
+            open(out, d + 1, &id(i + 1));
+            field(out, d + 2, "name", "string", name);
+            field(out, d + 2, "desc", "string", desc);
+            close(out, d + 1, &id(i + 1));
+        }
+        close(out, d, "traits");
+    }
+
+    if !npc.attacks.is_empty() {
+        open(out, d, "actions");
+        for (i, attack) in npc.attacks.iter().enumerate() {
+            open(out, d + 1, &id(i + 1));
+            field(out, d + 2, "name", "string", &attack.name);
+            field(out, d + 2, "desc", "string", &attack_description(npc, attack));
+            close(out, d + 1, &id(i + 1));
+        }
+        close(out, d, "actions");
+    }
+
+    if let Some(spells) = &npc.spells
+        && !spells.spells_known.is_empty()
+    {
+        if let Some(slots) = &spells.spell_slots {
+            open(out, d, "spellslots");
+            for level in 1..=9 {
+                if slots.slots(level) > 0 {
+                    field(out, d + 1, &format!("level{}", level), "number", &slots.slots(level).to_string());
+                }
+            }
+            close(out, d, "spellslots");
+        }
+        open(out, d, "spells");
+        let mut n = 0;
+        for (level, list) in spells.spells_known.by_level() {
+            for name in list {
+                n += 1;
+                open(out, d + 1, &id(n));
+                field(out, d + 2, "name", "string", name);
+                field(out, d + 2, "level", "number", &level.to_string());
+                close(out, d + 1, &id(n));
+            }
+        }
+        close(out, d, "spells");
+    }
+
+    let mut text = String::new();
+    for paragraph in paragraphs(&npc.backstory) {
+        let _ = write!(text, "<p>{}</p>", html::escape(paragraph));
+    }
+    let _ = writeln!(out, "{}<text type=\"formattedtext\">{}</text>", "\t".repeat(d), text);
+}
+
+fn spellcasting_trait(npc: &NPC) -> Option<String> {
+    let spells = npc.spells.as_ref()?;
+    if spells.spells_known.is_empty() {
+        return None;
+    }
+    let mut text = spellcasting_intro(npc, spells);
+    for (level, list) in spells.spells_known.by_level() {
+        if list.is_empty() {
+            continue;
+        }
+        let label = spell_level_label(spells, level);
+        let names: Vec<String> = list.iter().map(|s| s.to_lowercase()).collect();
+        let _ = write!(text, "\n{}: {}", label, names.join(", "));
+    }
+    Some(text)
+}
@@ -0,0 +1,681 @@
+//! 5etools homebrew: NPCs as `monster` entries, and importing such entries back into NPCs.
+//!
+//! Export writes a complete homebrew file (`_meta` plus one monster) that 5etools loads through
+//! its Homebrew manager. The stat block uses the standard `trait`, `action` and `spellcasting`
+//! arrays; backstory, appearance, personality and the character details a monster has no field
+//! for (classes, role, background, gear) go into the monster's `fluff` entries, which is also
+//! where import reads them back from.
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::path::Path;
+
+use super::{average_damage, is_melee, paragraphs, range_feet, signed, spaced_dice, spellcasting_intro};
+use crate::types::{Ability, Alignment, Size};
+use crate::{Attack, NPC, schema};
+
+const SOURCE: &str = "NPCForge";
+
+fn size_letter(size: Size) -> &'static str {
+    match size {
+        Size::Tiny => "T",
+        Size::Small => "S",
+        Size::Medium => "M",
+        Size::Large => "L",
+        Size::Huge => "H",
+        Size::Gargantuan => "G",
+    }
+}
+
+fn alignment_letters(alignment: &Alignment) -> Vec<&'static str> {
+    match alignment {
+        Alignment::LawfulGood => vec!["L", "G"],
+        Alignment::NeutralGood => vec!["N", "G"],
+        Alignment::ChaoticGood => vec!["C", "G"],
+        Alignment::LawfulNeutral => vec!["L", "N"],
+        Alignment::TrueNeutral => vec!["N"],
+        Alignment::ChaoticNeutral => vec!["C", "N"],
+        Alignment::LawfulEvil => vec!["L", "E"],
+        Alignment::NeutralEvil => vec!["N", "E"],
+        Alignment::ChaoticEvil => vec!["C", "E"],
+        Alignment::Unaligned => vec!["U"],
+        Alignment::Other(_) => vec!["A"],
+    }
+}
+
+fn named_list(name: &str, items: &[(&str, String)]) -> Value {
+    let items: Vec<Value> = items
+        .iter()
+        .filter(|(_, entry)| !entry.is_empty())
+        .map(|(name, entry)| json!({ "type": "item", "name": name, "entry": entry }))
+        .collect();
+    json!({ "type": "entries", "name": name, "entries": [{ "type": "list", "style": "list-hang-notitle", "items": items }] })
+}
+
+pub fn render(npc: &NPC) -> String {
+    let homebrew = json!({
+        "_meta": {
+            "sources": [{
+                "json": SOURCE,
+                "abbreviation": "NPCF",
+                "full": "NPCForge Generated NPCs",
+                "authors": ["NPCForge"],
+                "convertedBy": ["NPCForge"],
+                "version": env!("CARGO_PKG_VERSION"),
+            }],
+        },
+        "monster": [monster(npc)],
+    });
+    serde_json::to_string_pretty(&homebrew).unwrap_or_default()
+}
+
+fn monster(npc: &NPC) -> Value {
+    let size = npc.size.unwrap_or_else(|| Size::for_race(&npc.race));
+    let mut m = Map::new();
+    m.insert("name".into(), json!(npc.name));
+    m.insert("source".into(), json!(SOURCE));
+    m.insert("isNpc".into(), json!(true));
+    m.insert("level".into(), json!(npc.level));
+    m.insert("size".into(), json!([size_letter(size)]));
+    m.insert("type".into(), json!({ "type": "humanoid", "tags": [npc.race.to_lowercase()] }));
+    m.insert("alignment".into(), json!(alignment_letters(&npc.alignment)));
+
+    let mut ac = json!({ "ac": npc.armor_class });
+    if !npc.equipment.armor.is_empty() {
+        ac["from"] = json!(npc.equipment.armor.iter().map(|a| a.to_lowercase()).collect::<Vec<_>>());
+    }
+    m.insert("ac".into(), json!([ac]));
+    m.insert("hp".into(), json!({ "average": npc.hit_points.max, "formula": npc.hit_points.hit_dice }));
+    m.insert("speed".into(), json!({ "walk": npc.speed }));
+    for ability in Ability::ALL {
+        m.insert(ability.abbr().to_lowercase(), json!(npc.ability_score(ability)));
+    }
+
+    let saves: Map<String, Value> = npc
+        .saving_throws
+        .iter()
+        .filter(|s| s.proficient)
+        .map(|s| (s.ability.abbr().to_lowercase(), json!(signed(s.modifier))))
+        .collect();
+    if !saves.is_empty() {
+        m.insert("save".into(), Value::Object(saves));
+    }
+    let skills: Map<String, Value> = npc
+        .skills
+        .iter()
+        .filter(|s| s.proficiency.is_proficient())
+        .map(|s| (s.name.name().to_lowercase(), json!(signed(s.modifier))))
+        .collect();
+    if !skills.is_empty() {
+        m.insert("skill".into(), Value::Object(skills));
+    }
+    m.insert("passive".into(), json!(npc.passive_perception));
+    m.insert("languages".into(), json!(npc.languages));
+    m.insert("cr".into(), json!("Unknown"));
+
+    if !npc.features.is_empty() {
+        let traits: Vec<Value> =
+            npc.features.iter().map(|f| json!({ "name": f.name, "entries": [f.description] })).collect();
+        m.insert("trait".into(), json!(traits));
+    }
+    if let Some(spellcasting) = spellcasting(npc) {
+        m.insert("spellcasting".into(), json!([spellcasting]));
+    }
+    if !npc.attacks.is_empty() {
+        let actions: Vec<Value> = npc
+            .attacks
+            .iter()
+            .map(|a| json!({ "name": a.name, "entries": [action_entry(npc, a)] }))
+            .collect();
+        m.insert("action".into(), json!(actions));
+    }
+    m.insert("fluff".into(), json!({ "entries": fluff(npc) }));
+    Value::Object(m)
+}
+
+/// "{@atk mw} {@hit 6} to hit, reach 5 ft., one target. {@h}7 ({@damage 1d8 + 3}) slashing damage."
+fn action_entry(npc: &NPC, attack: &Attack) -> String {
+    let melee = is_melee(attack);
+    let tag = match (melee, npc.knows_spell(&attack.name)) {
+        (true, false) => "mw",
+        (false, false) => "rw",
+        (true, true) => "ms",
+        (false, true) => "rs",
+    };
+    let reach = match range_feet(attack) {
+        Some((normal, Some(long))) => format!("range {}/{} ft.", normal, long),
+        Some((normal, None)) => format!("range {} ft.", normal),
+        None => "reach 5 ft.".to_string(),
+    };
+    let damage = format!("{{@damage {}}}", spaced_dice(&attack.damage));
+    let hit = match average_damage(&attack.damage) {
+        Some(average) => format!("{} ({})", average, damage),
+        None => damage,
+    };
+    format!(
+        "{{@atk {}}} {{@hit {}}} to hit, {}, one target. {{@h}}{} {} damage.",
+        tag, attack.attack_bonus, reach, hit, attack.damage_type
+    )
+}
+
+fn spellcasting(npc: &NPC) -> Option<Value> {
+    let spells = npc.spells.as_ref()?;
+    if spells.spells_known.is_empty() {
+        return None;
+    }
+    let header = spellcasting_intro(npc, spells)
+        .replace(&format!("spell save DC {}", spells.spell_save_dc), &format!("spell save {{@dc {}}}", spells.spell_save_dc))
+        .replace(
+            &format!("{} to hit", signed(spells.spell_attack_bonus)),
+            &format!("{{@hit {}}} to hit", spells.spell_attack_bonus),
+        );
+    let mut by_level = Map::new();
+    for (level, list) in spells.spells_known.by_level() {
+        if list.is_empty() {
+            continue;
+        }
+        let names: Vec<String> = list.iter().map(|s| format!("{{@spell {}}}", s)).collect();
+        let mut entry = json!({ "spells": names });
+        if let Some(slots) = spells.spell_slots.as_ref().map(|s| s.slots(level))
+            && level > 0
+            && slots > 0
+        {
+            entry["slots"] = json!(slots);
+        }
+        by_level.insert(level.to_string(), entry);
+    }
+    let mut block = json!({
+        "name": "Spellcasting",
+        "type": "spellcasting",
+        "headerEntries": [header],
+        "spells": by_level,
+    });
+    if let Some(ability) = spells.spellcasting_ability {
+        block["ability"] = json!(ability.abbr().to_lowercase());
+    }
+    Some(block)
+}
+
+fn fluff(npc: &NPC) -> Vec<Value> {
+    let backstory: Vec<&str> = paragraphs(&npc.backstory);
+    let a = &npc.appearance;
+    let p = &npc.personality;
+    let eq = &npc.equipment;
+    let mut class = npc.class_summary();
+    if let Some(subclass) = &npc.subclass {
+        class.push_str(&format!(" ({})", subclass));
+    }
+    let mut treasure = format!("{} gp", eq.treasure.gold);
+    if !eq.treasure.items.is_empty() {
+        treasure.push_str(&format!("; {}", eq.treasure.items.join("; ")));
+    }
+    vec![
+        json!({ "type": "entries", "name": "Backstory", "entries": backstory }),
+        named_list(
+            "Appearance",
+            &[
+                ("Age", a.age.to_string()),
+                ("Height", a.height.clone()),
+                ("Weight", a.weight.clone()),
+                ("Eyes", a.eyes.clone()),
+                ("Hair", a.hair.clone()),
+                ("Skin", a.skin.clone()),
+                ("Distinguishing Features", a.distinguishing_features.join("; ")),
+            ],
+        ),
+        named_list(
+            "Personality",
+            &[
+                ("Traits", p.traits.join("; ")),
+                ("Ideals", p.ideals.clone()),
+                ("Bonds", p.bonds.clone()),
+                ("Flaws", p.flaws.clone()),
+            ],
+        ),
+        named_list(
+            "Character",
+            &[
+                ("Race", npc.race.clone()),
+                ("Class", class),
+                ("Role", npc.role.clone()),
+                ("Background", npc.background.clone()),
+                ("Fighting Style", npc.fighting_preference.clone()),
+                ("Tools", npc.tool_proficiencies.join("; ")),
+                ("Weapons", eq.weapons.join("; ")),
+                ("Gear", eq.gear.join("; ")),
+                ("Treasure", treasure),
+            ],
+        ),
+    ]
+}
+
+// ---------------------------------------------------------------------------------------------
+// Import
+// ---------------------------------------------------------------------------------------------
+
+/// Strip 5etools inline tags: "{@spell fire bolt|phb}" becomes "fire bolt", "{@h}" disappears.
+fn strip_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{@") {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let inner = &rest[start + 2..start + end];
+        // "{@tag text|source|display}" shows `display` if given, otherwise `text`
+        if let Some((_, content)) = inner.split_once(' ') {
+            let mut parts = content.split('|');
+            let text = parts.next().unwrap_or_default();
+            out.push_str(parts.nth(1).filter(|d| !d.is_empty()).unwrap_or(text));
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// First `{@tag ...}` argument in `text`, e.g. `tag_value("{@hit 6} to hit", "hit")` is "6".
+fn tag_value<'a>(text: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("{{@{} ", tag);
+    let start = text.find(&open)? + open.len();
+    let end = text[start..].find('}')? + start;
+    Some(text[start..end].split('|').next().unwrap_or_default().trim())
+}
+
+/// "fire bolt" becomes "Fire Bolt"; names that already have capitals are left alone.
+fn title_case(name: &str) -> String {
+    if name.chars().any(char::is_uppercase) {
+        return name.to_string();
+    }
+    name.split(' ')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn entries_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => strip_tags(s),
+        Value::Array(items) => items.iter().map(entries_text).filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" "),
+        Value::Object(obj) => obj
+            .get("entries")
+            .or_else(|| obj.get("items"))
+            .or_else(|| obj.get("entry"))
+            .map(entries_text)
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// `"Name" -> entry` for every named list item under the fluff section called `section`.
+fn fluff_items(monster: &Value, section: &str) -> Map<String, Value> {
+    let mut items = Map::new();
+    let Some(sections) = monster.pointer("/fluff/entries").and_then(Value::as_array) else {
+        return items;
+    };
+    let Some(found) = sections.iter().find(|s| s.get("name").and_then(Value::as_str) == Some(section)) else {
+        return items;
+    };
+    let lists = found.get("entries").and_then(Value::as_array).cloned().unwrap_or_default();
+    for item in lists.iter().filter_map(|l| l.get("items")).filter_map(Value::as_array).flatten() {
+        if let (Some(name), Some(entry)) = (item.get("name").and_then(Value::as_str), item.get("entry")) {
+            items.insert(name.to_string(), Value::String(entries_text(entry)));
+        }
+    }
+    items
+}
+
+fn fluff_paragraphs(monster: &Value, section: &str) -> Vec<String> {
+    monster
+        .pointer("/fluff/entries")
+        .and_then(Value::as_array)
+        .and_then(|sections| sections.iter().find(|s| s.get("name").and_then(Value::as_str) == Some(section)))
+        .and_then(|s| s.get("entries"))
+        .and_then(Value::as_array)
+        .map(|entries| entries.iter().map(entries_text).filter(|p| !p.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+fn text(items: &Map<String, Value>, key: &str) -> String {
+    items.get(key).and_then(Value::as_str).unwrap_or_default().to_string()
+}
+
+fn split_list(text: Option<&Value>) -> Vec<String> {
+    text.and_then(Value::as_str)
+        .map(|s| s.split(';').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn number(value: Option<&Value>) -> Option<i64> {
+    match value? {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.trim().trim_start_matches('+').parse().ok(),
+        // {"number": 30, "condition": "..."} and {"ac": 15, "from": [...]}
+        Value::Object(obj) => number(obj.get("number").or_else(|| obj.get("ac"))),
+        _ => None,
+    }
+}
+
+/// Parse an action entry like "{@atk mw} {@hit 6} to hit, ... {@h}7 ({@damage 1d8 + 3}) slashing
+/// damage." into an attack. Actions without an attack roll are not attacks.
+fn parse_action(name: &str, entry: &str) -> Option<Value> {
+    let attack_bonus: i64 = tag_value(entry, "hit")?.trim_start_matches('+').parse().ok()?;
+    let kinds = tag_value(entry, "atk").unwrap_or("mw");
+    let damage = tag_value(entry, "damage").unwrap_or("0").replace(' ', "");
+    // Damage type: the word(s) between the damage roll and "damage"
+    let plain = strip_tags(entry);
+    let after_hit = plain.split_once("to hit").map(|(_, rest)| rest).unwrap_or(&plain);
+    let damage_type = after_hit
+        .split_once(')')
+        .map(|(_, rest)| rest)
+        .or_else(|| after_hit.split_once(&spaced_dice(&damage)).map(|(_, rest)| rest))
+        .and_then(|rest| rest.split_once(" damage").map(|(t, _)| t.trim().to_string()))
+        .unwrap_or_else(|| "bludgeoning".to_string());
+    let range = if kinds.contains('m') && !kinds.contains('r') {
+        "Melee".to_string()
+    } else {
+        after_hit
+            .split_once("range ")
+            .and_then(|(_, rest)| rest.split_once(" ft.").map(|(r, _)| format!("{} ft.", r)))
+            .unwrap_or_else(|| "Ranged".to_string())
+    };
+    Some(json!({
+        "name": name,
+        "attack_bonus": attack_bonus,
+        "damage": damage,
+        "damage_type": damage_type,
+        "range": range,
+        "properties": [],
+    }))
+}
+
+fn parse_spellcasting(monster: &Value) -> Option<Value> {
+    let block = monster.get("spellcasting")?.as_array()?.first()?;
+    let header = block.get("headerEntries").map(|h| serde_json::to_string(h).unwrap_or_default()).unwrap_or_default();
+    let mut known = Map::new();
+    let mut slots = Map::new();
+    if let Some(levels) = block.get("spells").and_then(Value::as_object) {
+        for (level, entry) in levels {
+            let Ok(level) = level.parse::<u8>() else { continue };
+            let names: Vec<String> = entry
+                .get("spells")
+                .and_then(Value::as_array)
+                .map(|list| list.iter().filter_map(Value::as_str).map(|s| title_case(&strip_tags(s))).collect())
+                .unwrap_or_default();
+            let key = if level == 0 { "cantrips".to_string() } else { format!("level_{}", level) };
+            known.insert(key.clone(), json!(names));
+            if let Some(count) = number(entry.get("slots")) {
+                slots.insert(key, json!(count));
+            }
+        }
+    }
+    let ability = block
+        .get("ability")
+        .and_then(Value::as_str)
+        .and_then(|a| a.parse::<Ability>().ok())
+        .map(|a| a.to_string());
+    Some(json!({
+        "spellcasting_ability": ability,
+        "spell_save_dc": tag_value(&header, "dc").and_then(|v| v.parse::<u8>().ok()).unwrap_or(0),
+        "spell_attack_bonus": tag_value(&header, "hit").and_then(|v| v.trim_start_matches('+').parse::<i8>().ok()).unwrap_or(0),
+        "spell_slots": slots,
+        "spells_known": known,
+    }))
+}
+
+/// "Fighter 5 / Rogue 3 (Battle Master)" -> classes, per-class levels and subclass.
+fn parse_class(text: &str) -> (Vec<String>, Vec<(String, u8)>, Option<String>) {
+    let (classes, subclass) = match text.split_once(" (") {
+        Some((classes, sub)) => (classes, Some(sub.trim_end_matches(')').to_string())),
+        None => (text, None),
+    };
+    let mut names = Vec::new();
+    let mut levels = Vec::new();
+    for part in classes.split('/').map(str::trim).filter(|p| !p.is_empty()) {
+        match part.rsplit_once(' ').and_then(|(name, level)| level.parse::<u8>().ok().map(|l| (name, l))) {
+            Some((name, level)) => {
+                names.push(name.to_string());
+                levels.push((name.to_string(), level));
+            }
+            None => names.push(part.to_string()),
+        }
+    }
+    (names, levels, subclass)
+}
+
+/// Convert one 5etools monster entry into NPC JSON for the regular loader.
+fn monster_to_value(monster: &Value) -> Result<Value> {
+    let name = monster.get("name").and_then(Value::as_str).context("monster has no name")?;
+    let mut npc = Map::new();
+    npc.insert("schema_version".into(), json!(schema::CURRENT_SCHEMA_VERSION));
+    npc.insert("name".into(), json!(name));
+
+    let character = fluff_items(monster, "Character");
+    // The type tag is lowercase; NPCForge's own exports keep the exact race in the fluff
+    let race = character
+        .get("Race")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or_else(|| monster.pointer("/type/tags/0")
+        .and_then(|t| t.as_str().or_else(|| t.get("tag").and_then(Value::as_str)))
+        .or_else(|| monster.pointer("/type/type").and_then(Value::as_str))
+        .or_else(|| monster.get("type").and_then(Value::as_str))
+        .map(title_case))
+        .unwrap_or_else(|| "Humanoid".to_string());
+    npc.insert("race".into(), json!(race));
+
+    let class_text = character.get("Class").and_then(Value::as_str).unwrap_or_default();
+    let (classes, class_levels, subclass) = parse_class(class_text);
+    let level = if class_levels.is_empty() {
+        number(monster.get("level"))
+            .or_else(|| monster.get("cr").and_then(|cr| number(Some(cr)).or_else(|| number(cr.get("cr")))))
+            .unwrap_or(1)
+            .clamp(1, 20)
+    } else {
+        class_levels.iter().map(|(_, l)| *l as i64).sum()
+    };
+    npc.insert("class".into(), json!(if classes.is_empty() { "NPC".to_string() } else { classes.join("/") }));
+    npc.insert("level".into(), json!(level));
+    npc.insert("proficiency_bonus".into(), json!(2 + (level - 1) / 4));
+    if class_levels.len() > 1 {
+        let levels: Map<String, Value> = class_levels.iter().map(|(c, l)| (c.clone(), json!(l))).collect();
+        npc.insert("class_levels".into(), Value::Object(levels));
+    }
+    npc.insert("subclass".into(), json!(subclass));
+    for (key, field) in [("Role", "role"), ("Background", "background"), ("Fighting Style", "fighting_preference")] {
+        npc.insert(field.into(), json!(text(&character, key)));
+    }
+
+    let alignment: String = monster
+        .get("alignment")
+        .and_then(Value::as_array)
+        .map(|letters| letters.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let alignment = match alignment.as_str() {
+        "" => "N".to_string(),
+        "A" => "Any alignment".to_string(),
+        _ => alignment,
+    };
+    npc.insert("alignment".into(), json!(alignment));
+
+    if let Some(letter) = monster.pointer("/size/0").and_then(Value::as_str) {
+        let size = match letter {
+            "T" => Some(Size::Tiny),
+            "S" => Some(Size::Small),
+            "M" => Some(Size::Medium),
+            "L" => Some(Size::Large),
+            "H" => Some(Size::Huge),
+            "G" => Some(Size::Gargantuan),
+            _ => None,
+        };
+        if let Some(size) = size {
+            npc.insert("size".into(), json!(size.name()));
+        }
+    }
+
+    let mut scores = Map::new();
+    for ability in Ability::ALL {
+        let score = number(monster.get(ability.abbr().to_lowercase())).unwrap_or(10);
+        scores.insert(ability.name().to_lowercase(), json!(score));
+    }
+    let dex = number(monster.get("dex")).unwrap_or(10);
+    npc.insert("ability_scores".into(), Value::Object(scores));
+
+    let hp = number(monster.pointer("/hp/average")).unwrap_or(1);
+    let formula = monster.pointer("/hp/formula").and_then(Value::as_str).unwrap_or_default();
+    npc.insert("hit_points".into(), json!({ "max": hp, "current": hp, "temporary": 0, "hit_dice": formula }));
+    let ac = monster.get("ac").and_then(Value::as_array).and_then(|list| list.first());
+    npc.insert("armor_class".into(), json!(number(ac).unwrap_or(10)));
+    npc.insert("initiative".into(), json!((dex - 10).div_euclid(2)));
+    npc.insert("speed".into(), json!(number(monster.pointer("/speed/walk")).unwrap_or(30)));
+
+    let skills: Vec<Value> = monster
+        .get("skill")
+        .and_then(Value::as_object)
+        .map(|skills| {
+            skills
+                .iter()
+                .map(|(name, modifier)| json!({ "name": name, "modifier": number(Some(modifier)).unwrap_or(0), "proficient": true }))
+                .collect()
+        })
+        .unwrap_or_default();
+    npc.insert("skills".into(), json!(skills));
+    // 5etools only has the bonus, so Expertise has to be read off the numbers
+    schema::infer_expertise(&mut npc);
+    let saves: Vec<Value> = monster
+        .get("save")
+        .and_then(Value::as_object)
+        .map(|saves| {
+            saves
+                .iter()
+                .map(|(ability, modifier)| json!({ "ability": ability, "modifier": number(Some(modifier)).unwrap_or(0), "proficient": true }))
+                .collect()
+        })
+        .unwrap_or_default();
+    npc.insert("saving_throws".into(), json!(saves));
+
+    let languages: Vec<String> = match monster.get("languages") {
+        Some(Value::Array(list)) => list.iter().filter_map(Value::as_str).map(strip_tags).collect(),
+        Some(Value::String(s)) => s.split(',').map(|l| strip_tags(l.trim())).collect(),
+        _ => Vec::new(),
+    };
+    npc.insert("languages".into(), json!(languages));
+    npc.insert("tool_proficiencies".into(), json!(split_list(character.get("Tools"))));
+
+    let mut attacks = Vec::new();
+    let mut features = Vec::new();
+    for (key, is_action) in [("trait", false), ("action", true), ("bonus", true), ("reaction", true)] {
+        for entry in monster.get(key).and_then(Value::as_array).into_iter().flatten() {
+            let name = strip_tags(entry.get("name").and_then(Value::as_str).unwrap_or_default());
+            let raw = entry.get("entries").and_then(Value::as_array).and_then(|e| e.first()).and_then(Value::as_str);
+            match raw.filter(|_| is_action).and_then(|raw| parse_action(&name, raw)) {
+                Some(attack) => attacks.push(attack),
+                None => features.push(json!({ "name": name, "description": entries_text(entry.get("entries").unwrap_or(&Value::Null)) })),
+            }
+        }
+    }
+    npc.insert("attacks".into(), json!(attacks));
+    npc.insert("features".into(), json!(features));
+    if let Some(spells) = parse_spellcasting(monster) {
+        npc.insert("spells".into(), spells);
+    }
+
+    let armor: Vec<String> = ac
+        .and_then(|ac| ac.get("from"))
+        .and_then(Value::as_array)
+        .map(|from| from.iter().filter_map(Value::as_str).map(|a| title_case(&strip_tags(a))).collect())
+        .unwrap_or_default();
+    let treasure = character.get("Treasure").and_then(Value::as_str).unwrap_or_default();
+    let (gold, items) = match treasure.split_once(';') {
+        Some((gold, items)) => (gold, items),
+        None => (treasure, ""),
+    };
+    let gold = gold.trim().trim_end_matches("gp").trim().parse::<u32>().unwrap_or(0);
+    let items: Vec<&str> = items.split(';').map(str::trim).filter(|i| !i.is_empty()).collect();
+    npc.insert(
+        "equipment".into(),
+        json!({
+            "armor": armor,
+            "weapons": split_list(character.get("Weapons")),
+            "gear": split_list(character.get("Gear")),
+            "treasure": { "gold": gold, "items": items },
+        }),
+    );
+
+    let personality = fluff_items(monster, "Personality");
+    npc.insert(
+        "personality".into(),
+        json!({
+            "traits": split_list(personality.get("Traits")),
+            "ideals": text(&personality, "Ideals"),
+            "bonds": text(&personality, "Bonds"),
+            "flaws": text(&personality, "Flaws"),
+        }),
+    );
+    let appearance = fluff_items(monster, "Appearance");
+    npc.insert(
+        "appearance".into(),
+        json!({
+            "age": number(appearance.get("Age")).unwrap_or(0),
+            "height": text(&appearance, "Height"),
+            "weight": text(&appearance, "Weight"),
+            "eyes": text(&appearance, "Eyes"),
+            "hair": text(&appearance, "Hair"),
+            "skin": text(&appearance, "Skin"),
+            "distinguishing_features": split_list(appearance.get("Distinguishing Features")),
+        }),
+    );
+    npc.insert("backstory".into(), json!(fluff_paragraphs(monster, "Backstory").join("\n\n")));
+
+    Ok(Value::Object(npc))
+}
+
+/// Read a 5etools homebrew file (or a single monster object) and convert every monster in it.
+/// Each NPC comes with the repairs made while loading it.
+pub fn import_file(path: &Path) -> Result<Vec<(NPC, Vec<String>)>> {
+    let text = fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    let value: Value = serde_json::from_str(&text).context(format!("{} is not valid JSON", path.display()))?;
+    let monsters = match value.get("monster") {
+        Some(Value::Array(monsters)) => monsters.clone(),
+        _ if value.get("name").is_some() && value.get("str").is_some() => vec![value],
+        _ => bail!("{} does not contain any 5etools monsters", path.display()),
+    };
+
+    monsters
+        .iter()
+        .map(|monster| {
+            let name = monster.get("name").and_then(Value::as_str).unwrap_or("unnamed monster");
+            monster_to_value(monster)
+                .and_then(schema::upgrade_value)
+                .context(format!("Failed to import {}", name))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_then_import_round_trips() {
+        let mut original: NPC = serde_json::from_str(include_str!("../../tests/fixtures/seraphine.json")).unwrap();
+        let homebrew: Value = serde_json::from_str(&render(&original)).unwrap();
+        let (imported, repairs) = schema::upgrade_value(monster_to_value(&homebrew["monster"][0]).unwrap()).unwrap();
+        assert!(repairs.is_empty(), "{:?}", repairs);
+
+        // 5etools actions have no weapon properties; everything else comes back
+        for attack in &mut original.attacks {
+            attack.properties.clear();
+        }
+        assert_eq!(serde_json::to_value(&imported).unwrap(), serde_json::to_value(&original).unwrap());
+    }
+}
@@ -0,0 +1,326 @@
+//! Foundry VTT actor JSON for the dnd5e system, for the "Import Data" action on an NPC actor.
+//!
+//! Written in the dnd5e 3.x data layout; newer system versions migrate it on import.
+
+use serde_json::{Map, Value, json};
+
+use super::{html, is_melee, paragraphs, range_feet};
+use crate::types::{Ability, DamageType, Size, Skill};
+use crate::{Attack, NPC, ProficiencyLevel};
+
+fn skill_key(skill: Skill) -> &'static str {
+    match skill {
+        Skill::Acrobatics => "acr",
+        Skill::AnimalHandling => "ani",
+        Skill::Arcana => "arc",
+        Skill::Athletics => "ath",
+        Skill::Deception => "dec",
+        Skill::History => "his",
+        Skill::Insight => "ins",
+        Skill::Intimidation => "itm",
+        Skill::Investigation => "inv",
+        Skill::Medicine => "med",
+        Skill::Nature => "nat",
+        Skill::Perception => "prc",
+        Skill::Performance => "prf",
+        Skill::Persuasion => "per",
+        Skill::Religion => "rel",
+        Skill::SleightOfHand => "slt",
+        Skill::Stealth => "ste",
+        Skill::Survival => "sur",
+    }
+}
+
+fn ability_key(ability: Ability) -> String {
+    ability.abbr().to_lowercase()
+}
+
+fn size_key(size: Size) -> &'static str {
+    match size {
+        Size::Tiny => "tiny",
+        Size::Small => "sm",
+        Size::Medium => "med",
+        Size::Large => "lg",
+        Size::Huge => "huge",
+        Size::Gargantuan => "grg",
+    }
+}
+
+fn proficiency_value(level: ProficiencyLevel) -> f32 {
+    match level {
+        ProficiencyLevel::None => 0.0,
+        ProficiencyLevel::HalfProficient => 0.5,
+        ProficiencyLevel::Proficient => 1.0,
+        ProficiencyLevel::Expertise => 2.0,
+    }
+}
+
+/// Languages dnd5e knows by key; anything else goes in the free-text field.
+fn language_key(language: &str) -> Option<&'static str> {
+    let key = match language.to_lowercase().as_str() {
+        "common" => "common",
+        "dwarvish" => "dwarvish",
+        "elvish" => "elvish",
+        "giant" => "giant",
+        "gnomish" => "gnomish",
+        "goblin" => "goblin",
+        "halfling" => "halfling",
+        "orc" | "orcish" => "orc",
+        "abyssal" => "abyssal",
+        "celestial" => "celestial",
+        "deep speech" => "deep",
+        "draconic" => "draconic",
+        "infernal" => "infernal",
+        "primordial" => "primordial",
+        "sylvan" => "sylvan",
+        "undercommon" => "undercommon",
+        "druidic" => "druidic",
+        "thieves' cant" | "thieves cant" => "cant",
+        _ => return None,
+    };
+    Some(key)
+}
+
+fn description(text: &str) -> Value {
+    json!({ "value": format!("<p>{}</p>", html::escape(text)) })
+}
+
+pub fn render(npc: &NPC) -> String {
+    let actor = actor(npc);
+    serde_json::to_string_pretty(&actor).unwrap_or_default()
+}
+
+fn actor(npc: &NPC) -> Value {
+    let mut abilities = Map::new();
+    for ability in Ability::ALL {
+        let proficient = npc.saving_throws.iter().any(|s| s.ability == ability && s.proficient);
+        abilities.insert(
+            ability_key(ability),
+            json!({ "value": npc.ability_score(ability), "proficient": if proficient { 1 } else { 0 } }),
+        );
+    }
+
+    let mut skills = Map::new();
+    for skill in &npc.skills {
+        skills.insert(
+            skill_key(skill.name).to_string(),
+            json!({ "value": proficiency_value(skill.proficiency), "ability": ability_key(skill.ability) }),
+        );
+    }
+
+    let (known, custom): (Vec<&String>, Vec<&String>) =
+        npc.languages.iter().partition(|l| language_key(l).is_some());
+    let languages: Vec<&str> = known.iter().filter_map(|l| language_key(l)).collect();
+    let custom: Vec<&str> = custom.iter().map(|l| l.as_str()).collect();
+
+    let mut spell_slots = Map::new();
+    let mut spellcasting = String::new();
+    if let Some(spells) = &npc.spells {
+        for level in 1..=9 {
+            let slots = spells.spell_slots.as_ref().map(|s| s.slots(level)).unwrap_or(0);
+            spell_slots.insert(format!("spell{}", level), json!({ "value": slots, "override": slots }));
+        }
+        if let Some(ability) = spells.spellcasting_ability {
+            spellcasting = ability_key(ability);
+        }
+    }
+
+    let size = npc.size.unwrap_or_else(|| Size::for_race(&npc.race));
+    let hp = &npc.hit_points;
+
+    json!({
+        "name": npc.name,
+        "type": "npc",
+        "img": "icons/svg/mystery-man.svg",
+        "system": {
+            "abilities": abilities,
+            "attributes": {
+                "ac": { "calc": "flat", "flat": npc.armor_class },
+                "hp": { "value": hp.max, "max": hp.max, "temp": hp.temporary, "formula": hp.hit_dice },
+                "init": { "ability": "", "bonus": npc.initiative - npc.ability_mod(Ability::Dexterity) },
+                "movement": { "walk": npc.speed, "units": "ft", "hover": false },
+                "spellcasting": spellcasting,
+            },
+            "details": {
+                "biography": { "value": biography(npc), "public": "" },
+                "alignment": npc.alignment.to_string(),
+                "race": npc.race,
+                "type": { "value": "humanoid", "subtype": npc.race, "swarm": "", "custom": "" },
+                "spellLevel": npc.caster_level(),
+                "source": "NPCForge",
+            },
+            "traits": {
+                "size": size_key(size),
+                "languages": { "value": languages, "custom": custom.join("; ") },
+            },
+            "skills": skills,
+            "spells": spell_slots,
+            "currency": { "pp": 0, "gp": npc.equipment.treasure.gold, "ep": 0, "sp": 0, "cp": 0 },
+        },
+        "items": items(npc),
+        "prototypeToken": {
+            "name": npc.name,
+            "actorLink": false,
+            "disposition": 0,
+        },
+    })
+}
+
+fn biography(npc: &NPC) -> String {
+    let mut bio = String::new();
+    for paragraph in paragraphs(&npc.backstory) {
+        bio.push_str(&format!("<p>{}</p>", html::escape(paragraph)));
+    }
+
+    let a = &npc.appearance;
+    bio.push_str("<h3>Appearance</h3>");
+    bio.push_str(&format!(
+        "<p>Age {}, {}, {}. {} eyes, {} hair, {} skin.</p>",
+        a.age,
+        html::escape(&a.height),
+        html::escape(&a.weight),
+        html::escape(&a.eyes),
+        html::escape(&a.hair),
+        html::escape(&a.skin)
+    ));
+    if !a.distinguishing_features.is_empty() {
+        bio.push_str("<ul>");
+        for feature in &a.distinguishing_features {
+            bio.push_str(&format!("<li>{}</li>", html::escape(feature)));
+        }
+        bio.push_str("</ul>");
+    }
+
+    let p = &npc.personality;
+    bio.push_str("<h3>Personality</h3><ul>");
+    if !p.traits.is_empty() {
+        bio.push_str(&format!("<li><strong>Traits:</strong> {}</li>", html::escape(&p.traits.join("; "))));
+    }
+    bio.push_str(&format!("<li><strong>Ideals:</strong> {}</li>", html::escape(&p.ideals)));
+    bio.push_str(&format!("<li><strong>Bonds:</strong> {}</li>", html::escape(&p.bonds)));
+    bio.push_str(&format!("<li><strong>Flaws:</strong> {}</li></ul>", html::escape(&p.flaws)));
+    bio
+}
+
+fn items(npc: &NPC) -> Vec<Value> {
+    let mut items = Vec::new();
+
+    for attack in &npc.attacks {
+        items.push(weapon(npc, attack));
+    }
+    // Weapons that were not written up as attacks still belong in the inventory
+    for name in &npc.equipment.weapons {
+        if !npc.attacks.iter().any(|a| a.name.eq_ignore_ascii_case(name)) {
+            items.push(json!({
+                "name": name,
+                "type": "weapon",
+                "system": { "quantity": 1, "equipped": false },
+            }));
+        }
+    }
+    for name in &npc.equipment.armor {
+        items.push(json!({
+            "name": name,
+            "type": "equipment",
+            "system": { "quantity": 1, "equipped": true, "type": { "value": armor_type(name) } },
+        }));
+    }
+    for name in npc.equipment.gear.iter().chain(&npc.equipment.treasure.items) {
+        items.push(json!({ "name": name, "type": "loot", "system": { "quantity": 1 } }));
+    }
+
+    if let Some(spells) = &npc.spells {
+        for (level, list) in spells.spells_known.by_level() {
+            for name in list {
+                items.push(json!({
+                    "name": name,
+                    "type": "spell",
+                    "system": {
+                        "level": level,
+                        "preparation": { "mode": "prepared", "prepared": true },
+                    },
+                }));
+            }
+        }
+    }
+
+    for feature in &npc.features {
+        items.push(json!({
+            "name": feature.name,
+            "type": "feat",
+            "system": { "description": description(&feature.description), "type": { "value": "class" } },
+        }));
+    }
+
+    items
+}
+
+/// Weapon item whose attack bonus and damage reproduce the NPC's numbers in Foundry.
+fn weapon(npc: &NPC, attack: &Attack) -> Value {
+    let melee = is_melee(attack);
+    let finesse = attack.properties.iter().any(|p| p.eq_ignore_ascii_case("finesse"));
+    let ability = if !melee || (finesse && npc.ability_mod(Ability::Dexterity) > npc.ability_mod(Ability::Strength)) {
+        Ability::Dexterity
+    } else {
+        Ability::Strength
+    };
+    // Foundry adds the ability modifier and proficiency itself; carry only the difference
+    let bonus = attack.attack_bonus - npc.ability_mod(ability) - npc.proficiency_bonus;
+    // Mixed damage like "piercing + fire" has no dnd5e key; keep the formula, leave it untyped
+    let damage_type = match &attack.damage_type {
+        DamageType::Other(_) => String::new(),
+        damage_type => damage_type.to_string(),
+    };
+    let range = match range_feet(attack) {
+        Some((normal, long)) => json!({ "value": normal, "long": long, "units": "ft" }),
+        None => json!({ "value": 5, "long": null, "units": "ft" }),
+    };
+    json!({
+        "name": attack.name,
+        "type": "weapon",
+        "system": {
+            "quantity": 1,
+            "equipped": true,
+            "proficient": 1,
+            "ability": ability_key(ability),
+            "actionType": if melee { "mwak" } else { "rwak" },
+            "activation": { "type": "action", "cost": 1 },
+            "target": { "value": 1, "type": "creature" },
+            "range": range,
+            "attack": { "bonus": if bonus == 0 { String::new() } else { bonus.to_string() }, "flat": false },
+            // The damage expression already includes the ability modifier
+            "damage": { "parts": [[attack.damage, damage_type]], "versatile": "" },
+            "properties": attack.properties.iter().filter_map(|p| property_key(p)).collect::<Vec<_>>(),
+        },
+    })
+}
+
+/// dnd5e weapon property keys; "Versatile (1d10)" and the like match on the leading word.
+fn property_key(property: &str) -> Option<&'static str> {
+    let property = property.to_lowercase();
+    let keys = [
+        ("ammunition", "amm"),
+        ("finesse", "fin"),
+        ("heavy", "hvy"),
+        ("light", "lgt"),
+        ("loading", "lod"),
+        ("reach", "rch"),
+        ("thrown", "thr"),
+        ("two-handed", "two"),
+        ("versatile", "ver"),
+    ];
+    keys.iter().find(|(name, _)| property.starts_with(name)).map(|(_, key)| *key)
+}
+
+fn armor_type(name: &str) -> &'static str {
+    let name = name.to_lowercase();
+    if name.contains("shield") {
+        "shield"
+    } else if ["plate", "splint", "chain mail", "ring mail"].iter().any(|a| name.contains(a)) && !name.contains("half plate") {
+        "heavy"
+    } else if ["half plate", "breastplate", "scale", "chain shirt", "hide"].iter().any(|a| name.contains(a)) {
+        "medium"
+    } else {
+        "light"
+    }
+}
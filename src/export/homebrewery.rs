@@ -0,0 +1,172 @@
+//! Homebrewery (V3 renderer) markup: a framed monster stat block followed by a lore page.
+//! GM Binder reads the same `{{monster,frame}}` syntax.
+
+use std::fmt::Write;
+
+use super::{average_damage, is_melee, paragraphs, range_feet, signed, spaced_dice, spell_level_label, spellcasting_intro};
+use crate::types::{Ability, Size};
+use crate::{Attack, NPC, Spellcasting};
+
+pub fn render(npc: &NPC) -> String {
+    let mut out = String::new();
+    stat_block(&mut out, npc);
+    out.push_str("\n\\page\n\n");
+    lore_page(&mut out, npc);
+    out
+}
+
+fn stat_block(out: &mut String, npc: &NPC) {
+    let size = npc.size.unwrap_or_else(|| Size::for_race(&npc.race));
+    out.push_str("{{monster,frame\n");
+    let _ = writeln!(out, "## {}", npc.name);
+    let _ = writeln!(
+        out,
+        "*{} humanoid ({}), {}*",
+        size,
+        npc.race.to_lowercase(),
+        npc.alignment.to_string().to_lowercase()
+    );
+    out.push_str("___\n");
+    let _ = writeln!(out, "**Armor Class** :: {}", npc.armor_class);
+    let _ = writeln!(out, "**Hit Points** :: {} ({})", npc.hit_points.max, npc.hit_points.hit_dice);
+    let _ = writeln!(out, "**Speed** :: {} ft.", npc.speed);
+    out.push_str("___\n");
+
+    let header: Vec<String> = Ability::ALL.iter().map(|a| format!("  {}  ", a.abbr())).collect();
+    let _ = writeln!(out, "|{}|", header.join("|"));
+    let _ = writeln!(out, "|{}", ":-----:|".repeat(header.len()));
+    let scores: Vec<String> = Ability::ALL
+        .iter()
+        .map(|&a| format!("{} ({})", npc.ability_score(a), signed(npc.ability_mod(a))))
+        .collect();
+    let _ = writeln!(out, "|{}|", scores.join("|"));
+    out.push_str("___\n");
+
+    let saves: Vec<String> = npc
+        .saving_throws
+        .iter()
+        .filter(|s| s.proficient)
+        .map(|s| format!("{} {}", s.ability.abbr(), signed(s.modifier)))
+        .collect();
+    if !saves.is_empty() {
+        let _ = writeln!(out, "**Saving Throws** :: {}", saves.join(", "));
+    }
+    let skills: Vec<String> = npc
+        .skills
+        .iter()
+        .filter(|s| s.proficiency.is_proficient())
+        .map(|s| format!("{} {}", s.name, signed(s.modifier)))
+        .collect();
+    if !skills.is_empty() {
+        let _ = writeln!(out, "**Skills** :: {}", skills.join(", "));
+    }
+    let _ = writeln!(out, "**Senses** :: passive Perception {}", npc.passive_perception);
+    let languages = if npc.languages.is_empty() { "—".to_string() } else { npc.languages.join(", ") };
+    let _ = writeln!(out, "**Languages** :: {}", languages);
+    let _ = writeln!(out, "**Proficiency Bonus** :: {}", signed(npc.proficiency_bonus));
+    out.push_str("___\n");
+
+    for feature in &npc.features {
+        let _ = writeln!(out, "***{}.*** {}\n", feature.name, feature.description);
+    }
+    if let Some(spells) = &npc.spells
+        && !spells.spells_known.is_empty()
+    {
+        spellcasting(out, npc, spells);
+    }
+
+    if !npc.attacks.is_empty() {
+        out.push_str("### Actions\n");
+        for attack in &npc.attacks {
+            action(out, npc, attack);
+        }
+    }
+    out.push_str("}}\n");
+}
+
+fn spellcasting(out: &mut String, npc: &NPC, spells: &Spellcasting) {
+    let _ = writeln!(out, "***Spellcasting.*** {}\n", spellcasting_intro(npc, spells));
+    for (level, list) in spells.spells_known.by_level() {
+        if list.is_empty() {
+            continue;
+        }
+        let label = spell_level_label(spells, level);
+        let names: Vec<String> = list.iter().map(|s| s.to_lowercase()).collect();
+        let _ = writeln!(out, "{}: *{}*\n", label, names.join(", "));
+    }
+}
+
+/// Stat block wording for an attack; attacks named after a known spell are spell attacks.
+fn action(out: &mut String, npc: &NPC, attack: &Attack) {
+    let reach = match range_feet(attack) {
+        Some((normal, Some(long))) => format!("range {}/{} ft.", normal, long),
+        Some((normal, None)) => format!("range {} ft.", normal),
+        None => "reach 5 ft.".to_string(),
+    };
+    let kind = if is_melee(attack) { "Melee" } else { "Ranged" };
+    let source = if npc.knows_spell(&attack.name) { "Spell" } else { "Weapon" };
+    let hit = match average_damage(&attack.damage) {
+        Some(average) => format!("{} ({})", average, spaced_dice(&attack.damage)),
+        None => attack.damage.clone(),
+    };
+    let _ = writeln!(
+        out,
+        "***{}.*** *{} {} Attack:* {} to hit, {}, one target. *Hit:* {} {} damage.\n",
+        attack.name,
+        kind,
+        source,
+        signed(attack.attack_bonus),
+        reach,
+        hit,
+        attack.damage_type
+    );
+}
+
+fn lore_page(out: &mut String, npc: &NPC) {
+    let _ = writeln!(out, "## {}", npc.name);
+    let _ = writeln!(out, "*{} {} · {} · {} background*\n", npc.race, npc.class_summary(), npc.role, npc.background);
+
+    for paragraph in paragraphs(&npc.backstory) {
+        let _ = writeln!(out, "{}\n", paragraph);
+    }
+
+    let a = &npc.appearance;
+    out.push_str("{{descriptive\n#### Appearance\n");
+    let _ = writeln!(
+        out,
+        "Age {}, {}, {}. {} eyes, {} hair, {} skin.",
+        a.age, a.height, a.weight, a.eyes, a.hair, a.skin
+    );
+    if !a.distinguishing_features.is_empty() {
+        out.push('\n');
+    }
+    for feature in &a.distinguishing_features {
+        let _ = writeln!(out, "- {}", feature);
+    }
+    out.push_str("}}\n\n");
+
+    let p = &npc.personality;
+    out.push_str("### Personality\n");
+    if !p.traits.is_empty() {
+        let _ = writeln!(out, "**Traits.** {}\n", p.traits.join("; "));
+    }
+    let _ = writeln!(out, "**Ideals.** {}\n", p.ideals);
+    let _ = writeln!(out, "**Bonds.** {}\n", p.bonds);
+    let _ = writeln!(out, "**Flaws.** {}", p.flaws);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_shows_average_and_dice() {
+        let npc: NPC = serde_json::from_str(include_str!("../../tests/fixtures/seraphine.json")).unwrap();
+        let mut out = String::new();
+        for attack in &npc.attacks {
+            action(&mut out, &npc, attack);
+        }
+        assert!(out.contains("*Melee Weapon Attack:* +5 to hit, reach 5 ft., one target. *Hit:* 6 (1d8 + 2) piercing damage."));
+        assert!(out.contains("*Ranged Spell Attack:* +7 to hit, range 120 ft., one target. *Hit:* 5 (1d10) force damage."));
+    }
+}
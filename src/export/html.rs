@@ -0,0 +1,316 @@
+//! Self-contained HTML character sheet (inline CSS, no external assets) and a batch index page.
+
+use std::fmt::Write;
+
+use super::{ordinal, paragraphs, signed};
+use crate::types::{Ability, Size};
+use crate::{NPC, ProficiencyLevel};
+
+const STYLE: &str = r#"
+body { font-family: Georgia, "Times New Roman", serif; background: #eee5ce; color: #222; margin: 0; padding: 2rem; }
+.sheet { max-width: 960px; margin: 0 auto; background: #fdf8ec; border: 2px solid #58180d; padding: 1.5rem 2rem; box-shadow: 0 0 8px rgba(0,0,0,.25); }
+h1 { color: #58180d; font-variant: small-caps; margin: 0; font-size: 2.2rem; }
+h2 { color: #58180d; font-variant: small-caps; border-bottom: 2px solid #c9ad6a; margin: 1.5rem 0 .5rem; }
+.subtitle { font-style: italic; margin: .25rem 0 0; }
+.abilities { display: grid; grid-template-columns: repeat(6, 1fr); gap: .5rem; margin-top: 1rem; }
+.ability { border: 2px solid #58180d; border-radius: 8px; text-align: center; padding: .4rem 0; background: #fff; }
+.ability .label { font-size: .75rem; font-weight: bold; letter-spacing: .05em; }
+.ability .mod { font-size: 1.6rem; font-weight: bold; }
+.ability .score { font-size: .9rem; border: 1px solid #58180d; border-radius: 1rem; display: inline-block; padding: 0 .6rem; }
+.combat { display: grid; grid-template-columns: repeat(5, 1fr); gap: .5rem; margin-top: 1rem; }
+.stat { border: 1px solid #58180d; text-align: center; padding: .4rem; background: #fff; }
+.stat .value { font-size: 1.4rem; font-weight: bold; }
+.stat .label { font-size: .75rem; text-transform: uppercase; }
+.columns { display: grid; grid-template-columns: 1fr 1fr; gap: 0 2rem; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: .2rem .4rem; border-bottom: 1px solid #e0d6b8; }
+th { color: #58180d; }
+ul { margin: .25rem 0; padding-left: 1.2rem; }
+.proficient { font-weight: bold; }
+.muted { color: #777; }
+.feature { margin: .4rem 0; }
+@media print { body { background: none; padding: 0; } .sheet { box-shadow: none; border: none; } }
+"#;
+
+/// Escape text for use in HTML element content and attribute values.
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+pub fn render(npc: &NPC) -> String {
+    let mut body = String::from("<div class=\"sheet\">\n");
+    header(&mut body, npc);
+    abilities(&mut body, npc);
+    combat(&mut body, npc);
+
+    body.push_str("<div class=\"columns\">\n<div>\n");
+    proficiencies(&mut body, npc);
+    body.push_str("</div>\n<div>\n");
+    attacks(&mut body, npc);
+    equipment(&mut body, npc);
+    body.push_str("</div>\n</div>\n");
+
+    spells(&mut body, npc);
+    features(&mut body, npc);
+    personality(&mut body, npc);
+    backstory(&mut body, npc);
+    body.push_str("</div>\n");
+    page(&npc.name, &body)
+}
+
+fn header(out: &mut String, npc: &NPC) {
+    let size = npc.size.unwrap_or_else(|| Size::for_race(&npc.race));
+    let _ = writeln!(out, "<h1>{}</h1>", escape(&npc.name));
+    let mut classes = npc.class_summary();
+    if let Some(subclass) = &npc.subclass {
+        let _ = write!(classes, " ({})", subclass);
+    }
+    let _ = writeln!(
+        out,
+        "<p class=\"subtitle\">{} {} · {} · {}</p>",
+        size,
+        escape(&npc.race),
+        escape(&classes),
+        escape(&npc.alignment.to_string())
+    );
+    let _ = writeln!(
+        out,
+        "<p class=\"subtitle\">{} · {} background · {} fighter</p>",
+        escape(&npc.role),
+        escape(&npc.background),
+        escape(&npc.fighting_preference)
+    );
+}
+
+fn abilities(out: &mut String, npc: &NPC) {
+    out.push_str("<div class=\"abilities\">\n");
+    for ability in Ability::ALL {
+        let _ = writeln!(
+            out,
+            "<div class=\"ability\"><div class=\"label\">{}</div><div class=\"mod\">{}</div><div class=\"score\">{}</div></div>",
+            ability.name().to_uppercase(),
+            signed(npc.ability_mod(ability)),
+            npc.ability_score(ability)
+        );
+    }
+    out.push_str("</div>\n");
+}
+
+fn combat(out: &mut String, npc: &NPC) {
+    let hp = &npc.hit_points;
+    let stats = [
+        ("Armor Class", npc.armor_class.to_string()),
+        ("Hit Points", format!("{} <span class=\"muted\">({})</span>", hp.max, escape(&hp.hit_dice))),
+        ("Speed", format!("{} ft.", npc.speed)),
+        ("Initiative", signed(npc.initiative)),
+        ("Proficiency", signed(npc.proficiency_bonus)),
+    ];
+    out.push_str("<div class=\"combat\">\n");
+    for (label, value) in stats {
+        let _ = writeln!(out, "<div class=\"stat\"><div class=\"value\">{}</div><div class=\"label\">{}</div></div>", value, label);
+    }
+    out.push_str("</div>\n");
+}
+
+fn proficiencies(out: &mut String, npc: &NPC) {
+    out.push_str("<h2>Saving Throws</h2>\n<ul>\n");
+    for save in &npc.saving_throws {
+        let class = if save.proficient { " class=\"proficient\"" } else { "" };
+        let _ = writeln!(out, "<li{}>{} {}</li>", class, save.ability, signed(save.modifier));
+    }
+    out.push_str("</ul>\n<h2>Skills</h2>\n<ul>\n");
+    for skill in &npc.skills {
+        let class = if skill.proficiency.is_proficient() { " class=\"proficient\"" } else { "" };
+        let note = match skill.proficiency {
+            ProficiencyLevel::Expertise => " <span class=\"muted\">(expertise)</span>",
+            ProficiencyLevel::HalfProficient => " <span class=\"muted\">(half)</span>",
+            _ => "",
+        };
+        let _ = writeln!(out, "<li{}>{} {}{}</li>", class, skill.name, signed(skill.modifier), note);
+    }
+    out.push_str("</ul>\n<h2>Senses &amp; Languages</h2>\n<ul>\n");
+    let _ = writeln!(out, "<li>Passive Perception {}</li>", npc.passive_perception);
+    let _ = writeln!(out, "<li>Passive Insight {}</li>", npc.passive_insight);
+    let _ = writeln!(out, "<li>Passive Investigation {}</li>", npc.passive_investigation);
+    if !npc.languages.is_empty() {
+        let _ = writeln!(out, "<li>Languages: {}</li>", escape(&npc.languages.join(", ")));
+    }
+    if !npc.tool_proficiencies.is_empty() {
+        let _ = writeln!(out, "<li>Tools: {}</li>", escape(&npc.tool_proficiencies.join(", ")));
+    }
+    out.push_str("</ul>\n");
+}
+
+fn attacks(out: &mut String, npc: &NPC) {
+    if npc.attacks.is_empty() {
+        return;
+    }
+    out.push_str("<h2>Attacks</h2>\n<table>\n<tr><th>Name</th><th>Hit</th><th>Damage</th><th>Range</th></tr>\n");
+    for attack in &npc.attacks {
+        let mut name = escape(&attack.name);
+        if !attack.properties.is_empty() {
+            let _ = write!(name, "<br><span class=\"muted\">{}</span>", escape(&attack.properties.join(", ")));
+        }
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{} {}</td><td>{}</td></tr>",
+            name,
+            signed(attack.attack_bonus),
+            escape(&attack.damage),
+            escape(&attack.damage_type.to_string()),
+            escape(attack.range.as_deref().unwrap_or("—"))
+        );
+    }
+    out.push_str("</table>\n");
+}
+
+fn equipment(out: &mut String, npc: &NPC) {
+    let eq = &npc.equipment;
+    out.push_str("<h2>Equipment</h2>\n<ul>\n");
+    for (label, items) in [("Armor", &eq.armor), ("Weapons", &eq.weapons), ("Gear", &eq.gear)] {
+        if !items.is_empty() {
+            let _ = writeln!(out, "<li><strong>{}:</strong> {}</li>", label, escape(&items.join(", ")));
+        }
+    }
+    out.push_str("</ul>\n<h2>Treasure</h2>\n<ul>\n");
+    let _ = writeln!(out, "<li>{} gp</li>", eq.treasure.gold);
+    for item in &eq.treasure.items {
+        let _ = writeln!(out, "<li>{}</li>", escape(item));
+    }
+    out.push_str("</ul>\n");
+}
+
+fn spells(out: &mut String, npc: &NPC) {
+    let Some(spells) = &npc.spells else { return };
+    if spells.spells_known.is_empty() {
+        return;
+    }
+    out.push_str("<h2>Spellcasting</h2>\n");
+    if let Some(ability) = spells.spellcasting_ability {
+        let _ = writeln!(
+            out,
+            "<p>Ability <strong>{}</strong> · Save DC <strong>{}</strong> · Attack <strong>{}</strong></p>",
+            ability,
+            spells.spell_save_dc,
+            signed(spells.spell_attack_bonus)
+        );
+    }
+    out.push_str("<table>\n<tr><th>Level</th><th>Slots</th><th>Spells</th></tr>\n");
+    for (level, list) in spells.spells_known.by_level() {
+        if list.is_empty() {
+            continue;
+        }
+        let label = if level == 0 { "Cantrips".to_string() } else { ordinal(level) };
+        let slots = match spells.spell_slots.as_ref().map(|s| s.slots(level)) {
+            Some(slots) if level > 0 && slots > 0 => slots.to_string(),
+            _ => "—".to_string(),
+        };
+        let _ = writeln!(out, "<tr><td>{}</td><td>{}</td><td>{}</td></tr>", label, slots, escape(&list.join(", ")));
+    }
+    out.push_str("</table>\n");
+}
+
+fn features(out: &mut String, npc: &NPC) {
+    if npc.features.is_empty() {
+        return;
+    }
+    out.push_str("<h2>Features</h2>\n");
+    for feature in &npc.features {
+        let _ = writeln!(
+            out,
+            "<p class=\"feature\"><strong><em>{}.</em></strong> {}</p>",
+            escape(&feature.name),
+            escape(&feature.description)
+        );
+    }
+}
+
+fn personality(out: &mut String, npc: &NPC) {
+    let p = &npc.personality;
+    let a = &npc.appearance;
+    out.push_str("<div class=\"columns\">\n<div>\n<h2>Personality</h2>\n<ul>\n");
+    if !p.traits.is_empty() {
+        let _ = writeln!(out, "<li><strong>Traits:</strong> {}</li>", escape(&p.traits.join("; ")));
+    }
+    let _ = writeln!(out, "<li><strong>Ideals:</strong> {}</li>", escape(&p.ideals));
+    let _ = writeln!(out, "<li><strong>Bonds:</strong> {}</li>", escape(&p.bonds));
+    let _ = writeln!(out, "<li><strong>Flaws:</strong> {}</li>", escape(&p.flaws));
+    out.push_str("</ul>\n</div>\n<div>\n<h2>Appearance</h2>\n<ul>\n");
+    let _ = writeln!(
+        out,
+        "<li>Age {} · {} · {}</li>\n<li>{} eyes · {} hair · {} skin</li>",
+        a.age,
+        escape(&a.height),
+        escape(&a.weight),
+        escape(&a.eyes),
+        escape(&a.hair),
+        escape(&a.skin)
+    );
+    for feature in &a.distinguishing_features {
+        let _ = writeln!(out, "<li>{}</li>", escape(feature));
+    }
+    out.push_str("</ul>\n</div>\n</div>\n");
+}
+
+fn backstory(out: &mut String, npc: &NPC) {
+    out.push_str("<h2>Backstory</h2>\n");
+    for paragraph in paragraphs(&npc.backstory) {
+        let _ = writeln!(out, "<p>{}</p>", escape(paragraph));
+    }
+}
+
+/// One row of the batch index page.
+pub struct IndexEntry {
+    pub name: String,
+    pub summary: String,
+    /// Sheet location relative to the index page
+    pub href: String,
+}
+
+impl IndexEntry {
+    pub fn new(npc: &NPC, href: String) -> Self {
+        IndexEntry {
+            name: npc.name.clone(),
+            summary: format!("{} {} · {}", npc.race, npc.class_summary(), npc.role),
+            href,
+        }
+    }
+}
+
+/// Index page linking every sheet of a batch.
+pub fn render_index(entries: &[IndexEntry]) -> String {
+    let mut body = String::from("<div class=\"sheet\">\n<h1>NPC Index</h1>\n");
+    let _ = writeln!(body, "<p class=\"subtitle\">{} character sheet(s)</p>", entries.len());
+    body.push_str("<table>\n<tr><th>Name</th><th>Character</th></tr>\n");
+    for entry in entries {
+        let _ = writeln!(
+            body,
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td></tr>",
+            escape(&entry.href),
+            escape(&entry.name),
+            escape(&entry.summary)
+        );
+    }
+    body.push_str("</table>\n</div>\n");
+    page("NPC Index", &body)
+}
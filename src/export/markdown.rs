@@ -0,0 +1,247 @@
+//! Markdown stat block for pasting into session notes.
+
+use std::fmt::Write;
+
+use super::{paragraphs, signed, spell_level_label};
+use crate::{NPC, ProficiencyLevel};
+use crate::types::{Ability, Size};
+
+/// How names and free text are written: as-is here, wiki-linked in the Obsidian export.
+pub trait Links {
+    /// A spell or item name
+    fn name(&self, name: &str) -> String;
+    /// Backstory, personality and other prose that may mention people and places
+    fn prose(&self, text: &str) -> String;
+}
+
+struct Plain;
+
+impl Links for Plain {
+    fn name(&self, name: &str) -> String {
+        name.to_string()
+    }
+
+    fn prose(&self, text: &str) -> String {
+        text.to_string()
+    }
+}
+
+pub fn render(npc: &NPC) -> String {
+    render_with(npc, &Plain)
+}
+
+pub fn render_with(npc: &NPC, links: &dyn Links) -> String {
+    let mut out = String::new();
+    header(&mut out, npc);
+    stat_block(&mut out, npc);
+    attacks(&mut out, npc, links);
+    spellcasting(&mut out, npc, links);
+    features(&mut out, npc);
+    equipment(&mut out, npc, links);
+    personality(&mut out, npc, links);
+    appearance(&mut out, npc, links);
+    backstory(&mut out, npc, links);
+    out
+}
+
+fn header(out: &mut String, npc: &NPC) {
+    let _ = writeln!(out, "# {}\n", npc.name);
+    let size = npc.size.unwrap_or_else(|| Size::for_race(&npc.race));
+    let _ = writeln!(out, "*{} {}, {}*\n", size, npc.race, npc.alignment);
+    let mut class_line = format!("**{}**", npc.class_summary());
+    if let Some(subclass) = &npc.subclass {
+        let _ = write!(class_line, " ({})", subclass);
+    }
+    let _ = writeln!(
+        out,
+        "{} · Level {} · {} · {} background · {} fighter\n",
+        class_line, npc.level, npc.role, npc.background, npc.fighting_preference
+    );
+    out.push_str("---\n\n");
+}
+
+fn stat_block(out: &mut String, npc: &NPC) {
+    let hp = &npc.hit_points;
+    let _ = writeln!(out, "- **Armor Class** {}", npc.armor_class);
+    let _ = writeln!(out, "- **Hit Points** {} ({})", hp.max, hp.hit_dice);
+    let _ = writeln!(out, "- **Speed** {} ft.", npc.speed);
+    let _ = writeln!(out, "- **Initiative** {}", signed(npc.initiative));
+    let _ = writeln!(out, "- **Proficiency Bonus** {}\n", signed(npc.proficiency_bonus));
+
+    let header: Vec<&str> = Ability::ALL.iter().map(|a| a.abbr()).collect();
+    let _ = writeln!(out, "| {} |", header.join(" | "));
+    let _ = writeln!(out, "|{}", ":---:|".repeat(header.len()));
+    let scores: Vec<String> = Ability::ALL
+        .iter()
+        .map(|&a| format!("{} ({})", npc.ability_score(a), signed(npc.ability_mod(a))))
+        .collect();
+    let _ = writeln!(out, "| {} |\n", scores.join(" | "));
+
+    let saves: Vec<String> = npc
+        .saving_throws
+        .iter()
+        .filter(|s| s.proficient)
+        .map(|s| format!("{} {}", s.ability.abbr(), signed(s.modifier)))
+        .collect();
+    if !saves.is_empty() {
+        let _ = writeln!(out, "- **Saving Throws** {}", saves.join(", "));
+    }
+
+    let skills: Vec<String> = npc
+        .skills
+        .iter()
+        .filter(|s| s.proficiency.is_proficient())
+        .map(|s| {
+            let expertise = if s.proficiency == ProficiencyLevel::Expertise { " (expertise)" } else { "" };
+            format!("{} {}{}", s.name, signed(s.modifier), expertise)
+        })
+        .collect();
+    if !skills.is_empty() {
+        let _ = writeln!(out, "- **Skills** {}", skills.join(", "));
+    }
+
+    let _ = writeln!(
+        out,
+        "- **Senses** passive Perception {}, passive Insight {}, passive Investigation {}",
+        npc.passive_perception, npc.passive_insight, npc.passive_investigation
+    );
+    if !npc.languages.is_empty() {
+        let _ = writeln!(out, "- **Languages** {}", npc.languages.join(", "));
+    }
+    if !npc.tool_proficiencies.is_empty() {
+        let _ = writeln!(out, "- **Tools** {}", npc.tool_proficiencies.join(", "));
+    }
+    out.push('\n');
+}
+
+/// Text for a table cell: a bare `|` would end the cell, so it is escaped (Obsidian reads
+/// `\|` inside wiki-links in tables too).
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+fn attacks(out: &mut String, npc: &NPC, links: &dyn Links) {
+    if npc.attacks.is_empty() {
+        return;
+    }
+    out.push_str("### Attacks\n\n");
+    out.push_str("| Attack | To Hit | Damage | Range | Properties |\n");
+    out.push_str("|---|:---:|---|---|---|\n");
+    for attack in &npc.attacks {
+        let carried = npc.equipment.weapons.iter().any(|w| w.eq_ignore_ascii_case(&attack.name));
+        let name = if carried || npc.knows_spell(&attack.name) { links.name(&attack.name) } else { attack.name.clone() };
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} {} | {} | {} |",
+            cell(&name),
+            signed(attack.attack_bonus),
+            cell(&attack.damage),
+            cell(&attack.damage_type.to_string()),
+            cell(attack.range.as_deref().unwrap_or("—")),
+            if attack.properties.is_empty() { "—".to_string() } else { cell(&attack.properties.join(", ")) }
+        );
+    }
+    out.push('\n');
+}
+
+fn spellcasting(out: &mut String, npc: &NPC, links: &dyn Links) {
+    let Some(spells) = &npc.spells else { return };
+    if spells.spells_known.is_empty() {
+        return;
+    }
+    out.push_str("### Spellcasting\n\n");
+    if let Some(ability) = spells.spellcasting_ability {
+        let _ = writeln!(
+            out,
+            "**Spellcasting Ability** {} (spell save DC {}, {} to hit with spell attacks)\n",
+            ability,
+            spells.spell_save_dc,
+            signed(spells.spell_attack_bonus)
+        );
+    }
+    for (level, list) in spells.spells_known.by_level() {
+        if list.is_empty() {
+            continue;
+        }
+        let label = spell_level_label(spells, level);
+        let names: Vec<String> = list.iter().map(|s| format!("*{}*", links.name(s))).collect();
+        let _ = writeln!(out, "- **{}:** {}", label, names.join(", "));
+    }
+    out.push('\n');
+}
+
+fn features(out: &mut String, npc: &NPC) {
+    if npc.features.is_empty() {
+        return;
+    }
+    out.push_str("### Features\n\n");
+    for feature in &npc.features {
+        let _ = writeln!(out, "***{}.*** {}\n", feature.name, feature.description);
+    }
+}
+
+fn equipment(out: &mut String, npc: &NPC, links: &dyn Links) {
+    let eq = &npc.equipment;
+    out.push_str("### Equipment\n\n");
+    for (label, items) in [("Armor", &eq.armor), ("Weapons", &eq.weapons), ("Gear", &eq.gear)] {
+        if !items.is_empty() {
+            let items: Vec<String> = items.iter().map(|i| links.name(i)).collect();
+            let _ = writeln!(out, "- **{}** {}", label, items.join(", "));
+        }
+    }
+    let mut treasure = format!("{} gp", eq.treasure.gold);
+    if !eq.treasure.items.is_empty() {
+        let items: Vec<String> = eq.treasure.items.iter().map(|i| links.name(i)).collect();
+        let _ = write!(treasure, "; {}", items.join(", "));
+    }
+    let _ = writeln!(out, "- **Treasure** {}\n", treasure);
+}
+
+fn personality(out: &mut String, npc: &NPC, links: &dyn Links) {
+    let p = &npc.personality;
+    out.push_str("## Personality\n\n");
+    if !p.traits.is_empty() {
+        let _ = writeln!(out, "- **Traits:** {}", links.prose(&p.traits.join("; ")));
+    }
+    let _ = writeln!(out, "- **Ideals:** {}", links.prose(&p.ideals));
+    let _ = writeln!(out, "- **Bonds:** {}", links.prose(&p.bonds));
+    let _ = writeln!(out, "- **Flaws:** {}\n", links.prose(&p.flaws));
+}
+
+fn appearance(out: &mut String, npc: &NPC, links: &dyn Links) {
+    let a = &npc.appearance;
+    out.push_str("## Appearance\n\n");
+    let _ = writeln!(
+        out,
+        "Age {} · {} · {} · {} eyes · {} hair · {} skin\n",
+        a.age, a.height, a.weight, a.eyes, a.hair, a.skin
+    );
+    for feature in &a.distinguishing_features {
+        let _ = writeln!(out, "- {}", links.prose(feature));
+    }
+    if !a.distinguishing_features.is_empty() {
+        out.push('\n');
+    }
+}
+
+fn backstory(out: &mut String, npc: &NPC, links: &dyn Links) {
+    out.push_str("## Backstory\n\n");
+    for paragraph in paragraphs(&npc.backstory) {
+        let _ = writeln!(out, "{}\n", links.prose(paragraph));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipes_in_attack_cells_are_escaped() {
+        let mut npc: NPC = serde_json::from_str(include_str!("../../tests/fixtures/seraphine.json")).unwrap();
+        npc.attacks[0].name = "Rapier | Dagger".to_string();
+        npc.attacks[0].properties = vec!["Finesse|Light".to_string()];
+        let out = render(&npc);
+        let row = out.lines().find(|l| l.starts_with("| Rapier")).unwrap();
+        assert_eq!(row, "| Rapier \\| Dagger | +5 | 1d8+2 piercing | Melee | Finesse\\|Light |");
+    }
+}
@@ -0,0 +1,324 @@
+//! Rendering NPCs into formats other than NPCForge's own JSON.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use std::path::{Path, PathBuf};
+
+use crate::{Attack, NPC, Spellcasting, output, schema};
+
+pub mod fantasy_grounds;
+pub mod fivetools;
+pub mod foundry;
+pub mod homebrewery;
+pub mod html;
+pub mod markdown;
+pub mod obsidian;
+pub mod pdf;
+pub mod roll20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Markdown stat block followed by personality, appearance and backstory
+    #[value(alias = "md")]
+    Markdown,
+    /// Homebrewery V3 markup: `{{monster,frame}}` stat block and a lore page (also works in GM Binder)
+    #[value(alias = "hb")]
+    Homebrewery,
+    /// Self-contained HTML character sheet (inline CSS, no external assets)
+    Html,
+    /// Printable PDF character sheet (one to two US Letter pages)
+    Pdf,
+    /// Compact half-page PDF card for the DM screen
+    PdfCard,
+    /// Foundry VTT actor JSON for the dnd5e system
+    Foundry,
+    /// Fantasy Grounds NPC record (XML, as in a module's db.xml)
+    #[value(alias = "fg")]
+    FantasyGrounds,
+    /// Roll20 character JSON for the 5e OGL sheet
+    Roll20,
+    /// 5etools homebrew file with the NPC as a `monster` entry
+    #[value(name = "5etools")]
+    Fivetools,
+    /// Obsidian note with YAML frontmatter and wiki-links, plus an index note for the vault
+    Obsidian,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Homebrewery => "homebrewery.md",
+            Format::Html => "html",
+            Format::Pdf => "pdf",
+            Format::PdfCard => "card.pdf",
+            Format::Foundry => "foundry.json",
+            Format::FantasyGrounds => "fg.xml",
+            Format::Roll20 => "roll20.json",
+            Format::Fivetools => "5etools.json",
+            Format::Obsidian => "md",
+        }
+    }
+
+    pub fn render(self, npc: &NPC) -> Vec<u8> {
+        match self {
+            Format::Markdown => markdown::render(npc).into_bytes(),
+            Format::Homebrewery => homebrewery::render(npc).into_bytes(),
+            Format::Html => html::render(npc).into_bytes(),
+            Format::Pdf => pdf::render(npc).0,
+            Format::PdfCard => pdf::render_card(npc).0,
+            Format::Foundry => foundry::render(npc).into_bytes(),
+            Format::FantasyGrounds => fantasy_grounds::render(npc).into_bytes(),
+            Format::Roll20 => roll20::render(npc).into_bytes(),
+            Format::Fivetools => fivetools::render(npc).into_bytes(),
+            Format::Obsidian => obsidian::render(npc).into_bytes(),
+        }
+    }
+
+    /// Render `npc`, with a warning when the format had to leave part of it out.
+    pub fn render_checked(self, npc: &NPC) -> (Vec<u8>, Option<String>) {
+        let (bytes, cut_off) = match self {
+            Format::Pdf => pdf::render(npc),
+            Format::PdfCard => pdf::render_card(npc),
+            _ => (self.render(npc), false),
+        };
+        let warning = cut_off.then(|| {
+            format!("too long for the page even at the smallest font size; the rest is cut off (marked \"{}\")", pdf::CUT_OFF_MARKER)
+        });
+        (bytes, warning)
+    }
+}
+
+/// Path for an export of the NPC saved at `json_path`: same stem, format extension, either
+/// beside the JSON or in `out_dir`.
+pub fn output_path(json_path: &Path, format: Format, out_dir: Option<&Path>) -> PathBuf {
+    let file_name = json_path.with_extension(format.extension());
+    let file_name = file_name.file_name().unwrap_or_default();
+    match out_dir {
+        Some(dir) => dir.join(file_name),
+        None => json_path.with_file_name(file_name),
+    }
+}
+
+/// Write `npc` in `format` next to (or on behalf of) the JSON file at `json_path`. Returns the
+/// path and a warning if the export had to leave something out.
+pub fn write_export(
+    npc: &NPC,
+    format: Format,
+    json_path: &Path,
+    out_dir: Option<&Path>,
+) -> Result<(PathBuf, Option<String>)> {
+    let path = output_path(json_path, format, out_dir);
+    let (bytes, warning) = format.render_checked(npc);
+    output::write_atomic(&path, bytes)?;
+    Ok((path, warning))
+}
+
+/// Write `index.html` into `dir`, linking the sheets of a batch.
+pub fn write_index(entries: &[html::IndexEntry], dir: &Path) -> Result<PathBuf> {
+    let path = dir.join("index.html");
+    output::write_atomic(&path, html::render_index(entries))?;
+    Ok(path)
+}
+
+/// `npcforge export`: convert saved NPC files, reporting each one and carrying on past failures.
+pub fn export_files(files: &[PathBuf], format: Format, out_dir: Option<&Path>) -> Result<()> {
+    println!("=== NPCForge - Exporting {} file(s) ===\n", files.len());
+
+    let mut exported = 0;
+    let mut failed = 0;
+    let mut warned = 0;
+    let mut index = Vec::new();
+    let loaded: Vec<Result<NPC>> = files.iter().map(|file| schema::load_npc_file(file).map(|(npc, _)| npc)).collect();
+    // Obsidian notes go into one vault folder and link to each other, so they are written together
+    let vault = match format {
+        Format::Obsidian => {
+            let batch: Vec<&NPC> = loaded.iter().filter_map(|npc| npc.as_ref().ok()).collect();
+            Some(obsidian::Vault::open(out_dir.unwrap_or(Path::new(".")), &batch)?)
+        }
+        _ => None,
+    };
+    for (file, npc) in files.iter().zip(loaded) {
+        let result = npc.and_then(|npc| {
+            let path = match &vault {
+                Some(vault) => vault.write_note(&npc).map(|path| (path, None)),
+                None => write_export(&npc, format, file, out_dir),
+            };
+            path.map(|(path, warning)| (npc, path, warning))
+        });
+        match result {
+            Ok((npc, path, warning)) => {
+                println!("✓ {} -> {}", file.display(), path.display());
+                if let Some(warning) = warning {
+                    println!("  ⚠ {}: {}", path.display(), warning);
+                    warned += 1;
+                }
+                // Links are relative to the index, which goes in the output directory if there is one
+                let href = match out_dir {
+                    Some(_) => path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                    None => path.display().to_string(),
+                };
+                index.push(html::IndexEntry::new(&npc, href));
+                exported += 1;
+            }
+            Err(e) => {
+                eprintln!("✗ {}: {:#}", file.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    if format == Format::Html && index.len() > 1 {
+        let path = write_index(&index, out_dir.unwrap_or(Path::new(".")))?;
+        println!("✓ Index: {}", path.display());
+    }
+    if let Some(vault) = vault
+        && exported > 0
+    {
+        let path = vault.write_index()?;
+        println!("✓ Index: {}", path.display());
+    }
+
+    println!("\n=== Summary ===");
+    println!("Exported: {}", exported);
+    if warned > 0 {
+        println!("Cut off: {}", warned);
+    }
+    if failed > 0 {
+        println!("Failed: {}", failed);
+    }
+    Ok(())
+}
+
+/// Normal and long range in feet from a range like "20/60", "80/320 ft." or "120 ft.".
+fn distances(range: &str) -> Option<(u16, Option<u16>)> {
+    let mut numbers = range
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|n| !n.is_empty())
+        .filter_map(|n| n.parse().ok());
+    let normal = numbers.next()?;
+    Some((normal, numbers.next()))
+}
+
+/// Melee ranges mention melee, reach or touch, or are a single distance of 10 ft. or less
+/// (reach weapons); anything farther is ranged. No range at all counts as melee.
+pub fn is_melee_range(range: Option<&str>) -> bool {
+    let Some(range) = range.map(str::to_lowercase) else {
+        return true;
+    };
+    if ["melee", "reach", "touch"].iter().any(|m| range.contains(m)) {
+        return true;
+    }
+    matches!(distances(&range), Some((normal, None)) if normal <= 10)
+}
+
+pub fn is_melee(attack: &Attack) -> bool {
+    is_melee_range(attack.range.as_deref())
+}
+
+/// Normal and long range in feet from ranges like "20/60", "80/320 ft." or "120 ft.".
+/// `None` for melee ranges.
+pub fn range_feet(attack: &Attack) -> Option<(u16, Option<u16>)> {
+    if is_melee(attack) {
+        return None;
+    }
+    distances(attack.range.as_deref()?)
+}
+
+/// Average of a damage expression like "1d8+3" or "2d6 + 1d4 - 1", rounded down as in stat
+/// blocks. `None` if it is anything other than dice and flat numbers.
+pub fn average_damage(expression: &str) -> Option<i32> {
+    let expression: String = expression.chars().filter(|c| !c.is_whitespace()).collect();
+    if expression.is_empty() {
+        return None;
+    }
+    // Twice the average, to keep halves exact until the end
+    let mut doubled = 0;
+    for term in expression.replace('-', "+-").split('+').filter(|t| !t.is_empty()) {
+        let (sign, term) = match term.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, term),
+        };
+        let value = match term.split_once(['d', 'D']) {
+            Some((count, sides)) => {
+                let count: i32 = if count.is_empty() { 1 } else { count.parse().ok()? };
+                let sides: i32 = sides.parse().ok()?;
+                count * (sides + 1)
+            }
+            None => 2 * term.parse::<i32>().ok()?,
+        };
+        doubled += sign * value;
+    }
+    Some(doubled.div_euclid(2).max(1))
+}
+
+/// Damage expression with spaced operators: "1d8+3" becomes "1d8 + 3".
+pub fn spaced_dice(expression: &str) -> String {
+    let compact: String = expression.chars().filter(|c| !c.is_whitespace()).collect();
+    compact.replace('+', " + ").replace('-', " - ")
+}
+
+/// Stat block introduction to a spell list: "Name is a 5th-level spellcaster. Their
+/// spellcasting ability is ... They have the following spells prepared:"
+pub fn spellcasting_intro(npc: &NPC, spells: &Spellcasting) -> String {
+    let mut text = format!("{} is a {}-level spellcaster.", npc.name, ordinal(npc.caster_level().max(1)));
+    if let Some(ability) = spells.spellcasting_ability {
+        text.push_str(&format!(
+            " Their spellcasting ability is {} (spell save DC {}, {} to hit with spell attacks).",
+            ability,
+            spells.spell_save_dc,
+            signed(spells.spell_attack_bonus)
+        ));
+    }
+    text.push_str(" They have the following spells prepared:");
+    text
+}
+
+/// "Cantrips (at will)", "1st level (4 slots)", or "3rd level" when the slots are unknown.
+pub fn spell_level_label(spells: &Spellcasting, level: u8) -> String {
+    if level == 0 {
+        return "Cantrips (at will)".to_string();
+    }
+    match spells.spell_slots.as_ref().map(|s| s.slots(level)) {
+        Some(1) => format!("{} level (1 slot)", ordinal(level)),
+        Some(slots) if slots > 0 => format!("{} level ({} slots)", ordinal(level), slots),
+        _ => format!("{} level", ordinal(level)),
+    }
+}
+
+/// "+3", "-1", "+0"
+pub fn signed(n: i8) -> String {
+    format!("{:+}", n)
+}
+
+/// "1st", "2nd", "3rd", "4th"...
+pub fn ordinal(n: u8) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+/// Split the backstory into paragraphs, tolerating single newlines between them.
+pub fn paragraphs(text: &str) -> Vec<&str> {
+    text.split('\n').map(str::trim).filter(|p| !p.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_melee_range;
+
+    #[test]
+    fn melee_ranges_are_reach_distances_only() {
+        for melee in [None, Some("5 ft."), Some("10 ft. reach"), Some("Melee"), Some("Touch"), Some("melee or 20/60 ft.")] {
+            assert!(is_melee_range(melee), "{:?}", melee);
+        }
+        for ranged in ["15 ft.", "25 ft.", "150 ft.", "20/60", "80/320 ft.", "Self (30-foot cone)"] {
+            assert!(!is_melee_range(Some(ranged)), "{:?}", ranged);
+        }
+    }
+}
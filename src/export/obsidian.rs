@@ -0,0 +1,404 @@
+//! Obsidian vault notes: YAML frontmatter, the Markdown stat block with wiki-links, and an
+//! index note listing every NPC in the vault.
+//!
+//! The generated part of a note sits between two HTML comments, so exporting an NPC again
+//! replaces that part and keeps whatever was written around it, along with frontmatter keys
+//! and tags NPCForge doesn't manage. A note is found by its title anywhere in the vault, so
+//! notes that were moved into subfolders are updated where they are instead of duplicated.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::markdown::{self, Links};
+use crate::{NPC, output};
+
+const BLOCK_START: &str = "<!-- npcforge:start -->";
+const BLOCK_END: &str = "<!-- npcforge:end -->";
+const INDEX_TITLE: &str = "NPC Index";
+/// Heading for the DM's own notes under a new NPC note's generated block
+const NOTES_TAIL: &str = "\n## Notes\n\n";
+
+/// Frontmatter keys NPCForge writes (and replaces on update); other keys are left alone.
+const MANAGED_KEYS: [&str; 6] = ["species", "class", "level", "alignment", "role", "tags"];
+
+/// Words that put a place name after them: "from Baldur's Gate", "in the Underdark".
+const PLACE_PREPOSITIONS: [&str; 9] = ["in", "from", "at", "near", "of", "to", "across", "beyond", "outside"];
+
+/// Note title for an NPC: the name without the characters Obsidian doesn't allow in links.
+pub fn note_title(name: &str) -> String {
+    let title: String = name.chars().filter(|c| !"*\"\\/<>:|?#^[]".contains(*c)).collect();
+    title.trim().to_string()
+}
+
+/// Tag slug: lowercase, spaces as dashes, no punctuation ("Half-Elf" -> "half-elf").
+fn tag(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .replace(' ', "-")
+        .chars()
+        .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+        .collect()
+}
+
+/// YAML scalar, quoted only when a plain scalar would be misread.
+fn yaml(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value.trim() == value
+        && !value.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !["true", "false", "yes", "no", "null", "~"].contains(&value.to_lowercase().as_str())
+        && value.parse::<f64>().is_err();
+    if plain { value.to_string() } else { format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")) }
+}
+
+fn unyaml(value: &str) -> String {
+    let value = value.trim();
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => value.trim_matches('\'').to_string(),
+    }
+}
+
+/// Frontmatter as (key, lines) pairs in file order; a key's lines include its list items.
+type Frontmatter = Vec<(String, Vec<String>)>;
+
+/// Split a note into its frontmatter (without the `---` fences) and body.
+fn split_note(text: &str) -> (Option<&str>, &str) {
+    let Some(rest) = text.strip_prefix("---\n") else {
+        return (None, text);
+    };
+    if let Some(end) = rest.find("\n---\n") {
+        return (Some(&rest[..end]), &rest[end + 5..]);
+    }
+    match rest.strip_suffix("\n---") {
+        Some(frontmatter) => (Some(frontmatter), ""),
+        None => (None, text),
+    }
+}
+
+fn parse_frontmatter(text: &str) -> Frontmatter {
+    let mut entries: Frontmatter = Vec::new();
+    for line in text.lines() {
+        let top_level = !line.starts_with([' ', '\t', '-']) && line.contains(':');
+        match entries.last_mut() {
+            Some((_, lines)) if !top_level => lines.push(line.to_string()),
+            _ => {
+                let key = line.split(':').next().unwrap_or_default().trim().to_string();
+                entries.push((key, vec![line.to_string()]));
+            }
+        }
+    }
+    entries
+}
+
+/// Tags in either YAML form: a block list under `tags:` or `tags: [a, b]` / `tags: a`.
+fn frontmatter_tags(lines: &[String]) -> Vec<String> {
+    let inline = lines.first().and_then(|l| l.split_once(':')).map(|(_, v)| v.trim()).unwrap_or_default();
+    let inline = inline.trim_start_matches('[').trim_end_matches(']');
+    inline
+        .split(',')
+        .chain(lines.iter().skip(1).filter_map(|l| l.trim().strip_prefix('-')))
+        .map(unyaml)
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Frontmatter value of a plain `key: value` line.
+fn frontmatter_value(frontmatter: &Frontmatter, key: &str) -> String {
+    frontmatter
+        .iter()
+        .find(|(k, _)| k == key)
+        .and_then(|(_, lines)| lines.first())
+        .and_then(|l| l.split_once(':'))
+        .map(|(_, v)| unyaml(v))
+        .unwrap_or_default()
+}
+
+/// Tags NPCForge generates; anything else in an existing note was added by hand.
+fn is_generated_tag(tag: &str) -> bool {
+    tag == "npc" || ["species/", "class/", "role/"].iter().any(|p| tag.starts_with(p))
+}
+
+/// Write `ours` over the existing frontmatter: managed keys are replaced, the rest kept after
+/// them, and hand-added tags carried over.
+fn merge_frontmatter(existing: Option<&str>, ours: &[(&str, String)], mut tags: Vec<String>) -> String {
+    let existing = existing.map(parse_frontmatter).unwrap_or_default();
+    for (key, lines) in &existing {
+        if key == "tags" {
+            for t in frontmatter_tags(lines) {
+                if !is_generated_tag(&t) && !tags.contains(&t) {
+                    tags.push(t);
+                }
+            }
+        }
+    }
+
+    let mut out = String::from("---\n");
+    for (key, value) in ours {
+        out.push_str(&format!("{}: {}\n", key, value));
+    }
+    out.push_str("tags:\n");
+    for t in &tags {
+        out.push_str(&format!("  - {}\n", yaml(t)));
+    }
+    for (key, lines) in &existing {
+        if MANAGED_KEYS.contains(&key.as_str()) || ours.iter().any(|(k, _)| k == key) {
+            continue;
+        }
+        for line in lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out.push_str("---\n");
+    out
+}
+
+/// Replace the generated block in `body`, or add it if the note doesn't have one yet. New notes
+/// get `tail` after the block.
+fn merge_body(body: Option<&str>, block: &str, tail: &str) -> String {
+    let block = format!("{}\n{}{}\n", BLOCK_START, block, BLOCK_END);
+    let Some(body) = body.filter(|b| !b.trim().is_empty()) else {
+        return format!("{}{}", block, tail);
+    };
+    match (body.find(BLOCK_START), body.find(BLOCK_END)) {
+        (Some(start), Some(end)) if start < end => {
+            let after = &body[end + BLOCK_END.len()..];
+            let after = after.strip_prefix('\n').unwrap_or(after);
+            format!("{}{}{}", &body[..start], block, after)
+        }
+        _ => format!("{}\n\n{}", body.trim_end(), block),
+    }
+}
+
+/// Place names after a preposition: "streets of Baldur's Gate" gives "Baldur's Gate".
+fn place_names(text: &str) -> Vec<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut names = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        if !PLACE_PREPOSITIONS.contains(&word.to_lowercase().as_str()) {
+            continue;
+        }
+        // "Book of Shadows", "House of Lords": the preposition is inside a longer name
+        if i > 0 && words[i - 1].starts_with(|c: char| c.is_uppercase()) {
+            continue;
+        }
+        let mut j = i + 1;
+        if words.get(j).is_some_and(|w| *w == "the") {
+            j += 1;
+        }
+        let mut parts: Vec<&str> = Vec::new();
+        while let Some(word) = words.get(j) {
+            let bare = word.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '\'');
+            let capitalized = bare.starts_with(|c: char| c.is_uppercase());
+            let joiner = !parts.is_empty()
+                && (bare == "of" || bare == "the")
+                && words.get(j + 1).is_some_and(|w| w.starts_with(|c: char| c.is_uppercase()));
+            if !capitalized && !joiner {
+                break;
+            }
+            parts.push(bare);
+            j += 1;
+            if bare.len() < word.len() {
+                // Punctuation ends the name
+                break;
+            }
+        }
+        let name = parts.join(" ");
+        let name = name.strip_suffix("'s").unwrap_or(&name).trim_end_matches('\'');
+        if !name.is_empty() {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// `text` with the first standalone, not yet linked mention of `term` wiki-linked.
+fn link_first(text: &str, term: &str) -> Option<String> {
+    let mut from = 0;
+    while let Some(found) = text[from..].find(term) {
+        let start = from + found;
+        let end = start + term.len();
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        let standalone = !before.is_some_and(|c| c.is_alphanumeric() || c == '[')
+            && !after.is_some_and(|c| c.is_alphanumeric() || c == ']');
+        let in_link = text[..start].matches("[[").count() > text[..start].matches("]]").count();
+        if standalone && !in_link {
+            return Some(format!("{}[[{}]]{}", &text[..start], term, &text[end..]));
+        }
+        from = end;
+    }
+    None
+}
+
+/// Wiki-linking for one NPC's note.
+struct NoteLinks<'a> {
+    /// Other notes in the vault and NPCs in the batch, longest first
+    titles: &'a [String],
+    /// Words that look like places but aren't: the NPC's own names and languages
+    not_places: Vec<String>,
+}
+
+impl Links for NoteLinks<'_> {
+    fn name(&self, name: &str) -> String {
+        format!("[[{}]]", note_title(name))
+    }
+
+    fn prose(&self, text: &str) -> String {
+        let mut terms: Vec<String> = self.titles.iter().filter(|t| text.contains(t.as_str())).cloned().collect();
+        for place in place_names(text) {
+            if !self.not_places.contains(&place) && !terms.contains(&place) {
+                terms.push(place);
+            }
+        }
+        terms.sort_by_key(|t| std::cmp::Reverse(t.len()));
+        let mut text = text.to_string();
+        for term in terms {
+            if let Some(linked) = link_first(&text, &term) {
+                text = linked;
+            }
+        }
+        text
+    }
+}
+
+fn frontmatter(npc: &NPC) -> (Vec<(&'static str, String)>, Vec<String>) {
+    let fields = vec![
+        ("species", yaml(&npc.race)),
+        ("class", yaml(&npc.class_summary())),
+        ("level", npc.level.to_string()),
+        ("alignment", yaml(&npc.alignment.to_string())),
+        ("role", yaml(&npc.role)),
+    ];
+    let mut tags = vec!["npc".to_string(), format!("species/{}", tag(&npc.race))];
+    for class in &npc.class_name.0 {
+        tags.push(format!("class/{}", tag(class.name())));
+    }
+    if !npc.role.trim().is_empty() {
+        tags.push(format!("role/{}", tag(&npc.role)));
+    }
+    (fields, tags)
+}
+
+/// A fresh note for `npc`, linking only its own spells and items.
+pub fn render(npc: &NPC) -> String {
+    let (fields, tags) = frontmatter(npc);
+    let links = NoteLinks { titles: &[], not_places: Vec::new() };
+    let block = markdown::render_with(npc, &links);
+    format!("{}{}", merge_frontmatter(None, &fields, tags), merge_body(None, &block, NOTES_TAIL))
+}
+
+/// The notes of a vault folder, by title.
+pub struct Vault {
+    dir: PathBuf,
+    notes: BTreeMap<String, PathBuf>,
+    /// Every title that prose may link to, longest first
+    titles: Vec<String>,
+}
+
+fn scan(dir: &Path, notes: &mut BTreeMap<String, PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir).context(format!("Failed to read directory {}", dir.display()))? {
+        let path = entry?.path();
+        let hidden = path.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            scan(&path, notes)?;
+        } else if path.extension().is_some_and(|e| e == "md")
+            && let Some(title) = path.file_stem()
+        {
+            notes.entry(title.to_string_lossy().into_owned()).or_insert(path);
+        }
+    }
+    Ok(())
+}
+
+impl Vault {
+    /// Open (creating if needed) the vault folder `dir`. The `batch` NPCs are linkable from
+    /// each other's notes even before their own notes exist.
+    pub fn open(dir: &Path, batch: &[&NPC]) -> Result<Vault> {
+        fs::create_dir_all(dir).context(format!("Failed to create directory {}", dir.display()))?;
+        let mut notes = BTreeMap::new();
+        scan(dir, &mut notes)?;
+        let mut titles: Vec<String> = notes.keys().cloned().collect();
+        titles.extend(batch.iter().map(|npc| note_title(&npc.name)));
+        // Short or lowercase note names ("todo", "Inn") would link ordinary words
+        titles.retain(|t| t.len() >= 4 && t.chars().any(char::is_uppercase) && t != INDEX_TITLE);
+        titles.sort_by_key(|t| std::cmp::Reverse(t.len()));
+        titles.dedup();
+        Ok(Vault { dir: dir.to_path_buf(), notes, titles })
+    }
+
+    /// Path of the note titled `title`: where it already is in the vault, or a new file in the folder.
+    fn note_path(&self, title: &str) -> PathBuf {
+        self.notes.get(title).cloned().unwrap_or_else(|| self.dir.join(format!("{}.md", title)))
+    }
+
+    /// Create or update the note for `npc`.
+    pub fn write_note(&self, npc: &NPC) -> Result<PathBuf> {
+        let title = note_title(&npc.name);
+        let mut not_places: Vec<String> = npc.name.split_whitespace().map(str::to_string).collect();
+        not_places.push(npc.name.clone());
+        not_places.extend(npc.languages.iter().cloned());
+        let titles: Vec<String> = self.titles.iter().filter(|t| **t != title).cloned().collect();
+        let links = NoteLinks { titles: &titles, not_places };
+
+        let (fields, tags) = frontmatter(npc);
+        let block = markdown::render_with(npc, &links);
+        let path = self.note_path(&title);
+        write_merged(&path, &fields, tags, &block, NOTES_TAIL)?;
+        Ok(path)
+    }
+
+    /// Create or update the index note: a table of every NPCForge note in the vault.
+    pub fn write_index(&self) -> Result<PathBuf> {
+        let mut notes = BTreeMap::new();
+        scan(&self.dir, &mut notes)?;
+        let mut rows = Vec::new();
+        for (title, path) in &notes {
+            let text = fs::read_to_string(path).unwrap_or_default();
+            let (frontmatter, body) = split_note(&text);
+            if title == INDEX_TITLE || !body.contains(BLOCK_START) {
+                continue;
+            }
+            let frontmatter = parse_frontmatter(frontmatter.unwrap_or_default());
+            let cells: Vec<String> = ["species", "class", "level", "role"]
+                .iter()
+                .map(|key| frontmatter_value(&frontmatter, key).replace('|', "\\|"))
+                .collect();
+            rows.push(format!("| [[{}]] | {} |", title, cells.join(" | ")));
+        }
+
+        let mut block = format!("# {}\n\n", INDEX_TITLE);
+        block.push_str(&format!("{} NPC(s)\n\n", rows.len()));
+        block.push_str("| NPC | Species | Class | Level | Role |\n|---|---|---|:---:|---|\n");
+        for row in rows {
+            block.push_str(&row);
+            block.push('\n');
+        }
+        let path = self.note_path(INDEX_TITLE);
+        write_merged(&path, &[], vec!["npc/index".to_string()], &block, "")?;
+        Ok(path)
+    }
+}
+
+fn write_merged(path: &Path, fields: &[(&str, String)], tags: Vec<String>, block: &str, tail: &str) -> Result<()> {
+    let existing = match path.exists() {
+        true => Some(fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?),
+        false => None,
+    };
+    let (frontmatter, body) = match &existing {
+        Some(text) => {
+            let (frontmatter, body) = split_note(text);
+            (frontmatter, Some(body))
+        }
+        None => (None, None),
+    };
+    let note = format!("{}{}", merge_frontmatter(frontmatter, fields, tags), merge_body(body, block, tail));
+    output::write_atomic(path, note)
+}
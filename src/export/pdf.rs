@@ -0,0 +1,650 @@
+//! Printable PDF, written directly with `pdf-writer` using the standard Helvetica fonts so
+//! no font files or external tools are needed.
+//!
+//! The full sheet is US Letter and fits on one or two pages; the DM card is a half-letter page
+//! (8.5" × 5.5") with just what is needed at the table. Both are laid out at the largest font
+//! size that fits. Anything that still does not fit is cut off, with a "continued in JSON"
+//! marker at the foot of the last page.
+
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+
+use super::{ordinal, paragraphs, signed};
+use crate::types::{Ability, Size};
+use crate::{NPC, ProficiencyLevel};
+
+const LETTER: (f32, f32) = (612.0, 792.0);
+const HALF_LETTER: (f32, f32) = (612.0, 396.0);
+
+/// Marker drawn under the last line when the content did not fit.
+pub const CUT_OFF_MARKER: &str = "\u{2026} (continued in JSON)";
+
+/// Full character sheet: stats, attacks, spells, features, personality and backstory. The
+/// flag is set when some of it had to be cut off.
+pub fn render(npc: &NPC) -> (Vec<u8>, bool) {
+    let sheet = fit(&[10.0, 9.5, 9.0, 8.5, 8.0, 7.5, 7.0], |size| {
+        let mut sheet = Sheet::new(LETTER, 42.0, size, 2);
+        full_sheet(&mut sheet, npc);
+        sheet
+    });
+    let cut_off = sheet.overflow;
+    (sheet.finish(&npc.name), cut_off)
+}
+
+/// Half-page DM card: combat numbers, attacks, spells and a one-line personality reminder.
+/// The flag is set when some of it had to be cut off.
+pub fn render_card(npc: &NPC) -> (Vec<u8>, bool) {
+    let sheet = fit(&[9.0, 8.5, 8.0, 7.5, 7.0, 6.5, 6.0], |size| {
+        let mut sheet = Sheet::new(HALF_LETTER, 24.0, size, 1);
+        card(&mut sheet, npc);
+        sheet
+    });
+    let cut_off = sheet.overflow;
+    (sheet.finish(&npc.name), cut_off)
+}
+
+/// Lay out at each font size in turn until nothing overflows, falling back to the smallest.
+fn fit(sizes: &[f32], layout: impl Fn(f32) -> Sheet) -> Sheet {
+    let mut sheet = layout(sizes[0]);
+    for &size in &sizes[1..] {
+        if !sheet.overflow {
+            break;
+        }
+        sheet = layout(size);
+    }
+    sheet
+}
+
+fn subtitle(npc: &NPC) -> (String, String) {
+    let size = npc.size.unwrap_or_else(|| Size::for_race(&npc.race));
+    let mut classes = npc.class_summary();
+    if let Some(subclass) = &npc.subclass {
+        classes.push_str(&format!(" ({})", subclass));
+    }
+    (
+        format!("{} {} · {} · {}", size, npc.race, classes, npc.alignment),
+        format!("{} · {} background · {} fighter", npc.role, npc.background, npc.fighting_preference),
+    )
+}
+
+fn combat_line(npc: &NPC) -> Vec<(Font, String)> {
+    let hp = &npc.hit_points;
+    vec![
+        (Font::Bold, "Armor Class ".to_string()),
+        (Font::Regular, format!("{}    ", npc.armor_class)),
+        (Font::Bold, "Hit Points ".to_string()),
+        (Font::Regular, format!("{} ({})    ", hp.max, hp.hit_dice)),
+        (Font::Bold, "Speed ".to_string()),
+        (Font::Regular, format!("{} ft.    ", npc.speed)),
+        (Font::Bold, "Initiative ".to_string()),
+        (Font::Regular, format!("{}    ", signed(npc.initiative))),
+        (Font::Bold, "Proficiency ".to_string()),
+        (Font::Regular, signed(npc.proficiency_bonus)),
+    ]
+}
+
+fn saves(npc: &NPC) -> String {
+    npc.saving_throws
+        .iter()
+        .filter(|s| s.proficient)
+        .map(|s| format!("{} {}", s.ability.abbr(), signed(s.modifier)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn skills(npc: &NPC) -> String {
+    npc.skills
+        .iter()
+        .filter(|s| s.proficiency.is_proficient())
+        .map(|s| {
+            let expertise = if s.proficiency == ProficiencyLevel::Expertise { " (E)" } else { "" };
+            format!("{} {}{}", s.name, signed(s.modifier), expertise)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn attack_text(npc: &NPC) -> Vec<(String, String)> {
+    npc.attacks
+        .iter()
+        .map(|a| {
+            let mut text = format!("{} to hit, {} {}", signed(a.attack_bonus), a.damage, a.damage_type);
+            if let Some(range) = &a.range {
+                text.push_str(&format!(", {}", range));
+            }
+            if !a.properties.is_empty() {
+                text.push_str(&format!(" ({})", a.properties.join(", ")));
+            }
+            (format!("{}. ", a.name), text)
+        })
+        .collect()
+}
+
+fn spell_lines(npc: &NPC) -> Option<(String, Vec<(String, String)>)> {
+    let spells = npc.spells.as_ref()?;
+    if spells.spells_known.is_empty() {
+        return None;
+    }
+    let summary = match spells.spellcasting_ability {
+        Some(ability) => format!(
+            "{} · save DC {} · {} to hit",
+            ability,
+            spells.spell_save_dc,
+            signed(spells.spell_attack_bonus)
+        ),
+        None => String::new(),
+    };
+    let lines = spells
+        .spells_known
+        .by_level()
+        .into_iter()
+        .filter(|(_, list)| !list.is_empty())
+        .map(|(level, list)| {
+            let label = if level == 0 {
+                "Cantrips: ".to_string()
+            } else {
+                match spells.spell_slots.as_ref().map(|s| s.slots(level)) {
+                    Some(slots) if slots > 0 => format!("{} ({} slots): ", ordinal(level), slots),
+                    _ => format!("{}: ", ordinal(level)),
+                }
+            };
+            (label, list.join(", "))
+        })
+        .collect();
+    Some((summary, lines))
+}
+
+fn full_sheet(sheet: &mut Sheet, npc: &NPC) {
+    let size = sheet.size;
+    let (line1, line2) = subtitle(npc);
+    sheet.paragraph(&[(Font::Bold, &npc.name)], size * 2.2);
+    sheet.paragraph(&[(Font::Italic, &line1)], size);
+    sheet.paragraph(&[(Font::Italic, &line2)], size);
+    sheet.rule();
+
+    let combat = combat_line(npc);
+    sheet.spans(&combat, size);
+    sheet.gap(size * 0.4);
+    sheet.ability_boxes(npc);
+    sheet.gap(size * 0.4);
+
+    let saves = saves(npc);
+    if !saves.is_empty() {
+        sheet.labeled("Saving Throws ", &saves);
+    }
+    let skills = skills(npc);
+    if !skills.is_empty() {
+        sheet.labeled("Skills ", &skills);
+    }
+    sheet.labeled(
+        "Senses ",
+        &format!(
+            "passive Perception {}, passive Insight {}, passive Investigation {}",
+            npc.passive_perception, npc.passive_insight, npc.passive_investigation
+        ),
+    );
+    if !npc.languages.is_empty() {
+        sheet.labeled("Languages ", &npc.languages.join(", "));
+    }
+    if !npc.tool_proficiencies.is_empty() {
+        sheet.labeled("Tools ", &npc.tool_proficiencies.join(", "));
+    }
+
+    if !npc.attacks.is_empty() {
+        sheet.heading("Attacks");
+        for (name, text) in attack_text(npc) {
+            sheet.labeled(&name, &text);
+        }
+    }
+
+    if let Some((summary, lines)) = spell_lines(npc) {
+        sheet.heading("Spellcasting");
+        if !summary.is_empty() {
+            sheet.paragraph(&[(Font::Italic, &summary)], size);
+        }
+        for (label, spells) in lines {
+            sheet.labeled(&label, &spells);
+        }
+    }
+
+    if !npc.features.is_empty() {
+        sheet.heading("Features");
+        for feature in &npc.features {
+            sheet.labeled(&format!("{}. ", feature.name), &feature.description);
+        }
+    }
+
+    let eq = &npc.equipment;
+    sheet.heading("Equipment");
+    for (label, items) in [("Armor ", &eq.armor), ("Weapons ", &eq.weapons), ("Gear ", &eq.gear)] {
+        if !items.is_empty() {
+            sheet.labeled(label, &items.join(", "));
+        }
+    }
+    let mut treasure = format!("{} gp", eq.treasure.gold);
+    if !eq.treasure.items.is_empty() {
+        treasure.push_str(&format!("; {}", eq.treasure.items.join(", ")));
+    }
+    sheet.labeled("Treasure ", &treasure);
+
+    let p = &npc.personality;
+    sheet.heading("Personality");
+    if !p.traits.is_empty() {
+        sheet.labeled("Traits ", &p.traits.join("; "));
+    }
+    sheet.labeled("Ideals ", &p.ideals);
+    sheet.labeled("Bonds ", &p.bonds);
+    sheet.labeled("Flaws ", &p.flaws);
+
+    let a = &npc.appearance;
+    sheet.heading("Appearance");
+    let mut appearance = format!(
+        "Age {}, {}, {}. {} eyes, {} hair, {} skin.",
+        a.age, a.height, a.weight, a.eyes, a.hair, a.skin
+    );
+    for feature in &a.distinguishing_features {
+        appearance.push_str(&format!(" {}.", feature.trim_end_matches('.')));
+    }
+    sheet.paragraph(&[(Font::Regular, &appearance)], size);
+
+    sheet.heading("Backstory");
+    for paragraph in paragraphs(&npc.backstory) {
+        sheet.paragraph(&[(Font::Regular, paragraph)], size);
+        sheet.gap(size * 0.3);
+    }
+}
+
+fn card(sheet: &mut Sheet, npc: &NPC) {
+    let size = sheet.size;
+    let (line1, _) = subtitle(npc);
+    sheet.paragraph(&[(Font::Bold, &npc.name)], size * 1.6);
+    sheet.paragraph(&[(Font::Italic, &line1)], size);
+    sheet.rule();
+
+    let combat = combat_line(npc);
+    sheet.spans(&combat, size);
+    let abilities: Vec<String> = Ability::ALL
+        .iter()
+        .map(|&a| format!("{} {} ({})", a.abbr(), npc.ability_score(a), signed(npc.ability_mod(a))))
+        .collect();
+    sheet.paragraph(&[(Font::Regular, &abilities.join("   "))], size);
+
+    let saves = saves(npc);
+    if !saves.is_empty() {
+        sheet.labeled("Saves ", &saves);
+    }
+    let skills = skills(npc);
+    if !skills.is_empty() {
+        sheet.labeled("Skills ", &skills);
+    }
+    sheet.labeled(
+        "Passive ",
+        &format!(
+            "Perception {}, Insight {}, Investigation {}",
+            npc.passive_perception, npc.passive_insight, npc.passive_investigation
+        ),
+    );
+    sheet.rule();
+
+    for (name, text) in attack_text(npc) {
+        sheet.labeled(&name, &text);
+    }
+    if let Some((summary, lines)) = spell_lines(npc) {
+        if !summary.is_empty() {
+            sheet.labeled("Spellcasting ", &summary);
+        }
+        for (label, spells) in lines {
+            sheet.labeled(&label, &spells);
+        }
+    }
+    if !npc.features.is_empty() {
+        let names: Vec<&str> = npc.features.iter().map(|f| f.name.as_str()).collect();
+        sheet.labeled("Features ", &names.join(", "));
+    }
+    sheet.rule();
+
+    let p = &npc.personality;
+    sheet.labeled("Ideal ", &p.ideals);
+    sheet.labeled("Bond ", &p.bonds);
+    sheet.labeled("Flaw ", &p.flaws);
+    if let Some(feature) = npc.appearance.distinguishing_features.first() {
+        sheet.labeled("Look ", feature);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Font {
+    Regular,
+    Bold,
+    Italic,
+}
+
+/// Glyph widths (1/1000 em) of the printable ASCII range, from the Adobe Helvetica metrics.
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556, 556, 556, 556, 556,
+    556, 556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833,
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, 333, 556, 556, 500, 556,
+    556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334,
+    260, 334, 584,
+];
+
+const HELVETICA_BOLD: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, 556, 556, 556, 556, 556, 556, 556,
+    556, 556, 556, 333, 333, 584, 584, 584, 611, 975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833,
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556, 333, 556, 611, 556, 611,
+    556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611, 611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389,
+    280, 389, 584,
+];
+
+impl Font {
+    fn resource(self) -> Name<'static> {
+        match self {
+            Font::Regular => Name(b"F1"),
+            Font::Bold => Name(b"F2"),
+            Font::Italic => Name(b"F3"),
+        }
+    }
+
+    fn char_width(self, c: char) -> f32 {
+        let table = if self == Font::Bold { &HELVETICA_BOLD } else { &HELVETICA };
+        match c as u32 {
+            code @ 32..=126 => table[(code - 32) as usize] as f32,
+            // Dashes, quotes and accented letters are all close to the width of a digit
+            _ => 556.0,
+        }
+    }
+
+    fn width(self, text: &str, size: f32) -> f32 {
+        text.chars().map(|c| self.char_width(c)).sum::<f32>() * size / 1000.0
+    }
+}
+
+/// Encode text for the fonts' WinAnsiEncoding; characters it lacks become '?'.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            '\u{2013}' => 0x96,
+            '\u{2014}' => 0x97,
+            '\u{2018}' => 0x91,
+            '\u{2019}' => 0x92,
+            '\u{201c}' => 0x93,
+            '\u{201d}' => 0x94,
+            '\u{2022}' => 0x95,
+            '\u{2026}' => 0x85,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// Top-to-bottom text layout over a fixed number of pages.
+struct Sheet {
+    page_size: (f32, f32),
+    margin: f32,
+    /// Body font size; headings and the title scale from it
+    size: f32,
+    max_pages: usize,
+    pages: Vec<Content>,
+    /// Baseline position of the next line
+    y: f32,
+    /// Set once something did not fit on the last page
+    overflow: bool,
+}
+
+impl Sheet {
+    fn new(page_size: (f32, f32), margin: f32, size: f32, max_pages: usize) -> Self {
+        Sheet {
+            page_size,
+            margin,
+            size,
+            max_pages,
+            pages: vec![Content::new()],
+            y: page_size.1 - margin,
+            overflow: false,
+        }
+    }
+
+    fn width(&self) -> f32 {
+        self.page_size.0 - 2.0 * self.margin
+    }
+
+    /// Make room for `height` points, moving to a new page if needed. Returns false when the
+    /// last page is full, after marking the cut in the bottom margin.
+    fn reserve(&mut self, height: f32) -> bool {
+        if self.overflow {
+            return false;
+        }
+        if self.y - height >= self.margin {
+            return true;
+        }
+        if self.pages.len() < self.max_pages {
+            self.pages.push(Content::new());
+            self.y = self.page_size.1 - self.margin;
+            return true;
+        }
+        self.overflow = true;
+        let size = self.size * 0.9;
+        let x = self.page_size.0 - self.margin - Font::Italic.width(CUT_OFF_MARKER, size);
+        self.text(x, self.margin - size * 1.5, Font::Italic, size, CUT_OFF_MARKER);
+        false
+    }
+
+    fn content(&mut self) -> &mut Content {
+        self.pages.last_mut().expect("sheet always has a page")
+    }
+
+    fn text(&mut self, x: f32, y: f32, font: Font, size: f32, text: &str) {
+        let bytes = encode(text);
+        let content = self.content();
+        content.begin_text();
+        content.set_font(font.resource(), size);
+        content.next_line(x, y);
+        content.show(Str(&bytes));
+        content.end_text();
+    }
+
+    fn gap(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    /// Styled runs given as owned strings, word-wrapped like `paragraph`.
+    fn spans(&mut self, spans: &[(Font, String)], size: f32) {
+        let runs: Vec<(Font, &str)> = spans.iter().map(|(font, text)| (*font, text.as_str())).collect();
+        self.paragraph(&runs, size);
+    }
+
+    /// Styled runs, word-wrapped to the page width. Runs of spaces are kept as they are,
+    /// except at the start of a wrapped line.
+    fn paragraph(&mut self, runs: &[(Font, &str)], size: f32) {
+        // (font, word, spaces before it)
+        let mut words: Vec<(Font, String, usize)> = Vec::new();
+        let mut spaces = 0;
+        for &(font, text) in runs {
+            let mut word = String::new();
+            for c in text.chars() {
+                if c.is_whitespace() {
+                    if !word.is_empty() {
+                        words.push((font, std::mem::take(&mut word), spaces));
+                        spaces = 0;
+                    }
+                    spaces += 1;
+                } else {
+                    word.push(c);
+                }
+            }
+            if !word.is_empty() {
+                words.push((font, word, spaces));
+                spaces = 0;
+            }
+        }
+
+        let width = self.width();
+        let space = Font::Regular.width(" ", size);
+        // Each line is a list of (font, word, x offset)
+        let mut lines: Vec<Vec<(Font, String, f32)>> = vec![Vec::new()];
+        let mut line_width = 0.0;
+        for (font, word, spaces) in words {
+            let word_width = font.width(&word, size);
+            let mut gap = if line_width > 0.0 { spaces as f32 * space } else { 0.0 };
+            if line_width > 0.0 && line_width + gap + word_width > width {
+                lines.push(Vec::new());
+                line_width = 0.0;
+                gap = 0.0;
+            }
+            lines.last_mut().expect("at least one line").push((font, word, line_width + gap));
+            line_width += gap + word_width;
+        }
+
+        let line_height = size * 1.25;
+        for line in lines {
+            if !self.reserve(line_height) {
+                return;
+            }
+            self.y -= size;
+            for (font, word, offset) in &line {
+                self.text(self.margin + offset, self.y, *font, size, word);
+            }
+            self.y -= line_height - size;
+        }
+    }
+
+    /// Bold label followed by regular text, wrapped together.
+    fn labeled(&mut self, label: &str, text: &str) {
+        let size = self.size;
+        self.paragraph(&[(Font::Bold, label), (Font::Regular, text)], size);
+    }
+
+    fn heading(&mut self, text: &str) {
+        let size = self.size * 1.3;
+        // Keep a heading together with at least one line of its section
+        if !self.reserve(size * 1.6 + self.size * 2.5) {
+            return;
+        }
+        self.gap(size * 0.5);
+        self.paragraph(&[(Font::Bold, text)], size);
+        self.rule();
+    }
+
+    fn rule(&mut self) {
+        if !self.reserve(self.size * 0.6) {
+            return;
+        }
+        self.y -= self.size * 0.2;
+        let (x, y, width) = (self.margin, self.y, self.width());
+        let content = self.content();
+        content.set_line_width(0.75);
+        content.set_stroke_gray(0.35);
+        content.move_to(x, y);
+        content.line_to(x + width, y);
+        content.stroke();
+        self.y -= self.size * 0.4;
+    }
+
+    /// Six boxes: ability name, modifier, score.
+    fn ability_boxes(&mut self, npc: &NPC) {
+        let size = self.size;
+        let height = size * 4.2;
+        if !self.reserve(height) {
+            return;
+        }
+        let gap = size;
+        let box_width = (self.width() - 5.0 * gap) / 6.0;
+        let top = self.y;
+        for (i, ability) in Ability::ALL.into_iter().enumerate() {
+            let x = self.margin + i as f32 * (box_width + gap);
+            let content = self.content();
+            content.set_line_width(1.0);
+            content.set_stroke_gray(0.2);
+            content.rect(x, top - height, box_width, height);
+            content.stroke();
+
+            let centered = |font: Font, size: f32, text: &str| x + (box_width - font.width(text, size)) / 2.0;
+            let label = ability.name().to_uppercase();
+            let modifier = signed(npc.ability_mod(ability));
+            let score = npc.ability_score(ability).to_string();
+            self.text(centered(Font::Bold, size * 0.75, &label), top - size * 1.1, Font::Bold, size * 0.75, &label);
+            self.text(centered(Font::Bold, size * 1.8, &modifier), top - size * 2.8, Font::Bold, size * 1.8, &modifier);
+            self.text(centered(Font::Regular, size, &score), top - size * 3.8, Font::Regular, size, &score);
+        }
+        self.y -= height;
+    }
+
+    fn finish(self, title: &str) -> Vec<u8> {
+        let mut pdf = Pdf::new();
+        let catalog_id = Ref::new(1);
+        let page_tree_id = Ref::new(2);
+        let info_id = Ref::new(3);
+        let font_ids = [Ref::new(4), Ref::new(5), Ref::new(6)];
+        let first_page = 7;
+        let page_ids: Vec<Ref> = (0..self.pages.len()).map(|i| Ref::new(first_page + 2 * i as i32)).collect();
+
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        pdf.pages(page_tree_id).kids(page_ids.iter().copied()).count(page_ids.len() as i32);
+        pdf.document_info(info_id).title(TextStr(title)).creator(TextStr("NPCForge"));
+
+        for (id, base) in font_ids.iter().zip([b"Helvetica".as_slice(), b"Helvetica-Bold", b"Helvetica-Oblique"]) {
+            pdf.type1_font(*id).base_font(Name(base)).encoding_predefined(Name(b"WinAnsiEncoding"));
+        }
+
+        for (content, page_id) in self.pages.into_iter().zip(&page_ids) {
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut page = pdf.page(*page_id);
+            page.media_box(Rect::new(0.0, 0.0, self.page_size.0, self.page_size.1));
+            page.parent(page_tree_id);
+            page.contents(content_id);
+            let mut resources = page.resources();
+            let mut fonts = resources.fonts();
+            for (font, id) in [Font::Regular, Font::Bold, Font::Italic].into_iter().zip(font_ids) {
+                fonts.pair(font.resource(), id);
+            }
+            fonts.finish();
+            resources.finish();
+            page.finish();
+            pdf.stream(content_id, &content.finish());
+        }
+
+        pdf.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seraphine() -> NPC {
+        serde_json::from_str(include_str!("../../tests/fixtures/seraphine.json")).unwrap()
+    }
+
+    fn contains(pdf: &[u8], text: &str) -> bool {
+        pdf.windows(text.len()).any(|w| w == text.as_bytes())
+    }
+
+    /// pdf-writer writes strings with non-ASCII bytes, like the marker's ellipsis, in hex
+    fn has_marker(pdf: &[u8]) -> bool {
+        let hex: String = encode(CUT_OFF_MARKER).iter().map(|b| format!("{:02X}", b)).collect();
+        contains(pdf, &hex)
+    }
+
+    #[test]
+    fn content_that_fits_is_not_marked() {
+        let mut npc = seraphine();
+        npc.backstory.push_str("\n\nENDMARKER");
+        let (pdf, cut_off) = render(&npc);
+        assert!(!cut_off);
+        assert!(contains(&pdf, "ENDMARKER"));
+        assert!(!has_marker(&pdf));
+    }
+
+    #[test]
+    fn oversized_backstory_is_cut_off_with_a_marker() {
+        let mut npc = seraphine();
+        let paragraph = "The road north was long and the rain never stopped. ".repeat(20);
+        npc.backstory = vec![paragraph.trim(); 40].join("\n\n") + "\n\nENDMARKER";
+        let (pdf, cut_off) = render(&npc);
+        assert!(cut_off);
+        assert!(has_marker(&pdf));
+        assert!(!contains(&pdf, "ENDMARKER"));
+
+        let (_, warning) = super::super::Format::Pdf.render_checked(&npc);
+        assert!(warning.is_some_and(|w| w.contains("cut off")));
+    }
+}
@@ -0,0 +1,193 @@
+//! Roll20 character JSON for the "D&D 5E by Roll20" (OGL) sheet in NPC mode: the
+//! `{ name, bio, attribs: [{ name, current, max, id }] }` layout that character vault and
+//! importer scripts read. Repeating rows get stable ids so re-exports diff cleanly.
+
+use serde_json::{Value, json};
+
+use super::{average_damage, html, is_melee, paragraphs, range_feet, signed, spaced_dice, spellcasting_intro};
+use crate::types::{Ability, Size};
+use crate::{NPC, ProficiencyLevel};
+
+/// Roll20 sheet attribute collector.
+struct Attribs {
+    list: Vec<Value>,
+    next_row: usize,
+}
+
+impl Attribs {
+    fn set(&mut self, name: &str, current: impl ToString) {
+        self.list.push(json!({ "name": name, "current": current.to_string(), "max": "", "id": "" }));
+    }
+
+    fn set_max(&mut self, name: &str, current: impl ToString, max: impl ToString) {
+        self.list.push(json!({ "name": name, "current": current.to_string(), "max": max.to_string(), "id": "" }));
+    }
+
+    /// Id for a new row in a repeating section, in Roll20's "-" + 19 character form.
+    fn row_id(&mut self) -> String {
+        self.next_row += 1;
+        format!("-npcforge{:011}", self.next_row)
+    }
+}
+
+fn ability_attr(ability: Ability) -> String {
+    ability.name().to_lowercase()
+}
+
+/// Sheet attribute name of a skill: "Animal Handling" -> "animal_handling".
+fn skill_attr(name: &str) -> String {
+    name.to_lowercase().replace(' ', "_")
+}
+
+pub fn render(npc: &NPC) -> String {
+    let character = json!({
+        "schema_version": 2,
+        "name": npc.name,
+        "avatar": "",
+        "bio": bio(npc),
+        "gmnotes": "",
+        "defaulttoken": "",
+        "tags": "[]",
+        "controlledby": "",
+        "inplayerjournals": "",
+        "attribs": attribs(npc),
+        "abilities": [],
+    });
+    serde_json::to_string_pretty(&character).unwrap_or_default()
+}
+
+fn bio(npc: &NPC) -> String {
+    let mut bio = String::new();
+    for paragraph in paragraphs(&npc.backstory) {
+        bio.push_str(&format!("<p>{}</p>", html::escape(paragraph)));
+    }
+    let p = &npc.personality;
+    bio.push_str(&format!(
+        "<p><b>Ideals:</b> {}<br><b>Bonds:</b> {}<br><b>Flaws:</b> {}</p>",
+        html::escape(&p.ideals),
+        html::escape(&p.bonds),
+        html::escape(&p.flaws)
+    ));
+    bio
+}
+
+fn attribs(npc: &NPC) -> Vec<Value> {
+    let mut a = Attribs { list: Vec::new(), next_row: 0 };
+    let size = npc.size.unwrap_or_else(|| Size::for_race(&npc.race));
+
+    a.set("npc", 1);
+    a.set("npc_name", &npc.name);
+    a.set(
+        "npc_type",
+        format!("{} humanoid ({}), {}", size, npc.race.to_lowercase(), npc.alignment.to_string().to_lowercase()),
+    );
+    a.set("race", &npc.race);
+    a.set("class", npc.class_summary());
+    a.set("level", npc.level);
+    a.set("alignment", npc.alignment.to_string());
+    a.set("background", &npc.background);
+    a.set("npc_ac", npc.armor_class);
+    a.set("npc_actype", npc.equipment.armor.join(", ").to_lowercase());
+    a.set_max("hp", npc.hit_points.max, npc.hit_points.max);
+    a.set("npc_hpformula", &npc.hit_points.hit_dice);
+    a.set("npc_speed", format!("{} ft.", npc.speed));
+    a.set("pb", npc.proficiency_bonus);
+    a.set("initiative_bonus", npc.initiative);
+
+    for ability in Ability::ALL {
+        let name = ability_attr(ability);
+        a.set(&name, npc.ability_score(ability));
+        a.set(&format!("{}_base", name), npc.ability_score(ability));
+        a.set(&format!("{}_mod", name), npc.ability_mod(ability));
+    }
+
+    let saves: Vec<_> = npc.saving_throws.iter().filter(|s| s.proficient).collect();
+    a.set("npc_saving_flag", if saves.is_empty() { "" } else { "1" });
+    for save in saves {
+        let key = save.ability.abbr().to_lowercase();
+        a.set(&format!("npc_{}_save_base", key), signed(save.modifier));
+        a.set(&format!("npc_{}_save", key), save.modifier);
+    }
+
+    let skills: Vec<_> = npc.skills.iter().filter(|s| s.proficiency.is_proficient()).collect();
+    a.set("npc_skills_flag", if skills.is_empty() { "" } else { "1" });
+    for skill in skills {
+        let key = skill_attr(skill.name.name());
+        a.set(&format!("npc_{}_base", key), signed(skill.modifier));
+        a.set(&format!("npc_{}", key), skill.modifier);
+        if skill.proficiency == ProficiencyLevel::Expertise {
+            a.set(&format!("{}_type", key), 2);
+        }
+    }
+
+    a.set("npc_senses", format!("passive Perception {}", npc.passive_perception));
+    a.set("npc_languages", npc.languages.join(", "));
+    a.set("npc_challenge", "");
+    a.set("npc_xp", 0);
+
+    for feature in &npc.features {
+        let row = a.row_id();
+        a.set(&format!("repeating_npctrait_{}_name", row), &feature.name);
+        a.set(&format!("repeating_npctrait_{}_description", row), &feature.description);
+    }
+
+    for attack in &npc.attacks {
+        let row = a.row_id();
+        let prefix = format!("repeating_npcaction_{}", row);
+        let range = match range_feet(attack) {
+            Some((normal, Some(long))) => format!("{}/{} ft.", normal, long),
+            Some((normal, None)) => format!("{} ft.", normal),
+            None => "5 ft.".to_string(),
+        };
+        let damage = spaced_dice(&attack.damage);
+        let onhit = match average_damage(&attack.damage) {
+            Some(average) => format!("{} ({}) {} damage", average, damage, attack.damage_type),
+            None => format!("{} {} damage", damage, attack.damage_type),
+        };
+        a.set(&format!("{}_name", prefix), &attack.name);
+        a.set(&format!("{}_attack_flag", prefix), "on");
+        a.set(&format!("{}_attack_type", prefix), if is_melee(attack) { "Melee" } else { "Ranged" });
+        a.set(&format!("{}_attack_range", prefix), range);
+        a.set(&format!("{}_attack_target", prefix), "one target");
+        a.set(&format!("{}_attack_tohit", prefix), attack.attack_bonus);
+        a.set(&format!("{}_attack_damage", prefix), damage);
+        a.set(&format!("{}_attack_damagetype", prefix), attack.damage_type.to_string());
+        a.set(&format!("{}_attack_onhit", prefix), onhit);
+        a.set(&format!("{}_description", prefix), attack.properties.join(", "));
+    }
+
+    if let Some(spells) = &npc.spells
+        && !spells.spells_known.is_empty()
+    {
+        a.set("npcspellcastingflag", 1);
+        a.set("npc_spellcasting_desc", spellcasting_intro(npc, spells));
+        if let Some(ability) = spells.spellcasting_ability {
+            a.set("spellcasting_ability", format!("@{{{}_mod}}+", ability_attr(ability)));
+        }
+        a.set("spell_save_dc", spells.spell_save_dc);
+        a.set("spell_attack_bonus", spells.spell_attack_bonus);
+        if let Some(slots) = &spells.spell_slots {
+            for level in 1..=9 {
+                let count = slots.slots(level);
+                if count > 0 {
+                    a.set(&format!("lvl{}_slots_total", level), count);
+                    a.set(&format!("lvl{}_slots_expended", level), count);
+                }
+            }
+        }
+        for (level, list) in spells.spells_known.by_level() {
+            let section = if level == 0 { "cantrip".to_string() } else { level.to_string() };
+            for name in list {
+                let row = a.row_id();
+                let prefix = format!("repeating_spell-{}_{}", section, row);
+                a.set(&format!("{}_spellname", prefix), name);
+                a.set(&format!("{}_spelllevel", prefix), &section);
+                if level > 0 {
+                    a.set(&format!("{}_spellprepared", prefix), 1);
+                }
+            }
+        }
+    }
+
+    a.list
+}
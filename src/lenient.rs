@@ -0,0 +1,667 @@
+//! Tolerant parsing of model output into an `NPC`.
+//!
+//! The model regularly gets the *shape* of the JSON slightly wrong: an array where a string is
+//! expected, `"15"` instead of `15`, a negative number in an unsigned field, or a whole nested
+//! object left out. Instead of failing the NPC, the raw JSON is walked against a description of
+//! the expected structure, every slip is coerced into something serde accepts, and a warning is
+//! recorded for each fix.
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Map, Value};
+
+use crate::NPC;
+use crate::types::{Ability, Alignment, Class, Classes, Size, Skill};
+
+enum Shape {
+    /// Short string; arrays are joined with the given separator
+    Str(&'static str),
+    /// String or null
+    OptStr,
+    /// Integer clamped to the inclusive range
+    Int(i64, i64),
+    Bool,
+    StrList,
+    Obj(&'static [Field]),
+    OptObj(&'static [Field]),
+    /// List of objects. Bare strings are kept for types that accept them (skills, saves), or
+    /// expanded into `{key: string}` when a key is given.
+    List(&'static [Field], Option<&'static str>),
+    /// Object of integer values, e.g. `class_levels`
+    OptIntMap(i64, i64),
+}
+
+struct Field {
+    name: &'static str,
+    shape: Shape,
+    /// Whether serde requires the field; missing optional fields are left for `#[serde(default)]`
+    required: bool,
+}
+
+const fn req(name: &'static str, shape: Shape) -> Field {
+    Field { name, shape, required: true }
+}
+
+const fn opt(name: &'static str, shape: Shape) -> Field {
+    Field { name, shape, required: false }
+}
+
+const U8: Shape = Shape::Int(0, u8::MAX as i64);
+const I8: Shape = Shape::Int(i8::MIN as i64, i8::MAX as i64);
+const U16: Shape = Shape::Int(0, u16::MAX as i64);
+const U32: Shape = Shape::Int(0, u32::MAX as i64);
+
+const ABILITY_SCORES: &[Field] = &[
+    req("strength", U8),
+    req("dexterity", U8),
+    req("constitution", U8),
+    req("intelligence", U8),
+    req("wisdom", U8),
+    req("charisma", U8),
+];
+
+const HIT_POINTS: &[Field] = &[
+    req("max", U16),
+    req("current", U16),
+    req("temporary", U16),
+    req("hit_dice", Shape::Str(" + ")),
+];
+
+const SKILL: &[Field] = &[
+    req("name", Shape::Str(" ")),
+    opt("ability", Shape::Str("/")),
+    opt("modifier", I8),
+    opt("proficient", Shape::Bool),
+    opt("proficiency", Shape::Str(" ")),
+];
+
+const SAVING_THROW: &[Field] = &[
+    req("ability", Shape::Str("/")),
+    opt("modifier", I8),
+    opt("proficient", Shape::Bool),
+];
+
+const ATTACK: &[Field] = &[
+    req("name", Shape::Str(" / ")),
+    req("attack_bonus", I8),
+    req("damage", Shape::Str(" + ")),
+    req("damage_type", Shape::Str(" + ")),
+    opt("range", Shape::OptStr),
+    opt("properties", Shape::StrList),
+];
+
+const SPELL_SLOTS: &[Field] = &[
+    opt("level_1", U8),
+    opt("level_2", U8),
+    opt("level_3", U8),
+    opt("level_4", U8),
+    opt("level_5", U8),
+    opt("level_6", U8),
+    opt("level_7", U8),
+    opt("level_8", U8),
+    opt("level_9", U8),
+];
+
+const SPELLS_BY_LEVEL: &[Field] = &[
+    opt("cantrips", Shape::StrList),
+    opt("level_1", Shape::StrList),
+    opt("level_2", Shape::StrList),
+    opt("level_3", Shape::StrList),
+    opt("level_4", Shape::StrList),
+    opt("level_5", Shape::StrList),
+    opt("level_6", Shape::StrList),
+    opt("level_7", Shape::StrList),
+    opt("level_8", Shape::StrList),
+    opt("level_9", Shape::StrList),
+];
+
+const SPELLCASTING: &[Field] = &[
+    opt("spellcasting_ability", Shape::OptStr),
+    opt("spell_save_dc", U8),
+    opt("spell_attack_bonus", I8),
+    opt("spell_slots", Shape::OptObj(SPELL_SLOTS)),
+    opt("spells_known", Shape::Obj(SPELLS_BY_LEVEL)),
+];
+
+const TREASURE: &[Field] = &[opt("gold", U32), opt("items", Shape::StrList)];
+
+const EQUIPMENT: &[Field] = &[
+    opt("armor", Shape::StrList),
+    opt("weapons", Shape::StrList),
+    opt("gear", Shape::StrList),
+    req("treasure", Shape::Obj(TREASURE)),
+];
+
+const PERSONALITY: &[Field] = &[
+    opt("traits", Shape::StrList),
+    req("ideals", Shape::Str("; ")),
+    req("bonds", Shape::Str("; ")),
+    req("flaws", Shape::Str("; ")),
+];
+
+const APPEARANCE: &[Field] = &[
+    req("age", U16),
+    req("height", Shape::Str(" ")),
+    req("weight", Shape::Str(" ")),
+    req("eyes", Shape::Str(" and ")),
+    req("hair", Shape::Str(", ")),
+    req("skin", Shape::Str(", ")),
+    opt("distinguishing_features", Shape::StrList),
+];
+
+const FEATURE: &[Field] = &[req("name", Shape::Str(" / ")), req("description", Shape::Str(" "))];
+
+const NPC_FIELDS: &[Field] = &[
+    req("name", Shape::Str(" ")),
+    req("race", Shape::Str(" ")),
+    req("class", Shape::Str("/")),
+    opt("subclass", Shape::OptStr),
+    req("level", Shape::Int(1, 20)),
+    opt("class_levels", Shape::OptIntMap(1, 20)),
+    req("role", Shape::Str(" / ")),
+    req("background", Shape::Str(" / ")),
+    req("alignment", Shape::Str(" ")),
+    req("fighting_preference", Shape::Str("/")),
+    opt("size", Shape::OptStr),
+    req("ability_scores", Shape::Obj(ABILITY_SCORES)),
+    req("hit_points", Shape::Obj(HIT_POINTS)),
+    req("armor_class", U8),
+    req("initiative", I8),
+    req("speed", U8),
+    req("proficiency_bonus", I8),
+    opt("skills", Shape::List(SKILL, None)),
+    opt("saving_throws", Shape::List(SAVING_THROW, None)),
+    opt("passive_perception", U8),
+    opt("passive_insight", U8),
+    opt("passive_investigation", U8),
+    opt("languages", Shape::StrList),
+    opt("tool_proficiencies", Shape::StrList),
+    opt("attacks", Shape::List(ATTACK, None)),
+    opt("spells", Shape::OptObj(SPELLCASTING)),
+    req("equipment", Shape::Obj(EQUIPMENT)),
+    req("personality", Shape::Obj(PERSONALITY)),
+    req("backstory", Shape::Str("\n\n")),
+    req("appearance", Shape::Obj(APPEARANCE)),
+    opt("features", Shape::List(FEATURE, Some("name"))),
+];
+
+/// Parse model output into an `NPC`, coercing shape errors and returning a warning per fix.
+pub fn parse_npc_lenient(text: &str) -> Result<(NPC, Vec<String>)> {
+    let mut warnings = Vec::new();
+    let value = parse_json_text(text, &mut warnings)?;
+    let npc = parse_npc_value(value, &mut warnings)?;
+    Ok((npc, warnings))
+}
+
+/// Same as `parse_npc_lenient` for JSON that has already been parsed (or pieced together).
+pub fn parse_npc_value(mut value: Value, warnings: &mut Vec<String>) -> Result<NPC> {
+    coerce_npc_value(&mut value, warnings)?;
+    serde_json::from_value(value).context("NPC JSON still invalid after coercion")
+}
+
+/// Coerce an already-parsed JSON value in place.
+fn coerce_npc_value(value: &mut Value, warnings: &mut Vec<String>) -> Result<()> {
+    let Value::Object(obj) = value else {
+        bail!("expected a JSON object for the NPC, got {}", kind(value));
+    };
+    coerce_object(obj, NPC_FIELDS, "", warnings);
+    check_enums(obj, warnings);
+    Ok(())
+}
+
+/// Parse the raw text, tolerating Markdown code fences and chatter around the object.
+fn parse_json_text(text: &str, warnings: &mut Vec<String>) -> Result<Value> {
+    if let Ok(value) = serde_json::from_str(text) {
+        return Ok(value);
+    }
+    let start = text.find('{');
+    let end = text.rfind('}');
+    if let (Some(start), Some(end)) = (start, end)
+        && start < end
+        && let Ok(value) = serde_json::from_str(&text[start..=end])
+    {
+        warnings.push("response: stripped text surrounding the JSON object".to_string());
+        return Ok(value);
+    }
+    serde_json::from_str(text).context(format!(
+        "Failed to parse NPC JSON. Response was: {}",
+        &text[..text.floor_char_boundary(1000)]
+    ))
+}
+
+/// Tool proficiencies the model files under skills: "Thieves' Tools", "Herbalism Kit",
+/// "Dragonchess Set", "Calligrapher's Supplies".
+fn looks_like_tool(name: &str) -> bool {
+    name.split(|c: char| !c.is_ascii_alphabetic())
+        .map(str::to_ascii_lowercase)
+        .any(|word| matches!(word.as_str(), "tool" | "tools" | "kit" | "set" | "supplies" | "utensils" | "instrument"))
+}
+
+fn path_of(parent: &str, name: &str) -> String {
+    if parent.is_empty() { name.to_string() } else { format!("{}.{}", parent, name) }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+fn coerce_object(obj: &mut Map<String, Value>, fields: &[Field], parent: &str, warnings: &mut Vec<String>) {
+    for field in fields {
+        let path = path_of(parent, field.name);
+        match obj.get_mut(field.name) {
+            Some(value) => coerce(value, &field.shape, &path, warnings),
+            None if field.required => {
+                warnings.push(format!("{}: missing, using default", path));
+                let mut value = default_for(&field.shape);
+                coerce(&mut value, &field.shape, &path, &mut Vec::new());
+                obj.insert(field.name.to_string(), value);
+            }
+            None => {}
+        }
+    }
+}
+
+fn default_for(shape: &Shape) -> Value {
+    match shape {
+        Shape::Str(_) => Value::String(String::new()),
+        Shape::Int(min, _) => Value::from((*min).max(0)),
+        Shape::Bool => Value::Bool(false),
+        Shape::StrList | Shape::List(..) => Value::Array(Vec::new()),
+        Shape::Obj(_) => Value::Object(Map::new()),
+        Shape::OptStr | Shape::OptObj(_) | Shape::OptIntMap(..) => Value::Null,
+    }
+}
+
+fn coerce(value: &mut Value, shape: &Shape, path: &str, warnings: &mut Vec<String>) {
+    match shape {
+        Shape::Str(sep) => coerce_str(value, sep, path, warnings),
+        Shape::OptStr => {
+            if !value.is_null() {
+                coerce_str(value, " ", path, warnings);
+            }
+        }
+        Shape::Int(min, max) => coerce_int(value, *min, *max, path, warnings),
+        Shape::Bool => coerce_bool(value, path, warnings),
+        Shape::StrList => coerce_str_list(value, path, warnings),
+        Shape::Obj(fields) => match value {
+            Value::Object(obj) => coerce_object(obj, fields, path, warnings),
+            other => {
+                warnings.push(format!("{}: expected an object, got {}; using default", path, kind(other)));
+                let mut obj = Map::new();
+                coerce_object(&mut obj, fields, path, &mut Vec::new());
+                *other = Value::Object(obj);
+            }
+        },
+        Shape::OptObj(fields) => match value {
+            Value::Null => {}
+            Value::Object(obj) => coerce_object(obj, fields, path, warnings),
+            other => {
+                warnings.push(format!("{}: expected an object, got {}; dropped", path, kind(other)));
+                *other = Value::Null;
+            }
+        },
+        Shape::List(fields, str_key) => coerce_list(value, fields, *str_key, path, warnings),
+        Shape::OptIntMap(min, max) => match value {
+            Value::Null => {}
+            Value::Object(obj) => {
+                for (key, v) in obj.iter_mut() {
+                    coerce_int(v, *min, *max, &path_of(path, key), warnings);
+                }
+            }
+            other => {
+                warnings.push(format!("{}: expected an object, got {}; dropped", path, kind(other)));
+                *other = Value::Null;
+            }
+        },
+    }
+}
+
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        // Objects like {"name": "Longsword", "quantity": 1} are usually named items
+        Value::Object(obj) => obj.get("name").and_then(Value::as_str).map(str::to_string),
+        _ => None,
+    }
+}
+
+fn coerce_str(value: &mut Value, sep: &str, path: &str, warnings: &mut Vec<String>) {
+    let fixed = match &*value {
+        Value::String(_) => return,
+        Value::Array(items) => {
+            let parts: Vec<String> = items.iter().filter_map(scalar_to_string).collect();
+            warnings.push(format!("{}: expected a string, joined array with {:?}", path, sep));
+            parts.join(sep)
+        }
+        Value::Null => {
+            warnings.push(format!("{}: was null, using empty string", path));
+            String::new()
+        }
+        other => {
+            let s = scalar_to_string(other).unwrap_or_default();
+            warnings.push(format!("{}: expected a string, got {}", path, kind(other)));
+            s
+        }
+    };
+    *value = Value::String(fixed);
+}
+
+fn coerce_int(value: &mut Value, min: i64, max: i64, path: &str, warnings: &mut Vec<String>) {
+    let parsed = match &*value {
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Some(i),
+            None => {
+                let f = n.as_f64().unwrap_or(0.0);
+                warnings.push(format!("{}: rounded {} to an integer", path, f));
+                Some(f.round() as i64)
+            }
+        },
+        Value::String(s) => {
+            // "15", "+5", "30 ft.", "-1"
+            let trimmed = s.trim().trim_start_matches('+');
+            let digits: String = trimmed
+                .char_indices()
+                .take_while(|&(i, c)| c.is_ascii_digit() || (i == 0 && c == '-'))
+                .map(|(_, c)| c)
+                .collect();
+            let parsed = digits.parse::<i64>().ok();
+            match parsed {
+                Some(i) => warnings.push(format!("{}: converted string {:?} to {}", path, s, i)),
+                None => warnings.push(format!("{}: could not read a number from {:?}", path, s)),
+            }
+            parsed
+        }
+        other => {
+            warnings.push(format!("{}: expected a number, got {}", path, kind(other)));
+            None
+        }
+    };
+    let n = parsed.unwrap_or(min.max(0));
+    let clamped = n.clamp(min, max);
+    if clamped != n {
+        warnings.push(format!("{}: {} out of range, clamped to {}", path, n, clamped));
+    }
+    *value = Value::from(clamped);
+}
+
+fn coerce_bool(value: &mut Value, path: &str, warnings: &mut Vec<String>) {
+    let fixed = match value {
+        Value::Bool(_) => return,
+        Value::String(s) => matches!(s.trim().to_ascii_lowercase().as_str(), "true" | "yes" | "y" | "1"),
+        Value::Number(n) => n.as_f64().is_some_and(|f| f != 0.0),
+        _ => false,
+    };
+    warnings.push(format!("{}: expected a boolean, got {}", path, kind(value)));
+    *value = Value::Bool(fixed);
+}
+
+fn coerce_str_list(value: &mut Value, path: &str, warnings: &mut Vec<String>) {
+    match value {
+        Value::Array(items) => {
+            let mut converted = false;
+            let mut fixed = Vec::with_capacity(items.len());
+            for item in items.iter() {
+                match item {
+                    Value::String(s) => fixed.push(Value::String(s.clone())),
+                    other => {
+                        converted = true;
+                        if let Some(s) = scalar_to_string(other) {
+                            fixed.push(Value::String(s));
+                        }
+                    }
+                }
+            }
+            if converted {
+                warnings.push(format!("{}: converted non-string entries to strings", path));
+                *items = fixed;
+            }
+        }
+        Value::Null => {
+            warnings.push(format!("{}: was null, using empty list", path));
+            *value = Value::Array(Vec::new());
+        }
+        other => {
+            warnings.push(format!("{}: expected a list, wrapped {}", path, kind(other)));
+            let wrapped = scalar_to_string(other).map(Value::String).into_iter().collect();
+            *other = Value::Array(wrapped);
+        }
+    }
+}
+
+fn coerce_list(
+    value: &mut Value,
+    fields: &[Field],
+    str_key: Option<&str>,
+    path: &str,
+    warnings: &mut Vec<String>,
+) {
+    let items = match value.take() {
+        Value::Array(items) => items,
+        obj @ Value::Object(_) => {
+            warnings.push(format!("{}: expected a list, wrapped single object", path));
+            vec![obj]
+        }
+        Value::Null => {
+            warnings.push(format!("{}: was null, using empty list", path));
+            Vec::new()
+        }
+        other => {
+            warnings.push(format!("{}: expected a list, got {}; using empty list", path, kind(&other)));
+            Vec::new()
+        }
+    };
+
+    let mut fixed = Vec::with_capacity(items.len());
+    for (i, item) in items.into_iter().enumerate() {
+        let item_path = format!("{}[{}]", path, i);
+        match item {
+            Value::Object(mut obj) => {
+                coerce_object(&mut obj, fields, &item_path, warnings);
+                fixed.push(Value::Object(obj));
+            }
+            Value::String(s) => match str_key {
+                // Skills and saves accept bare names directly
+                None => fixed.push(Value::String(s)),
+                Some(key) => {
+                    warnings.push(format!("{}: expanded string into an object", item_path));
+                    let mut obj = Map::new();
+                    // "Second Wind: regain 1d10 + level HP" -> name + description
+                    match s.split_once(": ") {
+                        Some((name, rest)) => {
+                            obj.insert(key.to_string(), Value::String(name.trim().to_string()));
+                            obj.insert("description".to_string(), Value::String(rest.trim().to_string()));
+                        }
+                        None => {
+                            obj.insert(key.to_string(), Value::String(s));
+                        }
+                    }
+                    coerce_object(&mut obj, fields, &item_path, &mut Vec::new());
+                    fixed.push(Value::Object(obj));
+                }
+            },
+            other => warnings.push(format!("{}: dropped unusable {}", item_path, kind(&other))),
+        }
+    }
+    *value = Value::Array(fixed);
+}
+
+/// Flag values the typed enums will not recognize, and clear or replace ones that would fail outright.
+fn check_enums(obj: &mut Map<String, Value>, warnings: &mut Vec<String>) {
+    if let Some(Value::String(s)) = obj.get("alignment")
+        && let Ok(Alignment::Other(_)) = s.parse::<Alignment>()
+    {
+        warnings.push(format!("alignment: {:?} is not a standard alignment, kept as-is", s));
+    }
+
+    // The typed lists can't hold these; a tool listed as a skill moves to the tool proficiencies,
+    // anything else is dropped, and both are reported
+    let mut tools = Vec::new();
+    for (key, field) in [("skills", "name"), ("saving_throws", "ability")] {
+        let Some(Value::Array(items)) = obj.get_mut(key) else { continue };
+        let mut i = 0;
+        items.retain(|item| {
+            let index = i;
+            i += 1;
+            let name = match item {
+                Value::String(s) => s,
+                Value::Object(o) => match o.get(field) {
+                    Some(Value::String(s)) => s,
+                    _ => return true,
+                },
+                _ => return true,
+            };
+            let known = if key == "skills" {
+                name.parse::<Skill>().is_ok()
+            } else {
+                name.parse::<Ability>().is_ok()
+            };
+            if known {
+                return true;
+            }
+            if key == "skills" && looks_like_tool(name) {
+                warnings.push(format!("{}[{}]: {:?} is a tool, moved to tool_proficiencies", key, index, name));
+                tools.push(name.clone());
+            } else {
+                warnings.push(format!("{}[{}]: {:?} is not recognized and was dropped", key, index, name));
+            }
+            false
+        });
+    }
+    if !tools.is_empty() {
+        let list = obj.entry("tool_proficiencies").or_insert_with(|| Value::Array(Vec::new()));
+        if let Value::Array(list) = list {
+            for tool in tools {
+                let listed = list.iter().any(|t| t.as_str().is_some_and(|t| t.eq_ignore_ascii_case(&tool)));
+                if !listed {
+                    list.push(Value::String(tool));
+                }
+            }
+        }
+    }
+
+    if let Some(Value::String(s)) = obj.get("size")
+        && s.parse::<Size>().is_err()
+    {
+        warnings.push(format!("size: {:?} is not a creature size, cleared", s));
+        obj.insert("size".to_string(), Value::Null);
+    }
+
+    if let Some(Value::Object(spells)) = obj.get_mut("spells")
+        && let Some(Value::String(s)) = spells.get("spellcasting_ability")
+        && !s.trim().is_empty()
+        && s.parse::<Ability>().is_err()
+    {
+        warnings.push(format!("spells.spellcasting_ability: {:?} is not an ability, cleared", s));
+        spells.insert("spellcasting_ability".to_string(), Value::Null);
+    }
+
+    if let Some(Value::String(name)) = obj.get("name")
+        && name.trim().is_empty()
+    {
+        warnings.push("name: empty, using \"Unnamed NPC\"".to_string());
+        obj.insert("name".to_string(), Value::String("Unnamed NPC".to_string()));
+    }
+
+    if let Some(Value::String(class)) = obj.get("class")
+        && class.parse::<Classes>().is_err()
+    {
+        // The classes the levels were split across are the best guess; otherwise a plain commoner
+        let from_levels = match obj.get("class_levels") {
+            Some(Value::Object(levels)) => levels.keys().filter(|k| k.parse::<Class>().is_ok()).cloned().collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+        let fallback = if from_levels.is_empty() { "Commoner".to_string() } else { from_levels.join("/") };
+        warnings.push(format!("class: unrecognized {:?}, using {:?}", class, fallback));
+        obj.insert("class".to_string(), Value::String(fallback));
+    }
+
+    if let Some(Value::String(alignment)) = obj.get("alignment")
+        && alignment.parse::<Alignment>().is_err()
+    {
+        warnings.push("alignment: empty, using \"Unaligned\"".to_string());
+        obj.insert("alignment".to_string(), Value::String("Unaligned".to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn missing_class_and_alignment_get_parseable_values() {
+        let mut warnings = Vec::new();
+        let npc = parse_npc_value(json!({"name": "Mira"}), &mut warnings).unwrap();
+        assert_eq!(npc.class_name.to_string(), "Commoner");
+        assert_eq!(npc.alignment, Alignment::Unaligned);
+        assert!(warnings.contains(&"class: unrecognized \"\", using \"Commoner\"".to_string()));
+        assert!(warnings.iter().any(|w| w.starts_with("alignment: empty")));
+    }
+
+    #[test]
+    fn blank_class_falls_back_to_class_levels() {
+        let mut warnings = Vec::new();
+        let value = json!({"name": "Mira", "class": " / ", "alignment": "", "level": 5, "class_levels": {"Rogue": 3, "Wizard": 2}});
+        let npc = parse_npc_value(value, &mut warnings).unwrap();
+        assert_eq!(npc.class_name.to_string(), "Rogue/Wizard");
+        assert_eq!(npc.alignment, Alignment::Unaligned);
+    }
+
+    #[test]
+    fn unparseable_class_is_reported_as_unrecognized() {
+        let mut warnings = Vec::new();
+        let npc = parse_npc_value(json!({"name": "Mira", "class": "5 / 3", "alignment": "CN"}), &mut warnings).unwrap();
+        assert_eq!(npc.class_name.to_string(), "Commoner");
+        assert!(warnings.contains(&"class: unrecognized \"5 / 3\", using \"Commoner\"".to_string()));
+        assert!(!warnings.iter().any(|w| w.contains("empty")));
+    }
+
+    fn coerced(value: Value, fields: &[Field]) -> (Map<String, Value>, Vec<String>) {
+        let mut warnings = Vec::new();
+        let Value::Object(mut obj) = value else { panic!("not an object") };
+        coerce_object(&mut obj, fields, "", &mut warnings);
+        (obj, warnings)
+    }
+
+    #[test]
+    fn string_where_a_list_is_expected_is_wrapped() {
+        let (obj, warnings) = coerced(json!({"languages": "Common", "attacks": "Dagger"}), NPC_FIELDS);
+        assert_eq!(obj["languages"], json!(["Common"]));
+        assert_eq!(obj["attacks"], json!([]));
+        assert!(warnings.contains(&"languages: expected a list, wrapped a string".to_string()));
+        assert!(warnings.contains(&"attacks: expected a list, got a string; using empty list".to_string()));
+    }
+
+    #[test]
+    fn scalar_where_an_object_is_expected_gets_defaults_or_is_dropped() {
+        let (obj, warnings) = coerced(json!({"appearance": "tall", "spells": 3}), NPC_FIELDS);
+        let appearance = obj["appearance"].as_object().unwrap();
+        assert_eq!(appearance["age"], json!(0));
+        assert_eq!(appearance["eyes"], json!(""));
+        assert_eq!(obj["spells"], Value::Null);
+        assert!(warnings.contains(&"appearance: expected an object, got a string; using default".to_string()));
+        assert!(warnings.contains(&"spells: expected an object, got a number; dropped".to_string()));
+        // The fields filled in under the replaced object aren't reported one by one
+        assert!(!warnings.iter().any(|w| w.starts_with("appearance.")));
+    }
+
+    #[test]
+    fn missing_required_fields_get_defaults_and_optional_ones_stay_missing() {
+        let (obj, warnings) = coerced(json!({"name": "Longsword", "damage": ["1d8", "2"]}), ATTACK);
+        assert_eq!(obj["damage"], json!("1d8 + 2"));
+        assert_eq!(obj["attack_bonus"], json!(0));
+        assert_eq!(obj["damage_type"], json!(""));
+        assert!(!obj.contains_key("range"));
+        assert!(warnings.contains(&"attack_bonus: missing, using default".to_string()));
+        assert!(warnings.contains(&"damage_type: missing, using default".to_string()));
+        assert!(!warnings.iter().any(|w| w.starts_with("range") || w.starts_with("properties")));
+    }
+}
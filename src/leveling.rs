@@ -0,0 +1,885 @@
+//! `npcforge levelup` and `npcforge leveldown`: move a saved NPC to another total level.
+//!
+//! The rules-bound numbers are recomputed here: hit points (keeping whatever the NPC had on top
+//! of the average, such as the Tough feat), hit dice, proficiency bonus, skills and saves, spell
+//! slots, save DC, and the attack bonuses, damage and armor class that follow the ability scores. The choices — features and feats gained or lost, new
+//! spells, a subclass, ability score improvements and an optional "what happened since"
+//! addendum to the backstory — are left to the model, which sees the rest of the NPC.
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::export::{is_melee, ordinal};
+use crate::stats::proficiency_bonus_for_level;
+use crate::types::{Ability, Class, Classes};
+use crate::{AbilityScores, Attack, Feature, NPC, SpellSlots, Spellcasting, SpellsByLevel, library, ollama, output, schema};
+
+#[derive(clap::Args, Debug)]
+pub struct LevelArgs {
+    /// NPC JSON file
+    file: PathBuf,
+
+    /// Total character level to end up at (1-20)
+    #[arg(long)]
+    to: u8,
+
+    /// Class to add levels to or take them from (default: the first class when leveling up, the
+    /// last when leveling down). Leveling up in a class the NPC doesn't have multiclasses into it.
+    #[arg(long)]
+    class: Option<String>,
+
+    /// Ask the model for a short "what happened since" paragraph to append to the backstory
+    #[arg(long)]
+    addendum: bool,
+
+    /// Only recompute the numbers; don't ask the model for features, spells or an addendum
+    #[arg(long, conflicts_with = "addendum")]
+    numbers_only: bool,
+
+    /// Library database to update (default: $NPCFORGE_LIBRARY, or npcforge/library.db in the user data directory)
+    #[arg(long, value_name = "FILE")]
+    pub library: Option<PathBuf>,
+
+    /// Don't update the library
+    #[arg(long, conflicts_with = "library")]
+    pub no_library: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+/// How a class's levels count towards spell slots.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Progression {
+    Full,
+    /// Paladin, Ranger, Artificer: half, rounded up
+    Half,
+    /// Eldritch Knight and Arcane Trickster: a third, from 3rd level
+    Third,
+    /// Warlock Pact Magic, which has its own slots
+    Pact,
+    None,
+}
+
+fn progression(class: &Class, subclass: Option<&str>) -> Progression {
+    let subclass = subclass.unwrap_or_default().to_lowercase();
+    match class {
+        Class::Bard | Class::Cleric | Class::Druid | Class::Sorcerer | Class::Wizard => Progression::Full,
+        Class::Artificer | Class::Paladin | Class::Ranger => Progression::Half,
+        Class::Warlock => Progression::Pact,
+        Class::Fighter if subclass.contains("eldritch knight") => Progression::Third,
+        Class::Rogue if subclass.contains("arcane trickster") => Progression::Third,
+        _ => Progression::None,
+    }
+}
+
+/// Spell slots by caster level, from the multiclass spellcaster table.
+const SLOTS_BY_CASTER_LEVEL: [[u8; 9]; 20] = [
+    [2, 0, 0, 0, 0, 0, 0, 0, 0],
+    [3, 0, 0, 0, 0, 0, 0, 0, 0],
+    [4, 2, 0, 0, 0, 0, 0, 0, 0],
+    [4, 3, 0, 0, 0, 0, 0, 0, 0],
+    [4, 3, 2, 0, 0, 0, 0, 0, 0],
+    [4, 3, 3, 0, 0, 0, 0, 0, 0],
+    [4, 3, 3, 1, 0, 0, 0, 0, 0],
+    [4, 3, 3, 2, 0, 0, 0, 0, 0],
+    [4, 3, 3, 3, 1, 0, 0, 0, 0],
+    [4, 3, 3, 3, 2, 0, 0, 0, 0],
+    [4, 3, 3, 3, 2, 1, 0, 0, 0],
+    [4, 3, 3, 3, 2, 1, 0, 0, 0],
+    [4, 3, 3, 3, 2, 1, 1, 0, 0],
+    [4, 3, 3, 3, 2, 1, 1, 0, 0],
+    [4, 3, 3, 3, 2, 1, 1, 1, 0],
+    [4, 3, 3, 3, 2, 1, 1, 1, 0],
+    [4, 3, 3, 3, 2, 1, 1, 1, 1],
+    [4, 3, 3, 3, 3, 1, 1, 1, 1],
+    [4, 3, 3, 3, 3, 2, 1, 1, 1],
+    [4, 3, 3, 3, 3, 2, 2, 1, 1],
+];
+
+/// `(slots, slot level)` of Warlock Pact Magic.
+fn pact_slots(warlock_level: u8) -> (u8, u8) {
+    match warlock_level {
+        0 => (0, 0),
+        1 => (1, 1),
+        2 => (2, 1),
+        3..=4 => (2, 2),
+        5..=6 => (2, 3),
+        7..=8 => (2, 4),
+        9..=10 => (2, 5),
+        11..=16 => (3, 5),
+        _ => (4, 5),
+    }
+}
+
+/// Spell slots for the given class levels, or `None` if none of the classes casts spells.
+///
+/// A single spellcasting class uses its own table (half and third casters round up); several
+/// combine on the multiclass table (half round up, third round down). Pact Magic slots are
+/// only recorded for characters with no other slots, as the file has no separate place for them.
+fn spell_slots(levels: &[(Class, u8)], subclass: Option<&str>) -> Option<SpellSlots> {
+    let casting: Vec<(Progression, u8)> = levels
+        .iter()
+        .map(|(class, level)| (progression(class, subclass), *level))
+        .filter(|(p, _)| !matches!(p, Progression::None | Progression::Pact))
+        .collect();
+    let warlock_level = levels
+        .iter()
+        .filter(|(class, _)| progression(class, subclass) == Progression::Pact)
+        .map(|(_, level)| *level)
+        .sum();
+    if casting.is_empty() && warlock_level == 0 {
+        return None;
+    }
+
+    let single = casting.len() == 1;
+    let caster_level: u8 = casting
+        .iter()
+        .map(|&(p, level)| match p {
+            Progression::Full => level,
+            Progression::Half => level.div_ceil(2),
+            Progression::Third if level < 3 => 0,
+            Progression::Third if single => level.div_ceil(3),
+            Progression::Third => level / 3,
+            Progression::Pact | Progression::None => 0,
+        })
+        .sum();
+
+    let mut slots = [0u8; 9];
+    if caster_level > 0 {
+        slots = SLOTS_BY_CASTER_LEVEL[(caster_level.min(20) - 1) as usize];
+    } else {
+        let (count, level) = pact_slots(warlock_level);
+        if level > 0 {
+            slots[(level - 1) as usize] = count;
+        }
+    }
+    Some(SpellSlots {
+        level_1: slots[0],
+        level_2: slots[1],
+        level_3: slots[2],
+        level_4: slots[3],
+        level_5: slots[4],
+        level_6: slots[5],
+        level_7: slots[6],
+        level_8: slots[7],
+        level_9: slots[8],
+    })
+}
+
+/// Highest spell level with at least one slot, counting Pact Magic.
+fn highest_slot_level(levels: &[(Class, u8)], slots: &SpellSlots) -> u8 {
+    let pact = levels
+        .iter()
+        .filter(|(class, _)| *class == Class::Warlock)
+        .map(|(_, level)| pact_slots(*level).1)
+        .max()
+        .unwrap_or(0);
+    (1..=9).rev().find(|&level| slots.slots(level) > 0).unwrap_or(0).max(pact)
+}
+
+/// Average hit points for the class levels: the full hit die of the class the character started
+/// in at 1st level, then half the die plus one for every other level, each with the Constitution
+/// modifier. The starting class is passed separately because leveling down can remove it.
+fn average_hit_points(levels: &[(Class, u8)], starting: &Class, con_mod: i8) -> i32 {
+    let per_level = |die: i32| (die / 2 + 1 + con_mod as i32).max(1);
+    let total: i32 = levels.iter().map(|(class, level)| per_level(class.hit_die() as i32) * *level as i32).sum();
+    let die = starting.hit_die() as i32;
+    total + (die + con_mod as i32).max(1) - per_level(die)
+}
+
+/// "3d8+2d10", one term per class.
+fn hit_dice(levels: &[(Class, u8)]) -> String {
+    levels.iter().map(|(class, level)| format!("{}d{}", level, class.hit_die())).collect::<Vec<_>>().join("+")
+}
+
+/// Each class with its levels, in the NPC's class order.
+fn current_levels(npc: &NPC) -> Result<Vec<(Class, u8)>> {
+    if npc.class_name.0.len() > 1 && npc.class_levels.is_none() {
+        bail!(
+            "{} is multiclassed ({}) but has no class_levels, so there is no telling how the levels are split",
+            npc.name,
+            npc.class_name
+        );
+    }
+    let levels: Vec<(Class, u8)> = npc.class_name.0.iter().map(|class| (class.clone(), npc.levels_in(class))).collect();
+    let total: u32 = levels.iter().map(|(_, level)| *level as u32).sum();
+    if total != npc.level as u32 {
+        bail!(
+            "{}'s class levels ({}) don't add up to its level {}; fix the file first (see `npcforge validate`)",
+            npc.name,
+            npc.class_summary(),
+            npc.level
+        );
+    }
+    Ok(levels)
+}
+
+/// The class levels after moving to total level `to`.
+fn target_levels(current: &[(Class, u8)], total: u8, to: u8, class: Option<&Class>, direction: Direction) -> Result<Vec<(Class, u8)>> {
+    let mut levels = current.to_vec();
+    match direction {
+        Direction::Up => {
+            let delta = to - total;
+            let class = class.unwrap_or(&current[0].0);
+            match levels.iter().position(|(c, _)| c == class) {
+                Some(i) => levels[i].1 += delta,
+                None if levels.len() >= 3 => bail!("Multiclass characters are limited to 3 classes"),
+                None => levels.push((class.clone(), delta)),
+            }
+        }
+        Direction::Down => {
+            let mut delta = total - to;
+            match class {
+                Some(class) => {
+                    let Some((_, level)) = levels.iter_mut().find(|(c, _)| c == class) else {
+                        bail!("The NPC has no {} levels to remove", class);
+                    };
+                    if *level < delta {
+                        bail!("The NPC only has {} {} level(s), {} would have to go", level, class, delta);
+                    }
+                    *level -= delta;
+                }
+                // From the last class backwards, always keeping the first
+                None => {
+                    for i in (0..levels.len()).rev() {
+                        let keep = if i == 0 { 1 } else { 0 };
+                        let take = delta.min(levels[i].1 - keep);
+                        levels[i].1 -= take;
+                        delta -= take;
+                    }
+                }
+            }
+            levels.retain(|(_, level)| *level > 0);
+        }
+    }
+    Ok(levels)
+}
+
+/// Set the class, class levels and total level, dropping the subclass of any class that is gone.
+fn apply_levels(npc: &mut NPC, levels: &[(Class, u8)]) {
+    let old_classes = std::mem::take(&mut npc.class_name.0);
+    if let Some(subclass) = &npc.subclass {
+        let parts: Vec<&str> = subclass.split('/').map(str::trim).collect();
+        if parts.len() == old_classes.len() && old_classes.len() > 1 {
+            let kept: Vec<&str> = old_classes
+                .iter()
+                .zip(parts)
+                .filter(|(class, _)| levels.iter().any(|(c, _)| c == *class))
+                .map(|(_, part)| part)
+                .collect();
+            npc.subclass = if kept.is_empty() { None } else { Some(kept.join("/")) };
+        }
+    }
+    // Every class picks its subclass at 3rd level in the 2024 rules
+    if levels.iter().all(|(_, level)| *level < 3) {
+        npc.subclass = None;
+    }
+    npc.class_name = Classes(levels.iter().map(|(class, _)| class.clone()).collect());
+    npc.level = levels.iter().map(|(_, level)| level).sum();
+    npc.class_levels = if levels.len() > 1 { Some(levels.iter().cloned().collect::<BTreeMap<_, _>>()) } else { None };
+}
+
+fn describe(levels: &[(Class, u8)]) -> String {
+    levels.iter().map(|(class, level)| format!("{} {}", class, level)).collect::<Vec<_>>().join(" / ")
+}
+
+fn describe_slots(slots: Option<&SpellSlots>) -> String {
+    let Some(slots) = slots else { return "none".to_string() };
+    let parts: Vec<String> = (1..=9)
+        .filter(|&level| slots.slots(level) > 0)
+        .map(|level| format!("{}: {}", ordinal(level), slots.slots(level)))
+        .collect();
+    if parts.is_empty() { "none".to_string() } else { parts.join(", ") }
+}
+
+fn create_level_prompt(npc: &NPC, from: &[(Class, u8)], to: &[(Class, u8)], slots: Option<&SpellSlots>, direction: Direction, addendum: bool) -> String {
+    let mut context = serde_json::to_value(npc).unwrap_or_default();
+    if let Value::Object(obj) = &mut context {
+        obj.remove("schema_version");
+    }
+    let mut prompt = format!(
+        "You are a D&D 2024 character generator. This existing character is going from {} to {} \
+        (total level {} to {}). Everything about them stays the same except what the level change requires.\n\n{}\n\n\
+        Their spell slots at the new level: {}.\n\n\
+        Output ONLY valid JSON (no additional text) with these fields:\n",
+        describe(from),
+        describe(to),
+        from.iter().map(|(_, l)| l).sum::<u8>(),
+        to.iter().map(|(_, l)| l).sum::<u8>(),
+        serde_json::to_string_pretty(&context).unwrap_or_default(),
+        describe_slots(slots)
+    );
+    match direction {
+        Direction::Up => {
+            prompt.push_str(
+                r#"- "new_features": class features, subclass features and feats gained with the new levels, as [{"name": "...", "description": "..."}]. Do not repeat features they already have.
+- "new_spells": spells learned with the new levels, as {"cantrips": [], "level_1": [], ...}. Only use spell levels they have slots for, and do not repeat spells they already know. Use {} for non-casters.
+- "subclass": only if a class reaches the level where it gains a subclass: the subclasses of all their classes, "/"-separated in class order, keeping the existing ones
+- "ability_scores": only if they gain an Ability Score Improvement: all six scores afterwards, as {"strength": 10, ...}
+"#,
+            );
+        }
+        Direction::Down => {
+            prompt.push_str(
+                r#"- "removed_features": names of the features and feats they lose with the removed levels, as ["...", "..."]
+- "removed_spells": names of the spells they no longer know, as ["...", "..."]
+- "ability_scores": only if they lose an Ability Score Improvement: all six scores afterwards, as {"strength": 10, ...}
+"#,
+            );
+        }
+    }
+    if addendum {
+        prompt.push_str(match direction {
+            Direction::Up => "- \"backstory_addendum\": one short paragraph on what happened to them since, explaining how they grew in power\n",
+            Direction::Down => "- \"backstory_addendum\": one short paragraph on what happened to them since, explaining how they lost their edge\n",
+        });
+    }
+    prompt
+}
+
+/// Take `key` from the model's answer, recording a warning if it is there but malformed.
+fn take<T: serde::de::DeserializeOwned>(answer: &mut serde_json::Map<String, Value>, key: &str, warnings: &mut Vec<String>) -> Option<T> {
+    let value = answer.remove(key)?;
+    if value.is_null() {
+        return None;
+    }
+    match serde_json::from_value(value) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            warnings.push(format!("{}: ignored, not in the expected shape ({})", key, e));
+            None
+        }
+    }
+}
+
+/// What the level change brought, for the summary.
+#[derive(Default)]
+struct Changes {
+    features_added: Vec<String>,
+    features_removed: Vec<String>,
+    spells_added: Vec<String>,
+    spells_removed: Vec<String>,
+    subclass: Option<String>,
+    ability_scores: bool,
+    addendum: bool,
+}
+
+/// Apply the model's choices to `npc`. Only the fields asked for in `direction` are used, and
+/// new spells only up to `max_spell_level`.
+fn apply_answer(
+    npc: &mut NPC,
+    mut answer: serde_json::Map<String, Value>,
+    direction: Direction,
+    max_spell_level: u8,
+    changes: &mut Changes,
+    warnings: &mut Vec<String>,
+) {
+    match direction {
+        Direction::Up => {
+            for feature in take::<Vec<Feature>>(&mut answer, "new_features", warnings).unwrap_or_default() {
+                if !npc.features.iter().any(|f| f.name.eq_ignore_ascii_case(&feature.name)) {
+                    changes.features_added.push(feature.name.clone());
+                    npc.features.push(feature);
+                }
+            }
+
+            if let Some(new_spells) = take::<SpellsByLevel>(&mut answer, "new_spells", warnings)
+                && let Some(spells) = npc.spells.as_mut()
+            {
+                for (level, list) in new_spells.by_level() {
+                    if level > max_spell_level && !list.is_empty() {
+                        warnings.push(format!("new_spells: ignored {}, which need {} level slots", list.join(", "), ordinal(level)));
+                        continue;
+                    }
+                    for spell in list {
+                        let known = spells.spells_known.level_mut(level);
+                        if !known.iter().any(|s| s.eq_ignore_ascii_case(spell)) {
+                            known.push(spell.clone());
+                            changes.spells_added.push(spell.clone());
+                        }
+                    }
+                }
+            }
+
+            if let Some(subclass) = take::<String>(&mut answer, "subclass", warnings).filter(|s| !s.trim().is_empty()) {
+                // Existing subclasses stay; only accept an answer that keeps them and adds to them
+                let keeps_existing = npc.subclass.as_deref().is_none_or(|old| subclass.starts_with(old));
+                if keeps_existing && npc.subclass.as_deref() != Some(subclass.as_str()) {
+                    changes.subclass = Some(subclass.clone());
+                    npc.subclass = Some(subclass);
+                } else if !keeps_existing {
+                    warnings.push(format!(
+                        "subclass: ignored \"{}\", which would replace \"{}\"",
+                        subclass,
+                        npc.subclass.as_deref().unwrap_or_default()
+                    ));
+                }
+            }
+        }
+        Direction::Down => {
+            for name in take::<Vec<String>>(&mut answer, "removed_features", warnings).unwrap_or_default() {
+                let before = npc.features.len();
+                npc.features.retain(|f| !f.name.eq_ignore_ascii_case(&name));
+                if npc.features.len() < before {
+                    changes.features_removed.push(name);
+                }
+            }
+
+            for name in take::<Vec<String>>(&mut answer, "removed_spells", warnings).unwrap_or_default() {
+                let Some(spells) = npc.spells.as_mut() else { break };
+                let mut removed = false;
+                for level in 0..=9 {
+                    let list = spells.spells_known.level_mut(level);
+                    let before = list.len();
+                    list.retain(|s| !s.eq_ignore_ascii_case(&name));
+                    removed |= list.len() < before;
+                }
+                if removed {
+                    changes.spells_removed.push(name);
+                }
+            }
+        }
+    }
+
+    if let Some(scores) = take::<AbilityScores>(&mut answer, "ability_scores", warnings) {
+        let all = [scores.strength, scores.dexterity, scores.constitution, scores.intelligence, scores.wisdom, scores.charisma];
+        if all.iter().all(|score| (1..=30).contains(score)) {
+            changes.ability_scores = Ability::ALL.iter().zip(all).any(|(&a, score)| npc.ability_score(a) != score);
+            npc.ability_scores = scores;
+        } else {
+            warnings.push("ability_scores: ignored, scores out of range".to_string());
+        }
+    }
+
+    if let Some(addendum) = take::<String>(&mut answer, "backstory_addendum", warnings).filter(|s| !s.trim().is_empty()) {
+        npc.backstory = format!("{}\n\n{}", npc.backstory.trim_end(), addendum.trim());
+        changes.addendum = true;
+    }
+}
+
+/// The NPC's spellcasting, set up with the ability of its first spellcasting class if it had none.
+fn ensure_spellcasting<'a>(npc: &'a mut NPC, levels: &[(Class, u8)]) -> &'a mut Spellcasting {
+    let ability = levels
+        .iter()
+        .filter(|(class, _)| progression(class, npc.subclass.as_deref()) != Progression::None)
+        .find_map(|(class, _)| class.spellcasting_ability())
+        // Eldritch Knights and Arcane Tricksters
+        .or(Some(Ability::Intelligence));
+    let spells = npc.spells.get_or_insert_with(|| Spellcasting {
+        spellcasting_ability: ability,
+        spell_save_dc: 0,
+        spell_attack_bonus: 0,
+        spell_slots: None,
+        spells_known: SpellsByLevel::default(),
+    });
+    if spells.spellcasting_ability.is_none() {
+        spells.spellcasting_ability = ability;
+    }
+    spells
+}
+
+/// The numbers `recompute` starts from, taken before the levels or ability scores change.
+pub struct Before {
+    levels: Vec<(Class, u8)>,
+    /// Class whose full hit die the recomputed 1st level gets
+    starting: Class,
+    modifiers: [i8; 6],
+    pb: i8,
+    had_casting: bool,
+}
+
+impl Before {
+    pub fn of(npc: &NPC) -> Result<Before> {
+        let levels = current_levels(npc)?;
+        Ok(Before {
+            had_casting: spell_slots(&levels, npc.subclass.as_deref()).is_some(),
+            starting: levels[0].0.clone(),
+            modifiers: modifiers(npc),
+            pb: proficiency_bonus_for_level(npc.level),
+            levels,
+        })
+    }
+
+    /// For a sheet whose classes are replaced outright rather than leveled: recompute the hit
+    /// points as if the character had started in `class`.
+    pub fn starting_in(self, class: Class) -> Before {
+        Before { starting: class, ..self }
+    }
+}
+
+/// The six ability modifiers, in `Ability::ALL` order.
+fn modifiers(npc: &NPC) -> [i8; 6] {
+    Ability::ALL.map(|ability| npc.ability_mod(ability))
+}
+
+fn modifier(modifiers: &[i8; 6], ability: Ability) -> i8 {
+    let i = Ability::ALL.iter().position(|&a| a == ability).unwrap_or(0);
+    modifiers[i]
+}
+
+/// The modifier an attack adds to hit and to damage: the spellcasting ability for spells, the
+/// better of Strength and Dexterity for finesse weapons, Dexterity for ranged weapons that aren't
+/// thrown, and Strength otherwise.
+fn attack_modifier(npc: &NPC, attack: &Attack, modifiers: &[i8; 6]) -> Option<i8> {
+    if npc.knows_spell(&attack.name) {
+        let ability = npc.spells.as_ref().and_then(|s| s.spellcasting_ability)?;
+        return Some(modifier(modifiers, ability));
+    }
+    let has = |property: &str| attack.properties.iter().any(|p| p.to_lowercase().contains(property));
+    let strength = modifier(modifiers, Ability::Strength);
+    let dexterity = modifier(modifiers, Ability::Dexterity);
+    Some(if has("finesse") {
+        strength.max(dexterity)
+    } else if !is_melee(attack) && !has("thrown") {
+        dexterity
+    } else {
+        strength
+    })
+}
+
+/// Move the flat part of a damage expression by `delta`: "1d8+3" becomes "1d8+4", "1d8" becomes
+/// "1d8+1". Spell damage without a flat part (`add_missing` false) is left alone.
+fn shift_damage(expression: &str, delta: i8, add_missing: bool) -> String {
+    let compact: String = expression.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut terms: Vec<i32> = Vec::new();
+    let mut dice: Vec<String> = Vec::new();
+    for term in compact.replace('-', "+-").split('+').filter(|t| !t.is_empty()) {
+        match term.parse::<i32>() {
+            Ok(n) => terms.push(n),
+            Err(_) if term.contains(['d', 'D']) => dice.push(term.to_string()),
+            // Not plain dice and numbers; leave it to the reader
+            Err(_) => return expression.to_string(),
+        }
+    }
+    if dice.is_empty() || (terms.is_empty() && !add_missing) {
+        return expression.to_string();
+    }
+    let flat = terms.iter().sum::<i32>() + delta as i32;
+    let mut shifted = dice.join("+").replace("+-", "-");
+    if flat != 0 {
+        shifted.push_str(&format!("{:+}", flat));
+    }
+    shifted
+}
+
+/// How far Armor Class moves with the ability modifiers: Dexterity in full unarmored or in light
+/// armor, up to +2 in medium armor, not at all in heavy armor, plus Constitution (Barbarian) or
+/// Wisdom (Monk) for Unarmored Defense.
+fn armor_class_delta(npc: &NPC, before: &[i8; 6], after: &[i8; 6]) -> i8 {
+    let armor: Vec<String> = npc.equipment.armor.iter().map(|a| a.to_lowercase()).collect();
+    let wears = |names: &[&str]| armor.iter().any(|a| names.iter().any(|n| a.contains(n)));
+    let delta = |ability: Ability| modifier(after, ability) - modifier(before, ability);
+    if wears(&["hide", "chain shirt", "scale mail", "breastplate", "half plate", "spiked armor"]) {
+        let capped = |modifiers: &[i8; 6]| modifier(modifiers, Ability::Dexterity).min(2);
+        return capped(after) - capped(before);
+    }
+    if wears(&["ring mail", "chain mail", "splint", "plate"]) {
+        return 0;
+    }
+    let mut total = delta(Ability::Dexterity);
+    if !wears(&["padded", "leather", "studded"]) {
+        if npc.class_name.contains(&Class::Barbarian) {
+            total += delta(Ability::Constitution);
+        } else if npc.class_name.contains(&Class::Monk) && !wears(&["shield"]) {
+            total += delta(Ability::Wisdom);
+        }
+    }
+    total
+}
+
+/// Recompute everything that follows from the class levels and ability scores. Returns the known
+/// spells dropped because no slot is left for their level.
+pub fn recompute(npc: &mut NPC, before: &Before) -> Result<Vec<String>> {
+    let new_levels = current_levels(npc)?;
+    let after = modifiers(npc);
+    let con = modifier(&after, Ability::Constitution);
+    let old_con = modifier(&before.modifiers, Ability::Constitution);
+    let extra = npc.hit_points.max as i32 - average_hit_points(&before.levels, &before.levels[0].0, old_con);
+    let max = (average_hit_points(&new_levels, &before.starting, con) + extra).clamp(1, u16::MAX as i32) as u16;
+    npc.hit_points.current = if npc.hit_points.current >= npc.hit_points.max { max } else { npc.hit_points.current.min(max) };
+    npc.hit_points.max = max;
+    npc.hit_points.hit_dice = hit_dice(&new_levels);
+
+    npc.initiative += modifier(&after, Ability::Dexterity) - modifier(&before.modifiers, Ability::Dexterity);
+    let armor_class = npc.armor_class as i16 + armor_class_delta(npc, &before.modifiers, &after) as i16;
+    npc.armor_class = armor_class.clamp(0, u8::MAX as i16) as u8;
+    npc.complete_derived_fields();
+    let pb = proficiency_bonus_for_level(npc.level);
+
+    let shifts: Vec<(i8, bool)> = npc
+        .attacks
+        .iter()
+        .map(|attack| {
+            let delta = match (attack_modifier(npc, attack, &before.modifiers), attack_modifier(npc, attack, &after)) {
+                (Some(old), Some(new)) => new - old,
+                _ => 0,
+            };
+            (delta, !npc.knows_spell(&attack.name))
+        })
+        .collect();
+    for (attack, (delta, weapon)) in npc.attacks.iter_mut().zip(shifts) {
+        attack.attack_bonus += pb - before.pb + delta;
+        if delta != 0 {
+            attack.damage = shift_damage(&attack.damage, delta, weapon);
+        }
+    }
+
+    let mut dropped = Vec::new();
+    match spell_slots(&new_levels, npc.subclass.as_deref()) {
+        Some(slots) => {
+            let highest = highest_slot_level(&new_levels, &slots);
+            let spells = ensure_spellcasting(npc, &new_levels);
+            spells.spell_slots = Some(slots);
+            for level in highest + 1..=9 {
+                dropped.append(spells.spells_known.level_mut(level));
+            }
+        }
+        // Spells from a feat or species trait stay; lost spellcasting classes take theirs along
+        None if before.had_casting => {
+            if let Some(spells) = npc.spells.take() {
+                dropped.extend(spells.spells_known.by_level().into_iter().flat_map(|(_, list)| list.iter().cloned()));
+            }
+        }
+        None => {}
+    }
+    let modifier = npc.spells.as_ref().and_then(|s| s.spellcasting_ability).map(|ability| npc.ability_mod(ability));
+    if let (Some(spells), Some(modifier)) = (npc.spells.as_mut(), modifier) {
+        spells.spell_save_dc = (8 + pb + modifier).max(0) as u8;
+        spells.spell_attack_bonus = pb + modifier;
+    }
+    Ok(dropped)
+}
+
+/// `npcforge levelup` / `leveldown`: move the NPC in `args.file` to level `args.to` and
+/// rewrite the file, keeping the previous version as `<file>.bak`.
+pub async fn change_level(args: LevelArgs, direction: Direction, library: Option<&mut library::Library>) -> Result<()> {
+    let path: &Path = &args.file;
+    let (mut npc, _) = schema::load_npc_file(path)?;
+    println!("=== NPCForge - Leveling {} {} ===\n", npc.name, if direction == Direction::Up { "up" } else { "down" });
+
+    if !(1..=20).contains(&args.to) {
+        bail!("--to must be between 1 and 20");
+    }
+    match direction {
+        Direction::Up if args.to <= npc.level => bail!("{} is already level {}; use `leveldown` to lower it", npc.name, npc.level),
+        Direction::Down if args.to >= npc.level => bail!("{} is only level {}; use `levelup` to raise it", npc.name, npc.level),
+        _ => {}
+    }
+    let class = args.class.as_deref().map(str::parse::<Class>).transpose().map_err(anyhow::Error::msg)?;
+    if let Some(Class::Other(name)) = &class {
+        println!("⚠ Warning: \"{}\" is not an official class; treating it as homebrew", name);
+    }
+
+    let before = Before::of(&npc)?;
+    let old_levels = before.levels.clone();
+    let new_levels = target_levels(&old_levels, npc.level, args.to, class.as_ref(), direction)?;
+    let old_hp = npc.hit_points.max;
+
+    let mut changes = Changes::default();
+    let mut warnings = Vec::new();
+    if !args.numbers_only {
+        let slots = spell_slots(&new_levels, npc.subclass.as_deref());
+        let prompt = create_level_prompt(&npc, &old_levels, &new_levels, slots.as_ref(), direction, args.addendum);
+        println!("Choosing features and spells for {} with Ollama...\n", describe(&new_levels));
+        let client = ollama::client()?;
+        let output = ollama::generate(&client, &prompt, true).await?;
+        let answer: Value = serde_json::from_str(&output.text).context("The model's answer was not valid JSON")?;
+        let Value::Object(answer) = answer else {
+            bail!("The model's answer was not a JSON object");
+        };
+        apply_levels(&mut npc, &new_levels);
+        // A new spellcasting class needs somewhere to put its spells
+        let max_spell_level = match &slots {
+            Some(slots) => {
+                ensure_spellcasting(&mut npc, &new_levels);
+                highest_slot_level(&new_levels, slots)
+            }
+            None => 0,
+        };
+        apply_answer(&mut npc, answer, direction, max_spell_level, &mut changes, &mut warnings);
+    } else {
+        apply_levels(&mut npc, &new_levels);
+    }
+    for spell in recompute(&mut npc, &before)? {
+        if !changes.spells_removed.iter().any(|s| s.eq_ignore_ascii_case(&spell)) {
+            changes.spells_removed.push(spell);
+        }
+    }
+
+    let backup = output::rewrite(path, &npc)?;
+
+    println!("✓ {}: {} → {}", npc.name, describe(&old_levels), describe(&new_levels));
+    println!("  Hit points: {} → {} ({})", old_hp, npc.hit_points.max, npc.hit_points.hit_dice);
+    println!("  Proficiency bonus: {:+}", npc.proficiency_bonus);
+    if let Some(spells) = &npc.spells {
+        println!("  Spell slots: {}", describe_slots(spells.spell_slots.as_ref()));
+    }
+    if let Some(subclass) = &changes.subclass {
+        println!("  Subclass: {}", subclass);
+    }
+    if changes.ability_scores {
+        println!("  Ability scores changed");
+    }
+    for (label, names) in [
+        ("New features", &changes.features_added),
+        ("Removed features", &changes.features_removed),
+        ("New spells", &changes.spells_added),
+        ("Removed spells", &changes.spells_removed),
+    ] {
+        if !names.is_empty() {
+            println!("  {}: {}", label, names.join(", "));
+        }
+    }
+    if changes.addendum {
+        println!("  Backstory: added what happened since");
+    }
+    println!("✓ Saved to: {}", path.display());
+    println!("  Previous version: {}", backup.display());
+    if !warnings.is_empty() {
+        println!("⚠ Ignored {} problem(s) in the model output:", warnings.len());
+        for warning in &warnings {
+            println!("    - {}", warning);
+        }
+    }
+
+    if let Some(library) = library {
+        crate::add_to_library(library, &npc, Some(path));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shift_damage_moves_the_flat_part() {
+        assert_eq!(shift_damage("1d8+3", 1, true), "1d8+4");
+        assert_eq!(shift_damage("1d8 + 1", -1, true), "1d8");
+        assert_eq!(shift_damage("1d8", 1, true), "1d8+1");
+        assert_eq!(shift_damage("2d6-1", 2, true), "2d6+1");
+        assert_eq!(shift_damage("1d10", 1, false), "1d10");
+        assert_eq!(shift_damage("1d10+3", 1, false), "1d10+4");
+        assert_eq!(shift_damage("see text", 1, true), "see text");
+    }
+
+    #[test]
+    fn first_level_hit_die_stays_with_the_starting_class() {
+        let fighter_wizard = [(Class::Fighter, 1), (Class::Wizard, 4)];
+        // 10 + 4 x 4 with +0 Constitution
+        assert_eq!(average_hit_points(&fighter_wizard, &Class::Fighter, 0), 26);
+        // Dropping the Fighter level keeps the 1st-level bonus of the d10, not the d6
+        assert_eq!(average_hit_points(&[(Class::Wizard, 4)], &Class::Fighter, 0), 20);
+    }
+
+    fn seraphine() -> NPC {
+        serde_json::from_str(include_str!("../tests/fixtures/seraphine.json")).unwrap()
+    }
+
+    fn slot_list(slots: &SpellSlots) -> Vec<u8> {
+        (1..=9).map(|level| slots.slots(level)).collect()
+    }
+
+    #[test]
+    fn spell_slots_follow_the_caster_tables() {
+        let slots = |levels: &[(Class, u8)], subclass: Option<&str>| spell_slots(levels, subclass).map(|s| slot_list(&s));
+        assert_eq!(slots(&[(Class::Fighter, 5)], None), None);
+        // A lone half caster rounds up: Paladin 5 is a 3rd-level caster
+        assert_eq!(slots(&[(Class::Paladin, 5)], None), Some(vec![4, 2, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(slots(&[(Class::Paladin, 5), (Class::Sorcerer, 2)], None), Some(vec![4, 3, 2, 0, 0, 0, 0, 0, 0]));
+        // A lone third caster rounds up, a multiclassed one rounds down
+        assert_eq!(slots(&[(Class::Fighter, 4)], Some("Eldritch Knight")), Some(vec![3, 0, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(
+            slots(&[(Class::Fighter, 5), (Class::Wizard, 1)], Some("Eldritch Knight")),
+            Some(vec![3, 0, 0, 0, 0, 0, 0, 0, 0])
+        );
+        // Pact Magic is only recorded without other slots
+        assert_eq!(slots(&[(Class::Warlock, 5)], None), Some(vec![0, 0, 2, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(slots(&[(Class::Bard, 3), (Class::Warlock, 2)], None), Some(vec![4, 2, 0, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(pact_slots(11), (3, 5));
+    }
+
+    #[test]
+    fn multiclass_levels_need_class_levels() {
+        let mut npc = seraphine();
+        assert_eq!(current_levels(&npc).unwrap(), [(Class::Bard, 3), (Class::Warlock, 2)]);
+        npc.class_levels = None;
+        assert!(current_levels(&npc).is_err());
+    }
+
+    #[test]
+    fn target_levels_add_and_remove_class_levels() {
+        let current = [(Class::Bard, 3), (Class::Warlock, 2)];
+        let up = |to, class: Option<&Class>| target_levels(&current, 5, to, class, Direction::Up);
+        let down = |to, class: Option<&Class>| target_levels(&current, 5, to, class, Direction::Down);
+
+        assert_eq!(up(7, None).unwrap(), [(Class::Bard, 5), (Class::Warlock, 2)]);
+        assert_eq!(up(6, Some(&Class::Warlock)).unwrap(), [(Class::Bard, 3), (Class::Warlock, 3)]);
+        assert_eq!(up(6, Some(&Class::Rogue)).unwrap(), [(Class::Bard, 3), (Class::Warlock, 2), (Class::Rogue, 1)]);
+        let three = [(Class::Bard, 3), (Class::Warlock, 2), (Class::Rogue, 1)];
+        assert!(target_levels(&three, 6, 7, Some(&Class::Fighter), Direction::Up).is_err());
+
+        // Without a class, levels come off the last class first and the first class keeps one
+        assert_eq!(down(2, None).unwrap(), [(Class::Bard, 2)]);
+        assert_eq!(down(1, None).unwrap(), [(Class::Bard, 1)]);
+        assert_eq!(down(4, Some(&Class::Bard)).unwrap(), [(Class::Bard, 2), (Class::Warlock, 2)]);
+        assert!(down(2, Some(&Class::Warlock)).is_err());
+        assert!(down(4, Some(&Class::Rogue)).is_err());
+    }
+
+    #[test]
+    fn armor_class_follows_dexterity_by_armor_type() {
+        let mut npc = seraphine();
+        let before = [0, 2, 0, 0, 0, 0];
+        let after = [0, 4, 0, 0, 0, 0];
+        assert_eq!(armor_class_delta(&npc, &before, &after), 2);
+        npc.equipment.armor = vec!["Breastplate".to_string()];
+        assert_eq!(armor_class_delta(&npc, &before, &after), 0);
+        assert_eq!(armor_class_delta(&npc, &[0, 1, 0, 0, 0, 0], &after), 1);
+        npc.equipment.armor = vec!["Plate Armor".to_string()];
+        assert_eq!(armor_class_delta(&npc, &before, &after), 0);
+    }
+
+    #[test]
+    fn raising_dexterity_moves_weapon_attacks_armor_class_and_initiative() {
+        let mut npc = seraphine();
+        let before = Before::of(&npc).unwrap();
+        npc.ability_scores.dexterity = 16;
+        let dropped = recompute(&mut npc, &before).unwrap();
+
+        assert!(dropped.is_empty());
+        assert_eq!(npc.armor_class, 14);
+        assert_eq!(npc.initiative, 3);
+        assert_eq!(npc.hit_points.max, 33);
+        let rapier = &npc.attacks[0];
+        assert_eq!((rapier.attack_bonus, rapier.damage.as_str()), (6, "1d8+3"));
+        // Eldritch Blast runs on Charisma
+        let blast = npc.attacks.iter().find(|a| a.name == "Eldritch Blast").unwrap();
+        assert_eq!(blast.attack_bonus, 7);
+    }
+
+    #[test]
+    fn leveling_down_drops_hit_points_slots_and_out_of_reach_spells() {
+        let mut npc = seraphine();
+        let before = Before::of(&npc).unwrap();
+        let levels = target_levels(&before.levels, 5, 1, None, Direction::Down).unwrap();
+        apply_levels(&mut npc, &levels);
+        let dropped = recompute(&mut npc, &before).unwrap();
+
+        assert_eq!((npc.class_summary(), npc.class_levels.is_none()), ("Bard 1".to_string(), true));
+        // 8 + 1 for the d8 at 1st level with +1 Constitution
+        assert_eq!((npc.hit_points.max, npc.hit_points.hit_dice.as_str()), (9, "1d8"));
+        assert_eq!(dropped, ["Suggestion", "Invisibility"]);
+        let spells = npc.spells.as_ref().unwrap();
+        assert_eq!(slot_list(spells.spell_slots.as_ref().unwrap()), [2, 0, 0, 0, 0, 0, 0, 0, 0]);
+        // Proficiency bonus 3 -> 2
+        assert_eq!((spells.spell_save_dc, spells.spell_attack_bonus), (14, 6));
+        assert_eq!(npc.attacks[0].attack_bonus, 4);
+    }
+}
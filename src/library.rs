@@ -0,0 +1,563 @@
+//! The NPC library: an SQLite database holding every generated NPC (its full JSON plus indexed
+//! columns to filter on and a full-text index of the prose), and the `npcforge library` commands.
+
+use anyhow::{Context, Result, bail};
+use clap::Subcommand;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::types::{Alignment, Class};
+use crate::{NPC, export, schema};
+
+/// Bumped (with a migration in `Library::open`) whenever the tables below change.
+const SCHEMA_VERSION: i32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS npcs (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL COLLATE NOCASE,
+    species TEXT NOT NULL COLLATE NOCASE,
+    class TEXT NOT NULL,
+    subclass TEXT,
+    level INTEGER NOT NULL,
+    alignment TEXT NOT NULL COLLATE NOCASE,
+    role TEXT NOT NULL COLLATE NOCASE,
+    background TEXT NOT NULL,
+    file TEXT UNIQUE,
+    added TEXT NOT NULL DEFAULT (datetime('now')),
+    json TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS npcs_name ON npcs (name);
+CREATE INDEX IF NOT EXISTS npcs_species ON npcs (species);
+CREATE INDEX IF NOT EXISTS npcs_level ON npcs (level);
+CREATE INDEX IF NOT EXISTS npcs_alignment ON npcs (alignment);
+CREATE INDEX IF NOT EXISTS npcs_role ON npcs (role);
+
+-- One row per class, so multiclass NPCs are found under each of their classes
+CREATE TABLE IF NOT EXISTS npc_classes (
+    npc_id INTEGER NOT NULL REFERENCES npcs (id) ON DELETE CASCADE,
+    class TEXT NOT NULL COLLATE NOCASE,
+    level INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS npc_classes_class ON npc_classes (class);
+CREATE INDEX IF NOT EXISTS npc_classes_npc ON npc_classes (npc_id);
+
+-- Full-text index of the prose; rowid is the NPC's id
+CREATE VIRTUAL TABLE IF NOT EXISTS npc_text USING fts5 (name, backstory, personality, appearance);
+";
+
+/// `npcforge library` subcommands.
+#[derive(Subcommand, Debug)]
+pub enum LibraryCommand {
+    /// Add NPC files, or every NPC under a directory, to the library
+    Add {
+        /// NPC JSON files or directories (scanned recursively)
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// List NPCs in the library
+    List {
+        #[command(flatten)]
+        filter: Filter,
+    },
+    /// Search backstories, personalities and appearances
+    Search {
+        /// Words to search for (all must match; end a word with * to match prefixes)
+        query: String,
+
+        #[command(flatten)]
+        filter: Filter,
+    },
+    /// Print a stored NPC
+    Show {
+        /// Library ID or exact name
+        npc: String,
+
+        /// Print in this format instead of NPCForge JSON
+        #[arg(long = "to", value_enum)]
+        format: Option<export::Format>,
+    },
+    /// Remove NPCs from the library (their files are left alone)
+    Rm {
+        /// Library IDs
+        #[arg(required = true)]
+        ids: Vec<i64>,
+    },
+}
+
+/// Filters shared by `library list` and `library search`.
+#[derive(clap::Args, Debug)]
+pub struct Filter {
+    /// Only this species (e.g. "Dwarf")
+    #[arg(long, alias = "race")]
+    species: Option<String>,
+
+    /// Only NPCs with this class, including multiclass NPCs
+    #[arg(long)]
+    class: Option<String>,
+
+    /// Minimum total level
+    #[arg(long)]
+    min_level: Option<u8>,
+
+    /// Maximum total level
+    #[arg(long)]
+    max_level: Option<u8>,
+
+    /// Only this alignment ("CG", "lawful evil"), or any containing a word ("evil")
+    #[arg(long)]
+    alignment: Option<String>,
+
+    /// Only roles containing this text (e.g. "merchant")
+    #[arg(long)]
+    role: Option<String>,
+
+    /// Show at most this many NPCs
+    #[arg(long)]
+    limit: Option<u32>,
+}
+
+impl Filter {
+    /// SQL conditions on `npcs` and their parameters.
+    fn clauses(&self) -> (Vec<String>, Vec<SqlValue>) {
+        let mut clauses = Vec::new();
+        let mut params = Vec::new();
+        if let Some(species) = &self.species {
+            clauses.push("npcs.species = ?".to_string());
+            params.push(SqlValue::Text(species.trim().to_string()));
+        }
+        if let Some(class) = &self.class {
+            let class = class.parse::<Class>().map(|c| c.name().to_string()).unwrap_or_else(|_| class.clone());
+            clauses.push("EXISTS (SELECT 1 FROM npc_classes c WHERE c.npc_id = npcs.id AND c.class = ?)".to_string());
+            params.push(SqlValue::Text(class));
+        }
+        if let Some(min) = self.min_level {
+            clauses.push("npcs.level >= ?".to_string());
+            params.push(SqlValue::Integer(min.into()));
+        }
+        if let Some(max) = self.max_level {
+            clauses.push("npcs.level <= ?".to_string());
+            params.push(SqlValue::Integer(max.into()));
+        }
+        if let Some(alignment) = &self.alignment {
+            match alignment.parse::<Alignment>() {
+                Ok(Alignment::Other(word)) => {
+                    clauses.push("npcs.alignment LIKE ?".to_string());
+                    params.push(SqlValue::Text(format!("%{}%", word)));
+                }
+                Ok(parsed) => {
+                    clauses.push("npcs.alignment = ?".to_string());
+                    params.push(SqlValue::Text(parsed.to_string()));
+                }
+                Err(_) => {}
+            }
+        }
+        if let Some(role) = &self.role {
+            clauses.push("npcs.role LIKE ?".to_string());
+            params.push(SqlValue::Text(format!("%{}%", role.trim())));
+        }
+        (clauses, params)
+    }
+}
+
+/// One line of `library list`.
+struct Entry {
+    id: i64,
+    name: String,
+    species: String,
+    class: String,
+    alignment: String,
+    role: String,
+}
+
+/// What `Library::add` did with an NPC.
+pub enum Added {
+    New(i64),
+    /// The file was in the library already; its entry was refreshed
+    Updated(i64),
+}
+
+pub struct Library {
+    conn: Connection,
+}
+
+impl Library {
+    /// The library used when none is given: `$NPCFORGE_LIBRARY`, or `npcforge/library.db` in
+    /// the user's data directory.
+    pub fn default_path() -> PathBuf {
+        if let Some(path) = std::env::var_os("NPCFORGE_LIBRARY").filter(|p| !p.is_empty()) {
+            return PathBuf::from(path);
+        }
+        let data_dir = std::env::var_os("XDG_DATA_HOME")
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share")));
+        match data_dir {
+            Some(dir) => dir.join("npcforge").join("library.db"),
+            None => PathBuf::from("npcforge.db"),
+        }
+    }
+
+    /// Open the library at `path`, creating it if needed.
+    pub fn open(path: &Path) -> Result<Library> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).context(format!("Failed to create directory {}", dir.display()))?;
+        }
+        let conn = Connection::open(path).context(format!("Failed to open library {}", path.display()))?;
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            bail!(
+                "Library {} was created by a newer NPCForge (schema version {}); please upgrade",
+                path.display(),
+                version
+            );
+        }
+        conn.execute_batch(SCHEMA).context(format!("Failed to set up library {}", path.display()))?;
+        conn.execute_batch(&format!("PRAGMA user_version = {}; PRAGMA foreign_keys = ON;", SCHEMA_VERSION))?;
+        Ok(Library { conn })
+    }
+
+    /// Store `npc`. NPCs saved to a file are keyed by its path, so adding the same file again
+    /// updates its entry instead of duplicating it. NPCs without a file (printed to stdout) always
+    /// get a new entry: each is a separate generation, and nothing identifies a repeat.
+    pub fn add(&mut self, npc: &NPC, file: Option<&Path>) -> Result<Added> {
+        let json = serde_json::to_string(npc).context("Failed to serialize NPC to JSON")?;
+        let file = file.map(|f| fs::canonicalize(f).unwrap_or_else(|_| f.to_path_buf()).display().to_string());
+
+        let tx = self.conn.transaction()?;
+        let existing: Option<i64> = match &file {
+            Some(file) => tx.query_row("SELECT id FROM npcs WHERE file = ?", [file], |row| row.get(0)).optional()?,
+            None => None,
+        };
+        let id: i64 = tx.query_row(
+            "INSERT INTO npcs (name, species, class, subclass, level, alignment, role, background, file, json)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                ON CONFLICT (file) DO UPDATE SET name = excluded.name, species = excluded.species,
+                    class = excluded.class, subclass = excluded.subclass, level = excluded.level,
+                    alignment = excluded.alignment, role = excluded.role, background = excluded.background,
+                    json = excluded.json
+                RETURNING id",
+            params![
+                npc.name,
+                npc.race,
+                npc.class_summary(),
+                npc.subclass,
+                npc.level,
+                npc.alignment.to_string(),
+                npc.role,
+                npc.background,
+                file,
+                json
+            ],
+            |row| row.get(0),
+        )?;
+        tx.execute("DELETE FROM npc_classes WHERE npc_id = ?", [id])?;
+        tx.execute("DELETE FROM npc_text WHERE rowid = ?", [id])?;
+
+        for class in &npc.class_name.0 {
+            let level = match &npc.class_levels {
+                Some(levels) => levels.get(class).copied().unwrap_or(0),
+                None if npc.class_name.0.len() == 1 => npc.level,
+                None => 0,
+            };
+            tx.execute("INSERT INTO npc_classes (npc_id, class, level) VALUES (?, ?, ?)", params![id, class.name(), level])?;
+        }
+        let personality = &npc.personality;
+        let appearance = &npc.appearance;
+        tx.execute(
+            "INSERT INTO npc_text (rowid, name, backstory, personality, appearance) VALUES (?, ?, ?, ?, ?)",
+            params![
+                id,
+                npc.name,
+                npc.backstory,
+                [personality.traits.join(" "), personality.ideals.clone(), personality.bonds.clone(), personality.flaws.clone()]
+                    .join(" "),
+                [
+                    appearance.height.as_str(),
+                    &appearance.weight,
+                    &appearance.eyes,
+                    &appearance.hair,
+                    &appearance.skin,
+                    &appearance.distinguishing_features.join(" ")
+                ]
+                .join(" ")
+            ],
+        )?;
+        tx.commit()?;
+
+        Ok(if existing.is_some() { Added::Updated(id) } else { Added::New(id) })
+    }
+
+    /// NPCs matching `filter` (and `text`, if given: best matches first; otherwise by name).
+    fn find(&self, filter: &Filter, text: Option<&str>) -> Result<Vec<Entry>> {
+        let (mut clauses, mut values) = filter.clauses();
+        let mut sql = "SELECT npcs.id, npcs.name, npcs.species, npcs.class, npcs.alignment, npcs.role FROM npcs".to_string();
+        let order = match text {
+            Some(text) => {
+                sql.push_str(" JOIN npc_text ON npc_text.rowid = npcs.id");
+                clauses.push("npc_text MATCH ?".to_string());
+                values.push(SqlValue::Text(fts_query(text)));
+                "bm25(npc_text)"
+            }
+            None => "npcs.name, npcs.id",
+        };
+        if !clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&clauses.join(" AND "));
+        }
+        sql.push_str(&format!(" ORDER BY {}", order));
+        if let Some(limit) = filter.limit {
+            sql.push_str(&format!(" LIMIT {}", limit));
+        }
+
+        let mut statement = self.conn.prepare(&sql)?;
+        let rows = statement.query_map(params_from_iter(values.iter()), |row| {
+            Ok(Entry {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                species: row.get(2)?,
+                class: row.get(3)?,
+                alignment: row.get(4)?,
+                role: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    /// The NPC with library ID `key`, or the only one named `key`.
+    fn get(&self, key: &str) -> Result<(i64, NPC)> {
+        let found: Vec<(i64, String)> = match key.trim().parse::<i64>() {
+            Ok(id) => self
+                .conn
+                .query_row("SELECT id, json FROM npcs WHERE id = ?", [id], |row| Ok((row.get(0)?, row.get(1)?)))
+                .optional()?
+                .into_iter()
+                .collect(),
+            Err(_) => {
+                let mut statement = self.conn.prepare("SELECT id, json FROM npcs WHERE name = ? ORDER BY id")?;
+                let rows = statement.query_map([key.trim()], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect::<rusqlite::Result<Vec<_>>>()?
+            }
+        };
+        match found.as_slice() {
+            [] => bail!("No NPC \"{}\" in the library", key),
+            [(id, json)] => {
+                let value = serde_json::from_str(json).context(format!("NPC #{} is not valid JSON", id))?;
+                let (npc, _) = schema::upgrade_value(value).context(format!("Failed to load NPC #{}", id))?;
+                Ok((*id, npc))
+            }
+            several => {
+                let ids: Vec<String> = several.iter().map(|(id, _)| format!("#{}", id)).collect();
+                bail!("{} NPCs are named \"{}\" ({}); use an ID", several.len(), key, ids.join(", "))
+            }
+        }
+    }
+
+    /// Remove the NPC with library ID `id`, returning its name if it was there.
+    fn remove(&mut self, id: i64) -> Result<Option<String>> {
+        let tx = self.conn.transaction()?;
+        let name: Option<String> = tx.query_row("SELECT name FROM npcs WHERE id = ?", [id], |row| row.get(0)).optional()?;
+        tx.execute("DELETE FROM npc_text WHERE rowid = ?", [id])?;
+        tx.execute("DELETE FROM npcs WHERE id = ?", [id])?;
+        tx.commit()?;
+        Ok(name)
+    }
+}
+
+/// FTS5 query for free text: every word quoted (so punctuation is never query syntax), all of
+/// them required, and a trailing `*` kept as a prefix match.
+fn fts_query(text: &str) -> String {
+    let terms: Vec<String> = text
+        .split_whitespace()
+        .map(|word| {
+            let (word, prefix) = match word.strip_suffix('*') {
+                Some(stem) if !stem.is_empty() => (stem, "*"),
+                _ => (word, ""),
+            };
+            format!("\"{}\"{}", word.replace('"', "\"\""), prefix)
+        })
+        .collect();
+    terms.join(" ")
+}
+
+/// Print entries as an aligned table, followed by their count.
+fn print_entries(entries: &[Entry]) {
+    if entries.is_empty() {
+        println!("No matching NPCs.");
+        return;
+    }
+    let header = ["ID", "Name", "Species", "Class", "Alignment", "Role"];
+    let rows: Vec<[String; 6]> = entries
+        .iter()
+        .map(|e| {
+            [
+                format!("#{}", e.id),
+                e.name.clone(),
+                e.species.clone(),
+                e.class.clone(),
+                e.alignment.clone(),
+                e.role.clone(),
+            ]
+        })
+        .collect();
+    let mut widths = header.map(|h| h.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: &[&str]| {
+        let padded: Vec<String> = cells.iter().zip(widths).map(|(cell, width)| format!("{:<width$}", cell)).collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+    line(&header);
+    for row in &rows {
+        line(&row.each_ref().map(String::as_str));
+    }
+    println!("\n{} NPC(s)", entries.len());
+}
+
+/// Add a file, or every NPC under a directory, reporting each one.
+fn add_path(library: &mut Library, path: &Path, added: &mut u32, failed: &mut u32) -> Result<()> {
+    let npcs = if path.is_dir() {
+        schema::load_npc_directory(path)?
+    } else {
+        vec![(path.to_path_buf(), schema::load_npc_file(path).map(|(npc, _)| npc))]
+    };
+    for (file, npc) in npcs {
+        match npc.and_then(|npc| library.add(&npc, Some(&file)).map(|result| (npc, result))) {
+            Ok((npc, Added::New(id))) => {
+                println!("✓ #{} {} ({})", id, npc.name, file.display());
+                *added += 1;
+            }
+            Ok((npc, Added::Updated(id))) => {
+                println!("✓ #{} {} ({}, updated)", id, npc.name, file.display());
+                *added += 1;
+            }
+            Err(e) => {
+                eprintln!("✗ {}: {:#}", file.display(), e);
+                *failed += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `npcforge library`.
+pub fn run(path: &Path, command: LibraryCommand) -> Result<()> {
+    let mut library = Library::open(path)?;
+    match command {
+        LibraryCommand::Add { paths } => {
+            let mut added = 0;
+            let mut failed = 0;
+            for path in &paths {
+                if let Err(e) = add_path(&mut library, path, &mut added, &mut failed) {
+                    eprintln!("✗ {}: {:#}", path.display(), e);
+                    failed += 1;
+                }
+            }
+            println!("\nAdded {} NPC(s) to {}", added, path.display());
+            if failed > 0 {
+                println!("Failed: {}", failed);
+            }
+        }
+        LibraryCommand::List { filter } => {
+            print_entries(&library.find(&filter, None)?);
+        }
+        LibraryCommand::Search { query, filter } => {
+            if query.trim().is_empty() {
+                bail!("Search text is empty");
+            }
+            print_entries(&library.find(&filter, Some(&query))?);
+        }
+        LibraryCommand::Show { npc, format } => {
+            let (_, npc) = library.get(&npc)?;
+            let bytes = match format {
+                Some(format) => {
+                    let (bytes, warning) = format.render_checked(&npc);
+                    if let Some(warning) = warning {
+                        eprintln!("⚠ Warning: {}", warning);
+                    }
+                    bytes
+                }
+                None => {
+                    let mut json = serde_json::to_string_pretty(&npc).context("Failed to serialize NPC to JSON")?;
+                    json.push('\n');
+                    json.into_bytes()
+                }
+            };
+            std::io::stdout().write_all(&bytes).context("Failed to write to stdout")?;
+        }
+        LibraryCommand::Rm { ids } => {
+            for id in ids {
+                match library.remove(id)? {
+                    Some(name) => println!("✓ Removed #{} {}", id, name),
+                    None => eprintln!("✗ No NPC #{} in the library", id),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(json: &str) -> NPC {
+        schema::upgrade_value(serde_json::from_str(json).unwrap()).unwrap().0
+    }
+
+    fn filter() -> Filter {
+        Filter { species: None, class: None, min_level: None, max_level: None, alignment: None, role: None, limit: None }
+    }
+
+    fn names(entries: Vec<Entry>) -> Vec<String> {
+        entries.into_iter().map(|e| e.name).collect()
+    }
+
+    #[test]
+    fn adding_a_file_again_updates_its_entry() {
+        let mut library = Library::open(Path::new(":memory:")).unwrap();
+        let mut npc = fixture(include_str!("../tests/fixtures/seraphine.json"));
+        let file = Path::new("tests/fixtures/seraphine.json");
+        let Added::New(id) = library.add(&npc, Some(file)).unwrap() else { panic!("first add was not new") };
+        npc.role = "Smuggler".to_string();
+        assert!(matches!(library.add(&npc, Some(file)).unwrap(), Added::Updated(same) if same == id));
+        assert_eq!(library.get(&id.to_string()).unwrap().1.role, "Smuggler");
+
+        // Generations printed to stdout have no file, so each is its own entry
+        let Added::New(first) = library.add(&npc, None).unwrap() else { panic!("stdout NPC was not new") };
+        let Added::New(second) = library.add(&npc, None).unwrap() else { panic!("stdout NPC was not new") };
+        assert!(first != second && first != id);
+        assert_eq!(library.find(&filter(), None).unwrap().len(), 3);
+        assert!(library.get("Seraphine Voss").is_err(), "three NPCs share the name");
+    }
+
+    #[test]
+    fn filters_and_text_search_find_matching_npcs() {
+        let mut library = Library::open(Path::new(":memory:")).unwrap();
+        library.add(&fixture(include_str!("../tests/fixtures/seraphine.json")), None).unwrap();
+        library.add(&fixture(include_str!("../tests/fixtures/baseline.json")), None).unwrap();
+
+        assert_eq!(names(library.find(&filter(), None).unwrap()), ["Brakka Stonefist", "Seraphine Voss"]);
+        // Multiclass NPCs are found under each class
+        let warlocks = Filter { class: Some("warlocks".to_string()), ..filter() };
+        assert_eq!(names(library.find(&warlocks, None).unwrap()), ["Seraphine Voss"]);
+        let rogues = Filter { class: Some("Rogue".to_string()), ..filter() };
+        assert_eq!(names(library.find(&rogues, None).unwrap()), ["Brakka Stonefist"]);
+        let lawful = Filter { alignment: Some("lawful".to_string()), min_level: Some(6), ..filter() };
+        assert_eq!(names(library.find(&lawful, None).unwrap()), ["Brakka Stonefist"]);
+        let species = Filter { species: Some("half-elf".to_string()), ..filter() };
+        assert_eq!(names(library.find(&species, None).unwrap()), ["Seraphine Voss"]);
+
+        assert_eq!(names(library.find(&filter(), Some("playhouse*")).unwrap()), ["Seraphine Voss"]);
+        // Punctuation is never query syntax
+        assert_eq!(names(library.find(&filter(), Some("Ironhold-guard mine.")).unwrap()), ["Brakka Stonefist"]);
+        assert_eq!(names(library.find(&rogues, Some("playhouse*")).unwrap()), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,214 @@
+//! `--from <file> --lock <fields>`: generate a new NPC that keeps some fields of an existing one.
+//!
+//! The locked values go into the prompt as hard constraints, and since the model does not
+//! always honor them, they are compared against the result afterwards and put back where it
+//! drifted, with the rules-bound numbers recomputed to match.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::Value;
+
+use crate::{NPC, leveling};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LockedField {
+    Name,
+    #[value(alias = "species")]
+    Race,
+    /// Class, class levels and total level
+    Class,
+    Subclass,
+    Alignment,
+    Role,
+    Background,
+    AbilityScores,
+    Appearance,
+    Personality,
+    Backstory,
+    Equipment,
+}
+
+impl LockedField {
+    /// JSON keys the field covers.
+    fn keys(self) -> &'static [&'static str] {
+        match self {
+            LockedField::Name => &["name"],
+            LockedField::Race => &["race"],
+            LockedField::Class => &["class", "class_levels", "level"],
+            LockedField::Subclass => &["subclass"],
+            LockedField::Alignment => &["alignment"],
+            LockedField::Role => &["role"],
+            LockedField::Background => &["background"],
+            LockedField::AbilityScores => &["ability_scores"],
+            LockedField::Appearance => &["appearance"],
+            LockedField::Personality => &["personality"],
+            LockedField::Backstory => &["backstory"],
+            LockedField::Equipment => &["equipment"],
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            LockedField::Name => "name",
+            LockedField::Race => "race",
+            LockedField::Class => "class",
+            LockedField::Subclass => "subclass",
+            LockedField::Alignment => "alignment",
+            LockedField::Role => "role",
+            LockedField::Background => "background",
+            LockedField::AbilityScores => "ability scores",
+            LockedField::Appearance => "appearance",
+            LockedField::Personality => "personality",
+            LockedField::Backstory => "backstory",
+            LockedField::Equipment => "equipment",
+        }
+    }
+}
+
+/// The locked fields of the source NPC with their values.
+#[derive(Debug, Clone)]
+pub struct Locked {
+    /// Name of the NPC the fields come from, for messages
+    pub source: String,
+    fields: Vec<(LockedField, Vec<(&'static str, Value)>)>,
+}
+
+impl Locked {
+    pub fn from_npc(npc: &NPC, fields: &[LockedField]) -> Result<Locked> {
+        let value = serde_json::to_value(npc).context("Failed to serialize NPC to JSON")?;
+        let mut locked = Vec::new();
+        for &field in fields {
+            if locked.iter().any(|(f, _)| *f == field) {
+                continue;
+            }
+            let values = field
+                .keys()
+                .iter()
+                .map(|&key| (key, value.get(key).cloned().unwrap_or(Value::Null)))
+                .collect();
+            locked.push((field, values));
+        }
+        Ok(Locked { source: npc.name.clone(), fields: locked })
+    }
+
+    pub fn labels(&self) -> Vec<&'static str> {
+        self.fields.iter().map(|(field, _)| field.label()).collect()
+    }
+
+    pub fn contains(&self, field: LockedField) -> bool {
+        self.fields.iter().any(|(f, _)| *f == field)
+    }
+
+    /// The prompt section listing the locked values.
+    pub fn prompt_section(&self) -> String {
+        let mut section = String::from(
+            "LOCKED FIELDS (copy these into your answer EXACTLY as given, and make everything else consistent with them):\n",
+        );
+        for (_, values) in &self.fields {
+            for (key, value) in values {
+                if value.is_null() {
+                    continue;
+                }
+                section.push_str(&format!("- \"{}\": {}\n", key, serde_json::to_string(value).unwrap_or_default()));
+            }
+        }
+        section
+    }
+
+    /// Put back every locked field the model changed, returning a warning for each. When the
+    /// class, levels or ability scores come back, the numbers that follow from them (hit points,
+    /// attacks, armor class, slots, save DC) are recomputed; if the model's own class levels are
+    /// too broken to recompute from, the NPC is rejected rather than saved inconsistent.
+    pub fn enforce(&self, npc: &mut NPC) -> Result<Vec<String>> {
+        let before = leveling::Before::of(npc);
+        let mut value = serde_json::to_value(&*npc).context("Failed to serialize NPC to JSON")?;
+        let Value::Object(obj) = &mut value else {
+            return Ok(Vec::new());
+        };
+        let mut warnings = Vec::new();
+        let mut rules_bound = false;
+        for (field, values) in &self.fields {
+            let mut drifted = false;
+            for (key, locked) in values {
+                let current = obj.get(*key).unwrap_or(&Value::Null);
+                if current != locked {
+                    drifted = true;
+                    if locked.is_null() {
+                        obj.remove(*key);
+                    } else {
+                        obj.insert(key.to_string(), locked.clone());
+                    }
+                }
+            }
+            if drifted {
+                rules_bound |= matches!(field, LockedField::Class | LockedField::AbilityScores);
+                warnings.push(format!("locked {}: the model changed it, restored the value from {}", field.label(), self.source));
+            }
+        }
+        if warnings.is_empty() {
+            return Ok(warnings);
+        }
+        *npc = serde_json::from_value(value).context("Failed to restore locked fields")?;
+        if rules_bound {
+            let before = before.context("The model changed the locked class or ability scores, and its numbers can't be recomputed")?;
+            let before = match npc.class_name.0.first() {
+                Some(class) => before.starting_in(class.clone()),
+                None => before,
+            };
+            let dropped = leveling::recompute(npc, &before)?;
+            if !dropped.is_empty() {
+                warnings.push(format!("locked class: dropped spells above the restored slot levels: {}", dropped.join(", ")));
+            }
+        } else {
+            npc.complete_derived_fields();
+        }
+        Ok(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seraphine() -> NPC {
+        serde_json::from_str(include_str!("../tests/fixtures/seraphine.json")).unwrap()
+    }
+
+    #[test]
+    fn unchanged_fields_are_left_alone() {
+        let source = seraphine();
+        let locked = Locked::from_npc(&source, &[LockedField::Name, LockedField::Class, LockedField::Name]).unwrap();
+        assert_eq!(locked.labels(), ["name", "class"]);
+        let mut npc = seraphine();
+        npc.role = "Bard".to_string();
+        assert!(locked.enforce(&mut npc).unwrap().is_empty());
+        assert_eq!(npc.role, "Bard");
+    }
+
+    #[test]
+    fn drifted_fields_are_restored_and_their_numbers_recomputed() {
+        let source = seraphine();
+        let locked = Locked::from_npc(&source, &[LockedField::Name, LockedField::AbilityScores, LockedField::Backstory]).unwrap();
+
+        // What the model returned: other name and backstory, Dexterity 16 with numbers to match
+        let mut npc = seraphine();
+        let before = leveling::Before::of(&npc).unwrap();
+        npc.ability_scores.dexterity = 16;
+        leveling::recompute(&mut npc, &before).unwrap();
+        npc.name = "Sera Vane".to_string();
+        npc.backstory = "Someone else entirely.".to_string();
+        npc.role = "Bard".to_string();
+        assert_eq!(npc.armor_class, 14);
+
+        let warnings = locked.enforce(&mut npc).unwrap();
+        assert_eq!(warnings.len(), 3, "{:?}", warnings);
+        assert!(warnings[1].starts_with("locked ability scores: the model changed it"));
+        assert_eq!(npc.name, source.name);
+        assert_eq!(npc.backstory, source.backstory);
+        assert_eq!(npc.ability_scores.dexterity, 14);
+        assert_eq!((npc.armor_class, npc.initiative), (13, 2));
+        assert_eq!((npc.attacks[0].attack_bonus, npc.attacks[0].damage.as_str()), (5, "1d8+2"));
+        // Fields that weren't locked keep the model's value
+        assert_eq!(npc.role, "Bard");
+    }
+}
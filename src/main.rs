@@ -1,13 +1,200 @@
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+mod batch;
+mod concept;
+mod export;
+mod lenient;
+mod leveling;
+mod library;
+mod lock;
+mod ollama;
+mod output;
+mod recovery;
+mod reroll;
+mod roster;
+mod schema;
+mod stats;
+mod terminal;
+mod types;
+mod validate;
+mod wizard;
+
+use types::{Ability, Alignment, Class, Classes, DamageType, Size};
+
+/// Set when stdout carries the generated NPCs themselves (`--stdout`), so progress goes to stderr.
+static STATUS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Progress and log output of a generation run: stdout normally, stderr when streaming NPCs.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if STATUS_TO_STDERR.load(Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
 
 /// NPCForge - D&D 2024 NPC Generator using local AI
 #[derive(Parser, Debug)]
 #[command(name = "npcforge")]
 #[command(about = "Generate D&D 2024 NPCs using Ollama AI", long_about = None)]
-struct Args {
+#[command(args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    generate: GenerateArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Generate NPCs with the local model (the default when no command is given)
+    Generate(GenerateArgs),
+    /// Choose species, classes, levels, alignment, role and fighting preference from lists, then
+    /// generate (takes the same output options as `generate`)
+    New(GenerateArgs),
+    /// Generate several groups of NPCs described in a TOML spec file, each with its own options,
+    /// count and output folder
+    Batch {
+        /// Spec file with one [[entry]] table per group (and optional [defaults])
+        spec: PathBuf,
+
+        /// Options for entries that don't set them, and where to save
+        #[command(flatten)]
+        generate: GenerateArgs,
+    },
+    /// Check saved NPC files for missing fields and stale or inconsistent numbers
+    Validate {
+        /// NPC JSON files to check
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Print a saved NPC in the terminal
+    Show {
+        /// NPC JSON file
+        file: PathBuf,
+
+        /// Print the Markdown stat block instead of the formatted terminal view
+        #[arg(long)]
+        markdown: bool,
+    },
+    /// Regenerate one part of a saved NPC, keeping everything else (the old file is kept as a backup)
+    Reroll {
+        /// NPC JSON file
+        file: PathBuf,
+
+        /// Part to regenerate
+        #[arg(long, value_enum)]
+        section: reroll::Section,
+
+        /// Library database to update (default: $NPCFORGE_LIBRARY, or npcforge/library.db in the user data directory)
+        #[arg(long, value_name = "FILE")]
+        library: Option<PathBuf>,
+
+        /// Don't update the library
+        #[arg(long, conflicts_with = "library")]
+        no_library: bool,
+    },
+    /// Add levels to a saved NPC: hit points, proficiency, slots and saves are recomputed and the
+    /// model picks the new features and spells (the old file is kept as a backup)
+    Levelup(leveling::LevelArgs),
+    /// Remove levels from a saved NPC, the reverse of `levelup`
+    Leveldown(leveling::LevelArgs),
+    /// List the saved NPCs in a directory
+    List {
+        /// Directory to scan (recursively) for NPC JSON files
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Upgrade every saved NPC file in a directory to the current schema version
+    Migrate {
+        /// Directory to scan (recursively) for NPC JSON files
+        dir: PathBuf,
+
+        /// Don't keep a `.bak` copy of each file before upgrading it
+        #[arg(long)]
+        no_backup: bool,
+
+        /// Only report which files would be upgraded
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Convert saved NPC files to another format
+    Export {
+        /// NPC JSON files to convert
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Output format
+        #[arg(long = "to", value_enum)]
+        format: export::Format,
+
+        /// Directory to write the exports to (defaults to beside each JSON file)
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+    },
+    /// Summarize every saved NPC in a directory as CSV, TSV or JSON Lines
+    Roster {
+        /// Directory to scan (recursively) for NPC JSON files
+        dir: PathBuf,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "csv")]
+        format: roster::RosterFormat,
+
+        /// Columns to include, in order (default: all)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        columns: Vec<roster::Column>,
+
+        /// Columns to sort by, in priority order (default: file order)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        sort: Vec<roster::Column>,
+
+        /// Sort in descending order
+        #[arg(long)]
+        desc: bool,
+
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Import NPCs from 5etools homebrew monster entries into NPCForge JSON files
+    Import {
+        /// 5etools homebrew files (or single monster objects)
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Directory to save the NPC files in (default: current directory)
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+
+        /// File name template, as for generation
+        #[arg(long, default_value = output::DEFAULT_TEMPLATE)]
+        filename: String,
+    },
+    /// Browse and search the library of generated NPCs
+    Library {
+        /// Library database (default: $NPCFORGE_LIBRARY, or npcforge/library.db in the user data directory)
+        #[arg(long, global = true)]
+        library: Option<PathBuf>,
+
+        #[command(subcommand)]
+        command: library::LibraryCommand,
+    },
+}
+
+/// Generation options (`generate`, or no command at all)
+#[derive(clap::Args, Debug, Clone)]
+struct GenerateArgs {
     /// Number of NPCs to generate (max 25)
     #[arg(short = 'n', long, default_value_t = 1)]
     count: u8,
@@ -64,22 +251,142 @@ struct Args {
     /// Prefer ranged combat style (affects weapons, spells, subclass, feats)
     #[arg(long)]
     ranged: bool,
+
+    /// Free-text character idea, e.g. "a grumpy dwarf blacksmith who lost his son to a dragon". Species,
+    /// class, role, alignment and level are read from it unless given as flags, and the backstory follows it
+    #[arg(long)]
+    concept: Option<String>,
+
+    /// How to read constraints out of --concept
+    #[arg(long, value_enum, default_value = "local")]
+    concept_extract: concept::Extraction,
+
+    /// Existing NPC file to keep the --lock fields of
+    #[arg(long, value_name = "FILE", requires = "lock")]
+    from: Option<PathBuf>,
+
+    /// Fields to keep from the --from NPC, comma-separated (e.g. "name,race,appearance,backstory");
+    /// the model must reuse them, and they are restored if it changes them anyway
+    #[arg(long, value_enum, value_delimiter = ',', requires = "from")]
+    lock: Vec<lock::LockedField>,
+
+    /// Print each generated NPC in full (as `npcforge show` does) instead of a short summary
+    #[arg(long)]
+    show: bool,
+
+    /// Also write each NPC in these formats next to the JSON (e.g. "markdown"; repeatable or comma-separated)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    format: Vec<export::Format>,
+
+    /// Directory to save NPCs in (default: current directory)
+    #[arg(long)]
+    out_dir: Option<PathBuf>,
+
+    /// File name template without extension; "/" makes subdirectories. Placeholders: {name},
+    /// {species}, {class}, {subclass}, {level}, {role}, {alignment}, {background}, {index}
+    #[arg(long, default_value = output::DEFAULT_TEMPLATE)]
+    filename: String,
+
+    /// Write each NPC to stdout instead of saving files ("jsonl": one JSON object per line; logs go to stderr)
+    #[arg(long, value_enum, value_name = "FORMAT", conflicts_with = "format")]
+    stdout: Option<StdoutFormat>,
+
+    /// Read generation options as JSON lines from a file, or "-" for stdin (one batch per line)
+    #[arg(long, value_name = "FILE")]
+    input: Option<PathBuf>,
+
+    /// Library database to add generated NPCs to (default: $NPCFORGE_LIBRARY, or npcforge/library.db
+    /// in the user data directory)
+    #[arg(long, value_name = "FILE")]
+    library: Option<PathBuf>,
+
+    /// Don't add generated NPCs to the library
+    #[arg(long, conflicts_with = "library")]
+    no_library: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum StdoutFormat {
+    /// One JSON object per line
+    Jsonl,
+}
+
+/// A class given as "Fighter,Rogue" or as a list.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ClassList {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// One line of `--input`: the generation options for a batch, named like the command-line
+/// flags. Options a line leaves out keep their command-line values.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Constraints {
+    count: Option<u8>,
+    name: Option<String>,
+    #[serde(alias = "species")]
+    race: Option<String>,
+    class: Option<ClassList>,
+    level: Option<u8>,
+    lvl1: Option<u8>,
+    lvl2: Option<u8>,
+    lvl3: Option<u8>,
+    low: Option<u8>,
+    high: Option<u8>,
+    alignment: Option<String>,
+    role: Option<String>,
+    melee: Option<bool>,
+    ranged: Option<bool>,
+    concept: Option<String>,
+}
+
+impl Constraints {
+    fn apply(self, args: &GenerateArgs) -> GenerateArgs {
+        let mut args = args.clone();
+        args.count = self.count.unwrap_or(args.count);
+        args.name = self.name.or(args.name);
+        args.race = self.race.or(args.race);
+        args.class = match self.class {
+            Some(ClassList::One(class)) => Some(class),
+            Some(ClassList::Many(classes)) => Some(classes.join(",")),
+            None => args.class,
+        };
+        args.level = self.level.or(args.level);
+        args.lvl1 = self.lvl1.or(args.lvl1);
+        args.lvl2 = self.lvl2.or(args.lvl2);
+        args.lvl3 = self.lvl3.or(args.lvl3);
+        args.low = self.low.unwrap_or(args.low);
+        args.high = self.high.unwrap_or(args.high);
+        args.alignment = self.alignment.or(args.alignment);
+        args.role = self.role.unwrap_or(args.role);
+        args.melee = self.melee.unwrap_or(args.melee);
+        args.ranged = self.ranged.unwrap_or(args.ranged);
+        args.concept = self.concept.or(args.concept);
+        args
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
 struct NPC {
+    #[serde(default = "schema::default_schema_version")]
+    schema_version: u32,
     name: String,
     race: String,
     #[serde(rename = "class")]
-    class_name: String,
+    class_name: Classes,
     subclass: Option<String>,
     level: u8,
     #[serde(skip_serializing_if = "Option::is_none")]
-    class_levels: Option<std::collections::HashMap<String, u8>>,
+    class_levels: Option<BTreeMap<Class, u8>>,
     role: String,
     background: String,
-    alignment: String,
+    alignment: Alignment,
     fighting_preference: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<Size>,
 
     // Ability Scores
     ability_scores: AbilityScores,
@@ -92,10 +399,16 @@ struct NPC {
     proficiency_bonus: i8,
 
     // Skills and Proficiencies
+    #[serde(default, deserialize_with = "types::deserialize_known_entries")]
+    skills: Vec<SkillEntry>,
+    #[serde(default, deserialize_with = "types::deserialize_known_entries")]
+    saving_throws: Vec<SavingThrow>,
+    #[serde(default)]
+    passive_perception: u8,
     #[serde(default)]
-    skills: Vec<Skill>,
+    passive_insight: u8,
     #[serde(default)]
-    saving_throws: Vec<String>,
+    passive_investigation: u8,
     #[serde(default)]
     languages: Vec<String>,
     #[serde(default)]
@@ -138,10 +451,95 @@ struct HitPoints {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct Skill {
-    name: String,
+#[serde(from = "SkillEntryRepr")]
+struct SkillEntry {
+    name: types::Skill,
+    ability: Ability,
     modifier: i8,
+    /// Mirrors `proficiency`; files written before expertise was tracked only carry this flag
     proficient: bool,
+    proficiency: ProficiencyLevel,
+}
+
+/// The model sometimes lists proficient skills by name only, e.g. `["Stealth", "Athletics"]`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SkillEntryRepr {
+    Name(types::Skill),
+    Full {
+        name: types::Skill,
+        #[serde(default)]
+        ability: Option<Ability>,
+        #[serde(default)]
+        modifier: i8,
+        #[serde(default)]
+        proficient: bool,
+        #[serde(default)]
+        proficiency: ProficiencyLevel,
+    },
+}
+
+impl From<SkillEntryRepr> for SkillEntry {
+    fn from(repr: SkillEntryRepr) -> Self {
+        match repr {
+            SkillEntryRepr::Name(name) => SkillEntry {
+                name,
+                ability: name.ability(),
+                modifier: 0,
+                proficient: true,
+                proficiency: ProficiencyLevel::Proficient,
+            },
+            SkillEntryRepr::Full { name, ability, modifier, proficient, proficiency } => SkillEntry {
+                name,
+                ability: ability.unwrap_or(name.ability()),
+                modifier,
+                proficient,
+                proficiency,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ProficiencyLevel {
+    #[default]
+    None,
+    #[serde(alias = "half", alias = "half_proficiency")]
+    HalfProficient,
+    Proficient,
+    Expertise,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(from = "SavingThrowRepr")]
+struct SavingThrow {
+    ability: Ability,
+    modifier: i8,
+    proficient: bool,
+}
+
+/// Older files (and the model) list proficient saves by name only, e.g. `["Intelligence", "Wisdom"]`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavingThrowRepr {
+    Name(Ability),
+    Full {
+        ability: Ability,
+        #[serde(default)]
+        modifier: i8,
+        #[serde(default)]
+        proficient: bool,
+    },
+}
+
+impl From<SavingThrowRepr> for SavingThrow {
+    fn from(repr: SavingThrowRepr) -> Self {
+        match repr {
+            SavingThrowRepr::Name(ability) => SavingThrow { ability, modifier: 0, proficient: true },
+            SavingThrowRepr::Full { ability, modifier, proficient } => SavingThrow { ability, modifier, proficient },
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -149,7 +547,7 @@ struct Attack {
     name: String,
     attack_bonus: i8,
     damage: String,
-    damage_type: String,
+    damage_type: DamageType,
     range: Option<String>,
     #[serde(default)]
     properties: Vec<String>,
@@ -157,8 +555,8 @@ struct Attack {
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Spellcasting {
-    #[serde(default)]
-    spellcasting_ability: String,
+    #[serde(default, deserialize_with = "types::deserialize_optional_ability")]
+    spellcasting_ability: Option<Ability>,
     #[serde(default)]
     spell_save_dc: u8,
     #[serde(default)]
@@ -260,29 +658,7 @@ struct Feature {
     description: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct OllamaRequest {
-    model: String,
-    prompt: String,
-    stream: bool,
-    format: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    options: Option<OllamaOptions>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct OllamaOptions {
-    temperature: f32,
-    top_p: f32,
-    top_k: i32,
-    num_predict: i32,  // Maximum number of tokens to generate
-}
-
-#[derive(Debug, Deserialize)]
-struct OllamaResponse {
-    response: String,
-}
-
+#[allow(clippy::too_many_arguments)]
 async fn generate_npc_with_ollama(
     name: Option<&str>,
     race: Option<&str>,
@@ -294,54 +670,123 @@ async fn generate_npc_with_ollama(
     role: &str,
     melee: bool,
     ranged: bool,
-) -> Result<NPC> {
-    // Create client with extended timeout for AI generation
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(600)) // 10 minute timeout
-        .build()
-        .context("Failed to create HTTP client")?;
-
-    let prompt = create_npc_generation_prompt(name, race, class, level, level_distribution, level_range, alignment, role, melee, ranged);
-
-    let request = OllamaRequest {
-        model: "qwen2.5:32b-instruct".to_string(),
-        prompt,
-        stream: false,
-        format: "json".to_string(),
-        options: Some(OllamaOptions {
-            temperature: 1.2,  // Higher temperature for more randomness (default is 0.8)
-            top_p: 0.95,       // Nucleus sampling
-            top_k: 50,         // Top-k sampling for variety
-            num_predict: 4096, // Increase max tokens to prevent truncation
-        }),
-    };
+    concept: Option<&str>,
+    locked: Option<&lock::Locked>,
+) -> Result<(NPC, Vec<String>)> {
+    let client = ollama::client()?;
 
-    println!("Generating NPC with Ollama...");
-    println!("This may take a minute or two...\n");
+    let prompt = create_npc_generation_prompt(
+        name, race, class, level, level_distribution, level_range, alignment, role, melee, ranged, concept, locked,
+    );
 
-    let response = client
-        .post("http://localhost:11434/api/generate")
-        .json(&request)
-        .send()
-        .await
-        .context("Failed to connect to Ollama. Is it running?")?;
+    status!("Generating NPC with Ollama...");
+    status!("This may take a minute or two...\n");
 
-    let response_text = response
-        .text()
-        .await
-        .context("Failed to read response text")?;
+    let (mut npc, mut warnings) = generate_npc_json(&client, &prompt).await?;
+    npc.complete_derived_fields();
+    if let Some(locked) = locked {
+        warnings.extend(locked.enforce(&mut npc)?);
+    }
 
-    let ollama_response: OllamaResponse = serde_json::from_str(&response_text)
-        .context(format!("Failed to parse Ollama response. Response was: {}",
-            &response_text[..response_text.len().min(500)]))?;
+    Ok((npc, warnings))
+}
 
-    let npc: NPC = serde_json::from_str(&ollama_response.response)
-        .context(format!("Failed to parse NPC JSON. Response was: {}",
-            &ollama_response.response[..ollama_response.response.len().min(1000)]))?;
+/// Continuation requests to try before falling back to salvaging the partial JSON
+const MAX_CONTINUATIONS: usize = 2;
+
+/// Run the generation prompt and parse the result, recovering from output truncated by the
+/// token limit: first by asking the model to continue, then by closing the partial JSON and
+/// regenerating only the sections that were lost.
+async fn generate_npc_json(client: &reqwest::Client, prompt: &str) -> Result<(NPC, Vec<String>)> {
+    let output = ollama::generate(client, prompt, true).await?;
+    let mut text = output.text;
+    let mut truncated = output.truncated;
+
+    let mut continuations = 0;
+    while truncated && continuations < MAX_CONTINUATIONS {
+        continuations += 1;
+        status!("⚠ Output hit the token limit, asking the model to continue ({}/{})...",
+            continuations, MAX_CONTINUATIONS);
+        let more = ollama::continue_generation(client, prompt, &text).await?;
+        text.push_str(&more.text);
+        truncated = more.truncated;
+    }
 
-    Ok(npc)
+    let parse_error = match lenient::parse_npc_lenient(&text) {
+        Ok((npc, mut warnings)) => {
+            if continuations > 0 {
+                warnings.insert(0, format!("response: truncated by the token limit, completed with {} continuation request(s)", continuations));
+            }
+            return Ok((npc, warnings));
+        }
+        Err(e) => e,
+    };
+
+    let Some(salvaged) = recovery::close_partial_json(&text) else {
+        return Err(parse_error);
+    };
+    let missing = salvaged.missing_sections();
+    let mut warnings = vec![format!(
+        "response: JSON was incomplete ({}), salvaged the complete fields",
+        if truncated { "token limit reached" } else { "invalid JSON" }
+    )];
+    let mut value = salvaged.value;
+
+    if !missing.is_empty() {
+        status!("⚠ Salvaged partial output, regenerating: {}", missing.join(", "));
+        if let Some(obj) = value.as_object_mut() {
+            for section in &missing {
+                obj.remove(*section);
+            }
+        }
+        let section_prompt = create_missing_sections_prompt(prompt, &value, &missing);
+        match regenerate_sections(client, &section_prompt, &missing).await {
+            Ok(sections) => {
+                if let Some(obj) = value.as_object_mut() {
+                    for (key, section) in sections {
+                        obj.insert(key, section);
+                    }
+                }
+                warnings.push(format!("response: regenerated sections: {}", missing.join(", ")));
+            }
+            Err(e) => warnings.push(format!("response: could not regenerate {} ({:#})", missing.join(", "), e)),
+        }
+    }
+
+    let npc = lenient::parse_npc_value(value, &mut warnings)?;
+    Ok((npc, warnings))
 }
 
+/// Ask for the given top-level sections only, returning those the model actually produced.
+async fn regenerate_sections(
+    client: &reqwest::Client,
+    prompt: &str,
+    sections: &[&str],
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let output = ollama::generate(client, prompt, true).await?;
+    let value: serde_json::Value = serde_json::from_str(&output.text)
+        .context("Regenerated sections were not valid JSON")?;
+    let serde_json::Value::Object(mut obj) = value else {
+        anyhow::bail!("Regenerated sections were not a JSON object");
+    };
+    obj.retain(|key, _| sections.contains(&key.as_str()));
+    Ok(obj)
+}
+
+fn create_missing_sections_prompt(original_prompt: &str, partial: &serde_json::Value, missing: &[&str]) -> String {
+    let partial_json = serde_json::to_string_pretty(partial).unwrap_or_default();
+    format!(
+        "{}\n\nYour previous answer was cut off before it was finished. This part of the character was saved:\n\n{}\n\n\
+        Output ONLY a JSON object containing these missing fields, using the same formats as the structure above \
+        and staying consistent with the saved character: {}\n\
+        Keep it concise so the answer is not cut off again (the backstory should be at most 3 paragraphs).",
+        original_prompt,
+        partial_json,
+        missing.join(", ")
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 fn create_npc_generation_prompt(
     name: Option<&str>,
     race: Option<&str>,
@@ -353,6 +798,8 @@ fn create_npc_generation_prompt(
     role: &str,
     melee: bool,
     ranged: bool,
+    concept: Option<&str>,
+    locked: Option<&lock::Locked>,
 ) -> String {
     let mut prompt = String::from(
         "You are a D&D 2024 character generator. Generate a complete, TRULY RANDOM D&D character with maximum variety and creativity.\n\n"
@@ -363,7 +810,7 @@ fn create_npc_generation_prompt(
     let is_multiclass = classes.len() > 1;
 
     // Add user constraints if provided
-    let has_constraints = name.is_some() || race.is_some() || class.is_some() || level.is_some() || level_distribution.is_some() || level_range.is_some() || alignment.is_some() || role != "Mercenary";
+    let has_constraints = name.is_some() || race.is_some() || class.is_some() || level.is_some() || level_distribution.is_some() || level_range.is_some() || alignment.is_some() || role != "Mercenary" || concept.is_some() || locked.is_some();
 
     if has_constraints {
         prompt.push_str("USER CONSTRAINTS (MUST follow these exactly):\n");
@@ -426,6 +873,9 @@ fn create_npc_generation_prompt(
         if let Some(a) = alignment {
             prompt.push_str(&format!("- Alignment MUST be: {}\n", a));
         }
+        if let Some(c) = concept {
+            prompt.push_str(&format!("- Character concept (build the whole character around it): \"{}\"\n", c));
+        }
         if role.to_lowercase() != "mercenary" {
             if role.to_lowercase() == "random" {
                 prompt.push_str("- Role/Occupation MUST be randomly selected\n");
@@ -433,7 +883,11 @@ fn create_npc_generation_prompt(
                 prompt.push_str(&format!("- Role/Occupation MUST be: {}\n", role));
             }
         }
-        prompt.push_str("\n");
+        prompt.push('\n');
+        if let Some(locked) = locked {
+            prompt.push_str(&locked.prompt_section());
+            prompt.push('\n');
+        }
     }
 
     prompt.push_str(r#"IMPORTANT: Be EXTREMELY VARIED in your choices! Avoid patterns and defaults!
@@ -446,8 +900,8 @@ Requirements:
 "#);
 
     // Add race requirement (random or constrained)
-    if race.is_some() {
-        prompt.push_str(&format!("- Use the specified race: {}\n", race.unwrap()));
+    if let Some(r) = race {
+        prompt.push_str(&format!("- Use the specified race: {}\n", r));
     } else {
         prompt.push_str("- Choose a COMPLETELY RANDOM race from ALL official D&D races (Human, Elf, Dwarf, Halfling, Dragonborn, Gnome, Half-Elf, Half-Orc, Tiefling, Aasimar, Firbolg, Goliath, Kenku, Tabaxi, Triton, Genasi, Bugbear, Goblin, Hobgoblin, Kobold, Orc, Yuan-ti, Lizardfolk, etc.)\n");
     }
@@ -499,9 +953,7 @@ Requirements:
 
     // Add fighting style requirements
     let is_spellcaster = class.map(|c| {
-        let classes = c.split(',').collect::<Vec<_>>();
-        classes.iter().any(|&cls| matches!(cls.trim().to_lowercase().as_str(),
-            "wizard" | "sorcerer" | "warlock" | "cleric" | "druid" | "bard" | "artificer"))
+        c.split(',').any(|cls| cls.parse::<Class>().is_ok_and(|cls| cls.is_spellcaster()))
     }).unwrap_or(false);
 
     if melee && ranged {
@@ -556,7 +1008,7 @@ Requirements:
         prompt.push_str("  * Random allocation appropriate to class and randomly chosen fighting style\n");
         prompt.push_str("  * Prioritize primary stat for class, then physical stats based on chosen style\n");
     }
-    prompt.push_str("\n");
+    prompt.push('\n');
     prompt.push_str("- Calculate all derived stats correctly (AC, HP, initiative, proficiency bonus, etc.)\n");
     prompt.push_str("- Include all relevant skills, proficiencies, and saving throws\n");
     prompt.push_str("- For spellcasters, include appropriate spells based on class and level\n");
@@ -585,6 +1037,9 @@ Requirements:
         prompt.push_str("  * Current situation: Where they live (city, town, village, farm, wilderness), their daily life, current challenges\n");
         prompt.push_str("  * Examples: A farmer with Druid powers who uses them to help crops grow; A blacksmith Fighter who crafts weapons but rarely fights; A scholarly Wizard who teaches at an academy\n");
     }
+    if concept.is_some() {
+        prompt.push_str("  * The backstory, personality and appearance MUST honor the character concept given above\n");
+    }
     prompt.push_str("- Include personality traits, ideals, bonds, and flaws\n");
     prompt.push_str("- Create a vivid physical appearance\n\n");
 
@@ -597,6 +1052,11 @@ IMPORTANT: For multiclass characters:
 - "class_levels" is an object showing the level distribution (e.g., {"Fighter": 5, "Wizard": 5})
 - ONLY include "class_levels" for multiclass characters (omit for single-class)
 
+Skills and saving throws:
+- List ONLY the skills the character is proficient in; the full skill list is computed afterwards
+- "proficiency" is "proficient" or "expertise" (Rogue/Bard/Ranger expertise, Skill Expert feat, etc.)
+- "saving_throws" lists the abilities whose saves the character is proficient in
+
 Fighting preference values:
 - "Melee" if primarily melee combat
 - "Ranged" if primarily ranged combat
@@ -613,6 +1073,7 @@ Fighting preference values:
   "background": "Background name",
   "alignment": "Alignment",
   "fighting_preference": "Melee, Ranged, or Versatile",
+  "size": "Medium",
   "ability_scores": {
     "strength": 10,
     "dexterity": 14,
@@ -632,8 +1093,8 @@ Fighting preference values:
   "speed": 30,
   "proficiency_bonus": 4,
   "skills": [
-    {"name": "Arcana", "modifier": 7, "proficient": true},
-    {"name": "Investigation", "modifier": 7, "proficient": true}
+    {"name": "Arcana", "modifier": 11, "proficient": true, "proficiency": "expertise"},
+    {"name": "Investigation", "modifier": 7, "proficient": true, "proficiency": "proficient"}
   ],
   "saving_throws": ["Intelligence", "Wisdom"],
   "languages": ["Common", "Elvish"],
@@ -714,98 +1175,383 @@ Generate a completely random character now:"#);
     prompt
 }
 
-fn save_npc_to_file(npc: &NPC, index: Option<usize>) -> Result<String> {
-    let base_name = npc.name.replace(" ", "_");
-    let filename = if let Some(idx) = index {
-        format!("{}_{}.json", base_name, idx)
-    } else {
-        format!("{}.json", base_name)
-    };
-
+/// Save the NPC as pretty JSON where `layout` puts it, never replacing another file. Parse
+/// warnings, if any, go to a `.warnings.txt` file beside it.
+fn save_npc_to_file(npc: &NPC, warnings: &[String], index: Option<usize>, layout: &output::Layout) -> Result<PathBuf> {
     let json = serde_json::to_string_pretty(npc)
         .context("Failed to serialize NPC to JSON")?;
 
-    fs::write(&filename, json)
-        .context(format!("Failed to write to file: {}", filename))?;
+    let path = layout.create_json(npc, index, json)?;
+
+    if !warnings.is_empty() {
+        let mut text = warnings.join("\n");
+        text.push('\n');
+        output::write_atomic(&warnings_path(&path), text)?;
+    }
+
+    Ok(path)
+}
+
+/// `Name.warnings.txt` beside `Name.json`.
+fn warnings_path(json_path: &Path) -> PathBuf {
+    json_path.with_extension("warnings.txt")
+}
+
+/// Write the Obsidian notes for a generated batch into `dir`, plus the index note.
+fn write_obsidian_notes(npcs: &[NPC], dir: &Path) -> Result<()> {
+    let batch: Vec<&NPC> = npcs.iter().collect();
+    let vault = export::obsidian::Vault::open(dir, &batch)?;
+    for npc in npcs {
+        match vault.write_note(npc) {
+            Ok(path) => status!("✓ Obsidian note: {}", path.display()),
+            Err(e) => eprintln!("✗ Error writing Obsidian note for {}: {:#}", npc.name, e),
+        }
+    }
+    let path = vault.write_index()?;
+    status!("✓ Obsidian index: {}\n", path.display());
+    Ok(())
+}
 
-    Ok(filename)
+/// Open the library at `path` (or the default one) unless disabled. Failing to open it is only
+/// a warning, with `consequence` telling the user what they miss.
+fn open_library(path: Option<PathBuf>, disabled: bool, consequence: &str) -> Option<library::Library> {
+    if disabled {
+        return None;
+    }
+    let path = path.unwrap_or_else(library::Library::default_path);
+    match library::Library::open(&path) {
+        Ok(library) => Some(library),
+        Err(e) => {
+            eprintln!("⚠ Warning: {}: {:#}\n", consequence, e);
+            None
+        }
+    }
+}
+
+/// Add a generated NPC to the library. Failing to is reported, but the NPC is still saved.
+fn add_to_library(library: &mut library::Library, npc: &NPC, file: Option<&Path>) {
+    match library.add(npc, file) {
+        Ok(library::Added::New(id) | library::Added::Updated(id)) => status!("✓ Added to library as #{}", id),
+        Err(e) => eprintln!("✗ Error adding to library: {:#}", e),
+    }
+}
+
+/// `npcforge import`: convert 5etools monsters to NPC files.
+fn import_files(files: &[PathBuf], layout: &output::Layout) -> Result<()> {
+    println!("=== NPCForge - Importing {} file(s) ===\n", files.len());
+
+    let mut imported = 0;
+    let mut failed = 0;
+    for file in files {
+        match export::fivetools::import_file(file) {
+            Ok(npcs) => {
+                for (npc, warnings) in npcs {
+                    match save_npc_to_file(&npc, &warnings, None, layout) {
+                        Ok(path) => {
+                            println!("✓ {} ({}) -> {}", npc.name, file.display(), path.display());
+                            imported += 1;
+                        }
+                        Err(e) => {
+                            eprintln!("✗ {}: {:#}", npc.name, e);
+                            failed += 1;
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("✗ {}: {:#}", file.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\n=== Summary ===");
+    println!("Imported: {}", imported);
+    if failed > 0 {
+        println!("Failed: {}", failed);
+    }
+    Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let mut args = Args::parse();
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Generate(args)) => run_generate(args).await,
+        Some(Command::New(args)) => match wizard::build_request(args)? {
+            Some(args) => run_generate(args).await,
+            None => Ok(()),
+        },
+        Some(Command::Batch { spec, generate }) => run_batch(&spec, generate).await,
+        Some(Command::Validate { files }) => validate::validate_files(&files),
+        Some(Command::Show { file, markdown }) => {
+            let (npc, _) = schema::load_npc_file(&file)?;
+            if markdown {
+                print!("{}", export::markdown::render(&npc));
+            } else {
+                print!("{}", terminal::render(&npc, terminal::Style::stdout()));
+            }
+            Ok(())
+        }
+        Some(Command::Reroll { file, section, library, no_library }) => {
+            let mut library = open_library(library, no_library, "the library entry won't be updated");
+            reroll::reroll_file(&file, section, library.as_mut()).await
+        }
+        Some(Command::Levelup(args)) => {
+            let mut library = open_library(args.library.clone(), args.no_library, "the library entry won't be updated");
+            leveling::change_level(args, leveling::Direction::Up, library.as_mut()).await
+        }
+        Some(Command::Leveldown(args)) => {
+            let mut library = open_library(args.library.clone(), args.no_library, "the library entry won't be updated");
+            leveling::change_level(args, leveling::Direction::Down, library.as_mut()).await
+        }
+        Some(Command::List { dir }) => roster::list_directory(&dir),
+        Some(Command::Migrate { dir, no_backup, dry_run }) => schema::migrate_directory(&dir, !no_backup, dry_run),
+        Some(Command::Export { files, format, out_dir }) => export::export_files(&files, format, out_dir.as_deref()),
+        Some(Command::Import { files, out_dir, filename }) => {
+            import_files(&files, &output::Layout::new(out_dir.unwrap_or_else(|| PathBuf::from(".")), &filename)?)
+        }
+        Some(Command::Roster { dir, format, columns, sort, desc, output }) => {
+            roster::write_roster(&dir, format, &columns, &sort, desc, output.as_deref())
+        }
+        Some(Command::Library { library, command }) => {
+            library::run(&library.unwrap_or_else(library::Library::default_path), command)
+        }
+        None => run_generate(cli.generate).await,
+    }
+}
 
-    println!("=== NPCForge - D&D 2024 NPC Generator ===\n");
+/// Generated and failed NPCs across the batches of a run.
+#[derive(Default)]
+struct Totals {
+    succeeded: u32,
+    failed: u32,
+}
 
-    // If name is specified, set count to 1
-    if args.name.is_some() {
-        args.count = 1;
+async fn run_generate(args: GenerateArgs) -> Result<()> {
+    STATUS_TO_STDERR.store(args.stdout.is_some(), Ordering::Relaxed);
+    status!("=== NPCForge - D&D 2024 NPC Generator ===\n");
+
+    let layout = output::Layout::new(args.out_dir.clone().unwrap_or_else(|| PathBuf::from(".")), &args.filename)?;
+    let mut library = open_library(args.library.clone(), args.no_library, "NPCs won't be added to the library");
+    let mut totals = Totals::default();
+    match &args.input {
+        Some(input) => {
+            let reader: Box<dyn BufRead> = if input == Path::new("-") {
+                Box::new(std::io::stdin().lock())
+            } else {
+                let file = fs::File::open(input).context(format!("Failed to open {}", input.display()))?;
+                Box::new(BufReader::new(file))
+            };
+            // Lines are handled as they arrive, so npcforge can sit at the end of a pipe
+            for (number, line) in reader.lines().enumerate() {
+                let line = line.context("Failed to read input")?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                status!("--- Input line {} ---", number + 1);
+                match serde_json::from_str::<Constraints>(&line) {
+                    Ok(constraints) => generate_batch(constraints.apply(&args), &layout, library.as_mut(), &mut totals).await?,
+                    Err(e) => {
+                        eprintln!("✗ Error: input line {} is not a valid options object: {}\n", number + 1, e);
+                        totals.failed += 1;
+                    }
+                }
+            }
+        }
+        None => generate_batch(args, &layout, library.as_mut(), &mut totals).await?,
     }
 
-    // Limit count to 25
-    if args.count > 25 {
-        eprintln!("✗ Error: Count limited to 25 NPCs maximum");
-        args.count = 25;
-        println!("  Setting count to 25\n");
+    status!("=== Summary ===");
+    status!("Successfully generated: {}", totals.succeeded);
+    if totals.failed > 0 {
+        status!("Failed: {}", totals.failed);
     }
 
-    // Parse and validate classes (limit to 3)
+    Ok(())
+}
+
+/// `npcforge batch`: generate every entry of a spec file into its own folder under the output
+/// directory, then summarize the run per entry.
+async fn run_batch(spec: &Path, args: GenerateArgs) -> Result<()> {
+    if args.input.is_some() {
+        anyhow::bail!("--input can't be combined with a batch spec; give each set of options its own [[entry]]");
+    }
+    let entries = batch::load(spec, &args)?;
+
+    STATUS_TO_STDERR.store(args.stdout.is_some(), Ordering::Relaxed);
+    status!("=== NPCForge - D&D 2024 NPC Generator ===\n");
+    let requested: u32 = entries.iter().map(|e| if e.args.name.is_some() { 1 } else { e.args.count as u32 }).sum();
+    status!("Batch {}: {} entries, {} NPC(s)\n", spec.display(), entries.len(), requested);
+
+    let root = output::Layout::new(args.out_dir.clone().unwrap_or_else(|| PathBuf::from(".")), &args.filename)?;
+    let mut library = open_library(args.library.clone(), args.no_library, "NPCs won't be added to the library");
+    let mut results = Vec::new();
+    let count = entries.len();
+    for (i, entry) in entries.into_iter().enumerate() {
+        status!("=== Entry {}/{}: {} ===", i + 1, count, entry.folder);
+        let layout = root.within(&entry.folder);
+        let requested = if entry.args.name.is_some() { 1 } else { entry.args.count };
+        let mut totals = Totals::default();
+        generate_batch(entry.args, &layout, library.as_mut(), &mut totals).await?;
+        results.push((entry.folder, layout.dir, requested, totals));
+    }
+
+    status!("=== Summary ===");
+    let width = results.iter().map(|(folder, ..)| folder.chars().count()).max().unwrap_or(0);
+    let mut overall = Totals::default();
+    for (folder, dir, requested, totals) in &results {
+        let mut line = format!("  {:<width$}  {}/{} generated", folder, totals.succeeded, requested);
+        if totals.failed > 0 {
+            line.push_str(&format!(", {} failed", totals.failed));
+        }
+        if args.stdout.is_none() && totals.succeeded > 0 {
+            line.push_str(&format!("  ({})", dir.display()));
+        }
+        status!("{}", line);
+        overall.succeeded += totals.succeeded;
+        overall.failed += totals.failed;
+    }
+    status!();
+    status!("Successfully generated: {}", overall.succeeded);
+    if overall.failed > 0 {
+        status!("Failed: {}", overall.failed);
+    }
+
+    Ok(())
+}
+
+/// Carry the locked fields that have flags of their own over into those flags, so the rest of
+/// the prompt (and the validation below) agrees with them.
+fn lock_args(args: &mut GenerateArgs, source: &NPC, locked: &lock::Locked) {
+    let mut overridden = Vec::new();
+    let mut set = |flag: &str, slot: &mut Option<String>, value: String| {
+        if slot.as_ref().is_some_and(|old| *old != value) {
+            overridden.push(flag.to_string());
+        }
+        *slot = Some(value);
+    };
+    if locked.contains(lock::LockedField::Name) {
+        set("--name", &mut args.name, source.name.clone());
+    }
+    if locked.contains(lock::LockedField::Race) {
+        set("--race", &mut args.race, source.race.clone());
+    }
+    if locked.contains(lock::LockedField::Alignment) {
+        set("--alignment", &mut args.alignment, source.alignment.to_string());
+    }
+    if locked.contains(lock::LockedField::Class) {
+        let classes: Vec<&str> = source.class_name.0.iter().map(Class::name).collect();
+        set("--class", &mut args.class, classes.join(","));
+        let levels: Vec<Option<u8>> = match &source.class_levels {
+            Some(_) => source.class_name.0.iter().map(|class| Some(source.levels_in(class))).collect(),
+            None => Vec::new(),
+        };
+        let mut set_level = |flag: &str, slot: &mut Option<u8>, value: Option<u8>| {
+            if slot.is_some() && *slot != value {
+                overridden.push(flag.to_string());
+            }
+            *slot = value;
+        };
+        set_level("--level", &mut args.level, Some(source.level));
+        set_level("--lvl1", &mut args.lvl1, levels.first().copied().flatten());
+        set_level("--lvl2", &mut args.lvl2, levels.get(1).copied().flatten());
+        set_level("--lvl3", &mut args.lvl3, levels.get(2).copied().flatten());
+        // A fixed level leaves no room for a random range
+        if args.low != 1 || args.high != 10 {
+            overridden.push("--low/--high".to_string());
+        }
+    }
+    if locked.contains(lock::LockedField::Role) {
+        if args.role != "Mercenary" && args.role != source.role {
+            overridden.push("--role".to_string());
+        }
+        args.role = source.role.clone();
+    }
+    if !overridden.is_empty() {
+        status!("⚠ Warning: {} ignored; the locked value from {} is used", overridden.join(", "), locked.source);
+    }
+    status!("Keeping from {}: {}", locked.source, locked.labels().join(", "));
+}
+
+/// Read constraints out of the concept text, show them, and use them for the options the user
+/// left open.
+async fn apply_concept(args: &mut GenerateArgs, text: &str) {
+    let found = match args.concept_extract {
+        concept::Extraction::Local => concept::extract_local(text),
+        concept::Extraction::Model => match concept::extract_with_model(text).await {
+            Ok(found) => found,
+            Err(e) => {
+                status!("⚠ Warning: couldn't read the concept with the model ({:#}); matching keywords instead", e);
+                concept::extract_local(text)
+            }
+        },
+    };
+    status!("Concept: \"{}\"", text);
+    status!("{}", found.summary());
+
+    args.race = args.race.take().or(found.species);
+    args.class = args.class.take().or(found.class);
+    args.alignment = args.alignment.take().or(found.alignment);
+    if args.level.is_none() && args.lvl1.is_none() && args.lvl2.is_none() && args.lvl3.is_none() {
+        args.level = found.level;
+    }
+    // "Mercenary" is the default, so an explicit --role Mercenary gives way to the concept too
+    if let Some(role) = found.role
+        && args.role == "Mercenary"
+    {
+        args.role = role;
+    }
+}
+
+/// Check the level options against each other and the classes, returning the per-class levels
+/// if they were given.
+fn check_levels(args: &GenerateArgs) -> std::result::Result<Option<Vec<u8>>, String> {
     let classes: Vec<&str> = args.class.as_ref()
         .map(|c| c.split(',').take(3).collect())
         .unwrap_or_default();
     let num_classes = classes.len();
 
-    if num_classes > 3 {
-        println!("⚠ Warning: More than 3 classes specified. Using only first 3: {}", classes.join(", "));
-    }
-
     // Build level distribution vector if lvl1/lvl2/lvl3 are specified
     let level_distribution = if args.lvl1.is_some() || args.lvl2.is_some() || args.lvl3.is_some() {
         let mut dist = Vec::new();
         if let Some(l1) = args.lvl1 {
-            if l1 < 1 || l1 > 20 {
-                eprintln!("✗ Error: --lvl1 must be between 1 and 20");
-                return Ok(());
+            if !(1..=20).contains(&l1) {
+                return Err("--lvl1 must be between 1 and 20".to_string());
             }
             dist.push(l1);
         }
         if let Some(l2) = args.lvl2 {
-            if l2 < 1 || l2 > 20 {
-                eprintln!("✗ Error: --lvl2 must be between 1 and 20");
-                return Ok(());
+            if !(1..=20).contains(&l2) {
+                return Err("--lvl2 must be between 1 and 20".to_string());
             }
             dist.push(l2);
         }
         if let Some(l3) = args.lvl3 {
-            if l3 < 1 || l3 > 20 {
-                eprintln!("✗ Error: --lvl3 must be between 1 and 20");
-                return Ok(());
+            if !(1..=20).contains(&l3) {
+                return Err("--lvl3 must be between 1 and 20".to_string());
             }
             dist.push(l3);
         }
 
         // Validate that distribution matches number of classes
         if num_classes > 0 && dist.len() != num_classes {
-            eprintln!("✗ Error: Level distribution count ({}) doesn't match class count ({})",
-                dist.len(), num_classes);
-            eprintln!("  Classes: {}", classes.join(", "));
-            return Ok(());
+            return Err(format!("Level distribution count ({}) doesn't match class count ({})\n  Classes: {}",
+                dist.len(), num_classes, classes.join(", ")));
         }
 
         // Validate total level
         let total_level: u8 = dist.iter().sum();
-        if total_level < 1 || total_level > 20 {
-            eprintln!("✗ Error: Total level from distribution ({}) must be between 1 and 20", total_level);
-            return Ok(());
+        if !(1..=20).contains(&total_level) {
+            return Err(format!("Total level from distribution ({}) must be between 1 and 20", total_level));
         }
 
         // If explicit level was specified, validate it matches
-        if let Some(level) = args.level {
-            if total_level != level {
-                eprintln!("✗ Error: Level distribution sum ({}) doesn't match specified level ({})",
-                    total_level, level);
-                return Ok(());
-            }
+        if let Some(level) = args.level
+            && total_level != level {
+            return Err(format!("Level distribution sum ({}) doesn't match specified level ({})",
+                total_level, level));
         }
 
         Some(dist)
@@ -814,27 +1560,102 @@ async fn main() -> Result<()> {
     };
 
     // Validate level if specified
-    if let Some(level) = args.level {
-        if level < 1 || level > 20 {
-            eprintln!("✗ Error: Level must be between 1 and 20");
-            return Ok(());
-        }
+    if let Some(level) = args.level
+        && !(1..=20).contains(&level) {
+        return Err("Level must be between 1 and 20".to_string());
     }
 
     // Validate level range
     if args.low < 1 || args.low > 20 {
-        eprintln!("✗ Error: --low must be between 1 and 20");
-        return Ok(());
+        return Err("--low must be between 1 and 20".to_string());
     }
     if args.high < 1 || args.high > 20 {
-        eprintln!("✗ Error: --high must be between 1 and 20");
-        return Ok(());
+        return Err("--high must be between 1 and 20".to_string());
     }
     if args.low > args.high {
-        eprintln!("✗ Error: --low ({}) cannot be greater than --high ({})", args.low, args.high);
-        return Ok(());
+        return Err(format!("--low ({}) cannot be greater than --high ({})", args.low, args.high));
+    }
+
+    Ok(level_distribution)
+}
+
+/// Generate `args.count` NPCs from one set of options.
+async fn generate_batch(
+    mut args: GenerateArgs,
+    layout: &output::Layout,
+    mut library: Option<&mut library::Library>,
+    totals: &mut Totals,
+) -> Result<()> {
+
+    let locked = match &args.from {
+        Some(from) => {
+            let source = match schema::load_npc_file(from) {
+                Ok((source, _)) => source,
+                Err(e) => {
+                    eprintln!("✗ Error: {:#}", e);
+                    totals.failed += if args.name.is_some() { 1 } else { args.count.min(25) as u32 };
+                    return Ok(());
+                }
+            };
+            let locked = lock::Locked::from_npc(&source, &args.lock)?;
+            lock_args(&mut args, &source, &locked);
+            Some(locked)
+        }
+        None => None,
+    };
+
+    if let Some(text) = args.concept.clone() {
+        apply_concept(&mut args, &text).await;
+    }
+
+    // If name is specified, set count to 1
+    if args.name.is_some() {
+        args.count = 1;
+    }
+
+    // Limit count to 25
+    if args.count > 25 {
+        eprintln!("✗ Error: Count limited to 25 NPCs maximum");
+        args.count = 25;
+        status!("  Setting count to 25\n");
+    }
+
+    // Parse and validate classes (limit to 3)
+    let classes: Vec<&str> = args.class.as_ref()
+        .map(|c| c.split(',').take(3).collect())
+        .unwrap_or_default();
+    let num_classes = classes.len();
+
+    if num_classes > 3 {
+        status!("⚠ Warning: More than 3 classes specified. Using only first 3: {}", classes.join(", "));
+    }
+    for class in &classes {
+        if let Ok(Class::Other(name)) = class.parse::<Class>() {
+            status!("⚠ Warning: \"{}\" is not an official class; generating it as homebrew", name);
+        }
     }
 
+    // Normalize the alignment ("CG", "chaotic good", ...) so the prompt always gets the full name
+    if let Some(alignment) = &args.alignment {
+        match alignment.parse::<Alignment>() {
+            Ok(Alignment::Other(_)) | Err(_) => {
+                eprintln!("✗ Error: Unrecognized alignment \"{}\" (use e.g. \"CG\", \"LN\" or \"Neutral Evil\")", alignment);
+                totals.failed += args.count as u32;
+                return Ok(());
+            }
+            Ok(parsed) => args.alignment = Some(parsed.to_string()),
+        }
+    }
+
+    let level_distribution = match check_levels(&args) {
+        Ok(dist) => dist,
+        Err(message) => {
+            eprintln!("✗ Error: {}", message);
+            totals.failed += args.count as u32;
+            return Ok(());
+        }
+    };
+
     // Calculate level range (only if level is not explicitly specified)
     let level_range = if args.level.is_none() && level_distribution.is_none() && (args.low != 1 || args.high != 10) {
         Some((args.low, args.high))
@@ -842,14 +1663,14 @@ async fn main() -> Result<()> {
         None
     };
 
-    println!("Generating {} NPC(s)...\n", args.count);
+    status!("Generating {} NPC(s)...\n", args.count);
 
-    let mut success_count = 0;
-    let mut failed_count = 0;
+    let mut html_index = Vec::new();
+    let mut obsidian_notes = Vec::new();
 
     for i in 1..=args.count {
         if args.count > 1 {
-            println!("--- Generating NPC {}/{} ---", i, args.count);
+            status!("--- Generating NPC {}/{} ---", i, args.count);
         }
 
         let result = generate_npc_with_ollama(
@@ -863,32 +1684,90 @@ async fn main() -> Result<()> {
             &args.role,
             args.melee,
             args.ranged,
+            args.concept.as_deref(),
+            locked.as_ref(),
         )
         .await;
 
         match result {
-            Ok(npc) => {
-                println!("✓ Successfully generated NPC: {}", npc.name);
-                println!("  Race: {}", npc.race);
-                println!("  Class: {} (Level {})", npc.class_name, npc.level);
-                if let Some(subclass) = &npc.subclass {
-                    println!("  Subclass: {}", subclass);
+            Ok((npc, warnings)) => {
+                status!("✓ Successfully generated NPC: {}", npc.name);
+                if args.show {
+                    let style = if args.stdout.is_some() { terminal::Style::stderr() } else { terminal::Style::stdout() };
+                    status!("\n{}", terminal::render(&npc, style));
+                } else {
+                    status!("  Race: {}", npc.race);
+                    status!("  Class: {} (Level {})", npc.class_name, npc.level);
+                    if let Some(subclass) = &npc.subclass {
+                        status!("  Subclass: {}", subclass);
+                    }
+                    status!("  Background: {}", npc.background);
+                    status!("  Alignment: {}", npc.alignment);
+                }
+
+                if args.stdout == Some(StdoutFormat::Jsonl) {
+                    if let Some(library) = library.as_deref_mut() {
+                        add_to_library(library, &npc, None);
+                    }
+                    match serde_json::to_string(&npc) {
+                        Ok(line) => {
+                            println!("{}", line);
+                            if !warnings.is_empty() {
+                                status!("⚠ Repaired {} problem(s) in the model output: {}", warnings.len(), warnings.join("; "));
+                            }
+                            totals.succeeded += 1;
+                        }
+                        Err(e) => {
+                            eprintln!("✗ Error serializing NPC: {}", e);
+                            totals.failed += 1;
+                        }
+                    }
+                    status!();
+                    continue;
                 }
-                println!("  Background: {}", npc.background);
-                println!("  Alignment: {}", npc.alignment);
 
                 let index = if args.count > 1 { Some(i as usize) } else { None };
-                match save_npc_to_file(&npc, index) {
-                    Ok(filename) => {
-                        println!("✓ Saved to: {}", filename);
-                        success_count += 1;
+                match save_npc_to_file(&npc, &warnings, index, layout) {
+                    Ok(path) => {
+                        status!("✓ Saved to: {}", path.display());
+                        if let Some(library) = library.as_deref_mut() {
+                            add_to_library(library, &npc, Some(&path));
+                        }
+                        if !warnings.is_empty() {
+                            status!("⚠ Repaired {} problem(s) in the model output (see {})",
+                                warnings.len(), warnings_path(&path).display());
+                        }
+                        for &format in &args.format {
+                            // Obsidian notes link to each other, so they are written once the batch is done
+                            if format == export::Format::Obsidian {
+                                continue;
+                            }
+                            match export::write_export(&npc, format, &path, None) {
+                                Ok((path, warning)) => {
+                                    status!("✓ Exported to: {}", path.display());
+                                    if let Some(warning) = warning {
+                                        status!("⚠ Warning: {}: {}", path.display(), warning);
+                                    }
+                                    if format == export::Format::Html {
+                                        // The index goes in the output directory, so link relative to it
+                                        let href = path.strip_prefix(&layout.dir).unwrap_or(&path);
+                                        html_index.push(export::html::IndexEntry::new(&npc, href.display().to_string()));
+                                    }
+                                }
+                                Err(e) => eprintln!("✗ Error exporting {:?}: {:#}", format, e),
+                            }
+                        }
+                        totals.succeeded += 1;
+                        if args.format.contains(&export::Format::Obsidian) {
+                            obsidian_notes.push(npc);
+                        }
                     }
                     Err(e) => {
                         eprintln!("✗ Error saving file: {}", e);
-                        failed_count += 1;
+                        totals.failed += 1;
                     }
                 }
-                println!();
+                status!();
             }
             Err(e) => {
                 eprintln!("✗ Error generating NPC {}: {:#}", i, e);
@@ -896,8 +1775,8 @@ async fn main() -> Result<()> {
                     eprintln!("\nMake sure Ollama is running and you have the model installed:");
                     eprintln!("  ollama pull qwen2.5:32b-instruct");
                 }
-                failed_count += 1;
-                println!();
+                totals.failed += 1;
+                status!();
             }
         }
 
@@ -907,10 +1786,17 @@ async fn main() -> Result<()> {
         }
     }
 
-    println!("=== Summary ===");
-    println!("Successfully generated: {}", success_count);
-    if failed_count > 0 {
-        println!("Failed: {}", failed_count);
+    if !obsidian_notes.is_empty()
+        && let Err(e) = write_obsidian_notes(&obsidian_notes, &layout.dir)
+    {
+        eprintln!("✗ Error writing Obsidian notes: {:#}\n", e);
+    }
+
+    if html_index.len() > 1 {
+        match export::write_index(&html_index, &layout.dir) {
+            Ok(path) => status!("✓ Index of character sheets: {}\n", path.display()),
+            Err(e) => eprintln!("✗ Error writing index: {:#}\n", e),
+        }
     }
 
     Ok(())
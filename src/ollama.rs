@@ -0,0 +1,140 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+pub const MODEL: &str = "qwen2.5:32b-instruct";
+const GENERATE_URL: &str = "http://localhost:11434/api/generate";
+const CHAT_URL: &str = "http://localhost:11434/api/chat";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaRequest {
+    model: String,
+    prompt: String,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    options: Option<OllamaOptions>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaOptions {
+    temperature: f32,
+    top_p: f32,
+    top_k: i32,
+    num_predict: i32,  // Maximum number of tokens to generate
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    response: String,
+    /// "stop" when the model finished, "length" when it ran into `num_predict`
+    #[serde(default)]
+    done_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage<'a>>,
+    stream: bool,
+    options: OllamaOptions,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponse {
+    message: ChatResponseMessage,
+    #[serde(default)]
+    done_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatResponseMessage {
+    content: String,
+}
+
+/// Text produced by one request, and whether the model was cut off by the token limit.
+pub struct Generation {
+    pub text: String,
+    pub truncated: bool,
+}
+
+fn options() -> OllamaOptions {
+    OllamaOptions {
+        temperature: 1.2,  // Higher temperature for more randomness (default is 0.8)
+        top_p: 0.95,       // Nucleus sampling
+        top_k: 50,         // Top-k sampling for variety
+        num_predict: 4096, // Increase max tokens to prevent truncation
+    }
+}
+
+pub fn client() -> Result<reqwest::Client> {
+    // Extended timeout for AI generation
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(600)) // 10 minute timeout
+        .build()
+        .context("Failed to create HTTP client")
+}
+
+async fn post<T: Serialize, R: for<'de> Deserialize<'de>>(client: &reqwest::Client, url: &str, body: &T) -> Result<R> {
+    let response = client
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .context("Failed to connect to Ollama. Is it running?")?;
+
+    let response_text = response
+        .text()
+        .await
+        .context("Failed to read response text")?;
+
+    serde_json::from_str(&response_text)
+        .context(format!("Failed to parse Ollama response. Response was: {}",
+            &response_text[..response_text.floor_char_boundary(500)]))
+}
+
+/// Run `prompt` through the generate endpoint, constraining output to JSON when `json` is set.
+pub async fn generate(client: &reqwest::Client, prompt: &str, json: bool) -> Result<Generation> {
+    let request = OllamaRequest {
+        model: MODEL.to_string(),
+        prompt: prompt.to_string(),
+        stream: false,
+        format: json.then(|| "json".to_string()),
+        options: Some(options()),
+    };
+
+    let response: OllamaResponse = post(client, GENERATE_URL, &request).await?;
+    Ok(Generation {
+        truncated: response.done_reason.as_deref() == Some("length"),
+        text: response.response,
+    })
+}
+
+/// Ask the model to carry on from where `partial` stopped.
+///
+/// The partial output is sent back as an unfinished assistant turn, which Ollama renders as a
+/// prefix for the model to extend, so the returned text is the continuation only. JSON mode is
+/// left off here because the continuation is not a standalone JSON document.
+pub async fn continue_generation(client: &reqwest::Client, prompt: &str, partial: &str) -> Result<Generation> {
+    let request = ChatRequest {
+        model: MODEL,
+        messages: vec![
+            ChatMessage { role: "user", content: prompt },
+            ChatMessage { role: "assistant", content: partial },
+        ],
+        stream: false,
+        options: options(),
+    };
+
+    let response: ChatResponse = post(client, CHAT_URL, &request).await?;
+    Ok(Generation {
+        truncated: response.done_reason.as_deref() == Some("length"),
+        text: response.message.content,
+    })
+}
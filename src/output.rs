@@ -0,0 +1,226 @@
+//! Where saved NPCs go: the output directory, file name templates, and writes that never
+//! clobber another NPC or leave a half-written file behind.
+
+use anyhow::{Context, Result, bail};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use crate::NPC;
+
+/// Placeholders a file name template may use.
+const PLACEHOLDERS: [&str; 10] =
+    ["name", "species", "race", "class", "subclass", "level", "role", "alignment", "background", "index"];
+
+pub const DEFAULT_TEMPLATE: &str = "{name}";
+
+/// Make `text` safe as a single path component: no separators or characters Windows rejects,
+/// no apostrophes or quotes, spaces as underscores, and no leading dots.
+fn sanitize(text: &str) -> String {
+    let cleaned: String = text
+        .chars()
+        .filter_map(|c| match c {
+            '\'' | '"' | '`' | '\u{2018}' | '\u{2019}' => None,
+            '/' | '\\' | ':' | '*' | '?' | '<' | '>' | '|' => Some('-'),
+            c if c.is_whitespace() => Some('_'),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect();
+    let cleaned = cleaned.trim_start_matches(['.', '_', '-']).trim_end_matches(['.', '_']);
+    if cleaned.is_empty() { "unnamed".to_string() } else { cleaned.to_string() }
+}
+
+/// Where generated NPCs are saved: a directory and a file name template such as
+/// `{species}/{class}/{name}-{level}` (without extension; `/` makes subdirectories).
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub dir: PathBuf,
+    template: String,
+}
+
+impl Layout {
+    pub fn new(dir: PathBuf, template: &str) -> Result<Layout> {
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').context(format!("Unclosed placeholder in file name template \"{}\"", template))?;
+            let placeholder = &rest[start + 1..start + end];
+            if !PLACEHOLDERS.contains(&placeholder) {
+                bail!(
+                    "Unknown placeholder {{{}}} in file name template (available: {})",
+                    placeholder,
+                    PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(", ")
+                );
+            }
+            rest = &rest[start + end + 1..];
+        }
+        if template.split('/').all(|part| part.trim().is_empty()) {
+            bail!("File name template \"{}\" is empty", template);
+        }
+        Ok(Layout { dir, template: template.to_string() })
+    }
+
+    /// The same layout in a subdirectory of this one's directory.
+    pub fn within(&self, folder: &str) -> Layout {
+        Layout { dir: self.dir.join(sanitize(folder)), template: self.template.clone() }
+    }
+
+    /// Path (without extension) the template gives `npc`. Each component is filled in and
+    /// sanitized separately, so a slash in a value never creates a directory. Batches number
+    /// their files with `{index}`, or with an `_N` suffix when the template doesn't use it.
+    fn stem(&self, npc: &NPC, index: Option<usize>) -> PathBuf {
+        let values = [
+            ("name", npc.name.clone()),
+            ("species", npc.race.clone()),
+            ("race", npc.race.clone()),
+            ("class", npc.class_name.to_string()),
+            ("subclass", npc.subclass.clone().unwrap_or_default()),
+            ("level", npc.level.to_string()),
+            ("role", npc.role.clone()),
+            ("alignment", npc.alignment.to_string()),
+            ("background", npc.background.clone()),
+            ("index", index.map(|i| i.to_string()).unwrap_or_default()),
+        ];
+        let mut path = self.dir.clone();
+        let parts: Vec<&str> = self.template.split('/').filter(|p| !p.trim().is_empty()).collect();
+        for (i, part) in parts.iter().enumerate() {
+            let mut filled = part.to_string();
+            for (key, value) in &values {
+                filled = filled.replace(&format!("{{{}}}", key), value);
+            }
+            if i == parts.len() - 1
+                && let Some(index) = index
+                && !self.template.contains("{index}")
+            {
+                filled = format!("{}_{}", filled, index);
+            }
+            path.push(sanitize(&filled));
+        }
+        path
+    }
+
+    /// Save `contents` as a new `.json` file for `npc` and return its path: the template's path,
+    /// or the same with `-2`, `-3`... appended. The name is claimed atomically, so neither an
+    /// existing file nor one another run creates at the same moment is ever replaced.
+    pub fn create_json(&self, npc: &NPC, index: Option<usize>, contents: impl AsRef<[u8]>) -> Result<PathBuf> {
+        let stem = self.stem(npc, index);
+        let name = stem.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let candidates = (1..).map(|n| match n {
+            1 => stem.with_file_name(format!("{}.json", name)),
+            n => stem.with_file_name(format!("{}-{}.json", name, n)),
+        });
+        create_new(candidates, contents.as_ref())
+    }
+}
+
+/// Write `contents` under the first of `candidates` that doesn't exist yet. The contents go to a
+/// temporary file first and are hard-linked into place, which fails instead of replacing a file
+/// that appeared in the meantime; on filesystems without hard links the name is claimed with an
+/// exclusive create instead.
+fn create_new(candidates: impl IntoIterator<Item = PathBuf>, contents: &[u8]) -> Result<PathBuf> {
+    let mut candidates = candidates.into_iter().peekable();
+    let first = candidates.peek().context("No file name to save to")?.clone();
+    if let Some(dir) = first.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).context(format!("Failed to create directory {}", dir.display()))?;
+    }
+    let file_name = first.file_name().unwrap_or_default().to_string_lossy();
+    let temp = first.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+    fs::write(&temp, contents).context(format!("Failed to write to file: {}", temp.display()))?;
+
+    let mut linking = true;
+    let result = loop {
+        let Some(path) = candidates.next() else {
+            break Err(anyhow::anyhow!("No free file name beside {}", first.display()));
+        };
+        let claimed = if linking {
+            match fs::hard_link(&temp, &path) {
+                Err(e) if e.kind() != ErrorKind::AlreadyExists => {
+                    linking = false;
+                    write_exclusive(&path, contents)
+                }
+                other => other,
+            }
+        } else {
+            write_exclusive(&path, contents)
+        };
+        match claimed {
+            Ok(()) => break Ok(path),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => break Err(anyhow::Error::new(e).context(format!("Failed to write to file: {}", path.display()))),
+        }
+    };
+    let _ = fs::remove_file(&temp);
+    result
+}
+
+fn write_exclusive(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    if let Err(e) = file.write_all(contents) {
+        let _ = fs::remove_file(path);
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Write `contents` to a temporary file beside `path` and rename it into place, so readers
+/// never see a partial file and a failed write leaves any previous version intact. This
+/// replaces whatever is at `path`; new NPC files go through `Layout::create_json` instead.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).context(format!("Failed to create directory {}", dir.display()))?;
+    }
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+    fs::write(&temp, contents).context(format!("Failed to write to file: {}", temp.display()))?;
+    fs::rename(&temp, path).map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        anyhow::Error::new(e).context(format!("Failed to write to file: {}", path.display()))
+    })
+}
+
+/// Copy `path` to the first free `<file>.bak`, `<file>.2.bak`, ... beside it, so rewriting a
+/// file in place never loses an earlier backup.
+pub fn backup(path: &Path) -> Result<PathBuf> {
+    let contents = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+    let candidates = (1..).map(|n| match n {
+        1 => PathBuf::from(format!("{}.bak", path.display())),
+        n => PathBuf::from(format!("{}.{}.bak", path.display(), n)),
+    });
+    create_new(candidates, &contents).context(format!("Failed to back up {}", path.display()))
+}
+
+/// Write `npc` back over the file it was loaded from, keeping the previous version as a backup.
+/// Returns the backup's path.
+pub fn rewrite(path: &Path, npc: &NPC) -> Result<PathBuf> {
+    let json = serde_json::to_string_pretty(npc).context("Failed to serialize NPC to JSON")?;
+    rewrite_contents(path, json)
+}
+
+/// Same as `rewrite`, for contents the caller has already put together.
+pub fn rewrite_contents(path: &Path, contents: impl AsRef<[u8]>) -> Result<PathBuf> {
+    let backup = backup(path)?;
+    write_atomic(path, contents)?;
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_new_never_replaces_an_existing_file() {
+        let dir = std::env::temp_dir().join(format!("npcforge-output-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let candidates = || (1..).map(|n| dir.join(format!("npc-{}.json", n)));
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("npc-1.json"), "first").unwrap();
+        assert_eq!(create_new(candidates(), b"second").unwrap(), dir.join("npc-2.json"));
+        assert_eq!(create_new(candidates(), b"third").unwrap(), dir.join("npc-3.json"));
+        assert_eq!(fs::read_to_string(dir.join("npc-1.json")).unwrap(), "first");
+        assert_eq!(fs::read_to_string(dir.join("npc-2.json")).unwrap(), "second");
+        // Only the claimed files are left; the temporary file is gone
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
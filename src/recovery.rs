@@ -0,0 +1,288 @@
+//! Salvaging NPC JSON that was cut off by the token limit.
+//!
+//! When the model runs into `num_predict` the output stops mid-object. `close_partial_json`
+//! keeps every value that was completely written, drops the one in progress and closes the
+//! open objects and arrays, so the caller can tell which top-level sections are missing and
+//! ask the model for just those.
+
+use serde_json::Value;
+
+use crate::types::{Class, Classes};
+
+/// Top-level sections of the NPC in the order the prompt asks for them. Derived fields
+/// (passive scores, size), multiclass-only ones and tool proficiencies, which many NPCs
+/// rightly have none of, are left out. "spells" is only wanted from spellcasters (see
+/// `missing_sections`); "features" stays because every class has some from 1st level.
+pub const SECTIONS: &[&str] = &[
+    "name",
+    "race",
+    "class",
+    "subclass",
+    "level",
+    "role",
+    "background",
+    "alignment",
+    "fighting_preference",
+    "ability_scores",
+    "hit_points",
+    "armor_class",
+    "initiative",
+    "speed",
+    "proficiency_bonus",
+    "skills",
+    "saving_throws",
+    "languages",
+    "attacks",
+    "spells",
+    "equipment",
+    "personality",
+    "backstory",
+    "appearance",
+    "features",
+];
+
+/// The complete part of a truncated object.
+pub struct Salvaged {
+    pub value: Value,
+    /// Top-level key whose value was cut off part-way and closed early, if any
+    pub cut_inside: Option<String>,
+}
+
+impl Salvaged {
+    /// Sections that have to be regenerated: absent ones, plus the one that was cut short.
+    /// Spells are skipped for NPCs whose class doesn't cast at their level.
+    pub fn missing_sections(&self) -> Vec<&'static str> {
+        let casts = self.casts_spells();
+        SECTIONS
+            .iter()
+            .copied()
+            .filter(|&section| section != "spells" || casts)
+            .filter(|section| {
+                self.value.get(section).is_none() || self.cut_inside.as_deref() == Some(section)
+            })
+            .collect()
+    }
+
+    /// Whether the salvaged class and level make a spellcaster: a full caster or Warlock, or a
+    /// half caster from 2nd level. For a homebrew class, or without a readable one, there is no
+    /// telling, so yes.
+    fn casts_spells(&self) -> bool {
+        let Some(classes) = self.value.get("class").and_then(Value::as_str).and_then(|c| c.parse::<Classes>().ok()) else {
+            return true;
+        };
+        let level = self.value.get("level").and_then(Value::as_u64).unwrap_or(1);
+        classes.0.iter().any(|class| {
+            class.is_spellcaster()
+                || (class.spellcasting_ability().is_some() && level >= 2)
+                || matches!(class, Class::Other(_))
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    ExpectKey,
+    ExpectColon,
+    ExpectValue,
+    AfterValue,
+}
+
+#[derive(Clone, Copy)]
+struct Container {
+    is_object: bool,
+    state: State,
+}
+
+/// Point where the text can be cut and closed into valid JSON.
+struct CutPoint {
+    end: usize,
+    closers: String,
+    top_level_key: Option<String>,
+    depth: usize,
+}
+
+fn closers_for(stack: &[Container]) -> String {
+    stack.iter().rev().map(|c| if c.is_object { '}' } else { ']' }).collect()
+}
+
+/// Close a truncated JSON object, keeping only values that were fully written.
+pub fn close_partial_json(text: &str) -> Option<Salvaged> {
+    let start = text.find('{')?;
+    let bytes = text.as_bytes();
+    let mut stack: Vec<Container> = Vec::new();
+    let mut last_cut: Option<CutPoint> = None;
+    let mut top_level_key: Option<String> = None;
+    let mut string_start: Option<usize> = None;
+    let mut escaped = false;
+    // Start of a bare number/literal value that has not been terminated yet
+    let mut scalar_in_progress = false;
+
+    let mut i = start;
+    while i < bytes.len() {
+        let b = bytes[i];
+
+        if let Some(s) = string_start {
+            if escaped {
+                escaped = false;
+            } else if b == b'\\' {
+                escaped = true;
+            } else if b == b'"' {
+                string_start = None;
+                let top = stack.last_mut()?;
+                match top.state {
+                    State::ExpectKey => {
+                        top.state = State::ExpectColon;
+                        if stack.len() == 1 {
+                            top_level_key = serde_json::from_str(&text[s..=i]).ok();
+                        }
+                    }
+                    _ => {
+                        top.state = State::AfterValue;
+                        last_cut = Some(CutPoint {
+                            end: i + 1,
+                            closers: closers_for(&stack),
+                            top_level_key: top_level_key.clone(),
+                            depth: stack.len(),
+                        });
+                    }
+                }
+            }
+            i += 1;
+            continue;
+        }
+
+        if scalar_in_progress && matches!(b, b',' | b'}' | b']' | b' ' | b'\n' | b'\r' | b'\t') {
+            scalar_in_progress = false;
+            if let Some(top) = stack.last_mut() {
+                top.state = State::AfterValue;
+            }
+            last_cut = Some(CutPoint {
+                end: i,
+                closers: closers_for(&stack),
+                top_level_key: top_level_key.clone(),
+                depth: stack.len(),
+            });
+        }
+
+        match b {
+            b'"' => string_start = Some(i),
+            b'{' | b'[' => {
+                stack.push(Container {
+                    is_object: b == b'{',
+                    state: if b == b'{' { State::ExpectKey } else { State::ExpectValue },
+                });
+                last_cut = Some(CutPoint {
+                    end: i + 1,
+                    closers: closers_for(&stack),
+                    top_level_key: top_level_key.clone(),
+                    depth: stack.len(),
+                });
+            }
+            b'}' | b']' => {
+                stack.pop();
+                match stack.last_mut() {
+                    Some(top) => {
+                        top.state = State::AfterValue;
+                        last_cut = Some(CutPoint {
+                            end: i + 1,
+                            closers: closers_for(&stack),
+                            top_level_key: top_level_key.clone(),
+                            depth: stack.len(),
+                        });
+                    }
+                    // The object was complete after all
+                    None => return serde_json::from_str(&text[start..=i]).ok().map(|value| Salvaged { value, cut_inside: None }),
+                }
+            }
+            b':' => {
+                if let Some(top) = stack.last_mut() {
+                    top.state = State::ExpectValue;
+                }
+            }
+            b',' => {
+                if let Some(top) = stack.last_mut() {
+                    top.state = if top.is_object { State::ExpectKey } else { State::ExpectValue };
+                }
+            }
+            b if b.is_ascii_whitespace() => {}
+            _ => scalar_in_progress = true,
+        }
+        i += 1;
+    }
+
+    let cut = last_cut?;
+    let mut closed = text[start..cut.end].trim_end().to_string();
+    // A cut right after a complete member can leave a dangling comma in front of it
+    if closed.ends_with(',') {
+        closed.pop();
+    }
+    closed.push_str(&cut.closers);
+    let value: Value = serde_json::from_str(&closed).ok()?;
+    Some(Salvaged {
+        value,
+        cut_inside: if cut.depth > 1 { cut.top_level_key } else { None },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn cut_inside_a_string_drops_the_unfinished_value() {
+        let salvaged = close_partial_json(r#"{"name": "Mira", "backstory": "She grew up in"#).unwrap();
+        assert_eq!(salvaged.value, json!({"name": "Mira"}));
+        assert_eq!(salvaged.cut_inside, None);
+        assert!(salvaged.missing_sections().contains(&"backstory"));
+
+        let salvaged = close_partial_json(r#"{"personality": {"ideals": "Freedom", "bonds": "Her sis"#).unwrap();
+        assert_eq!(salvaged.value, json!({"personality": {"ideals": "Freedom"}}));
+        assert_eq!(salvaged.cut_inside.as_deref(), Some("personality"));
+        assert!(salvaged.missing_sections().contains(&"personality"));
+    }
+
+    #[test]
+    fn cut_after_an_escape_stays_inside_the_string() {
+        let salvaged = close_partial_json(r#"{"name": "Mira", "backstory": "She said \"run\" and \"#).unwrap();
+        assert_eq!(salvaged.value, json!({"name": "Mira"}));
+
+        let salvaged = close_partial_json(r#"{"name": "Mira \"Quick\" Vale", "race": "Half\"#).unwrap();
+        assert_eq!(salvaged.value, json!({"name": "Mira \"Quick\" Vale"}));
+    }
+
+    #[test]
+    fn cut_after_a_key_drops_the_key() {
+        for text in [r#"{"name": "Mira", "race""#, r#"{"name": "Mira", "race": "#] {
+            let salvaged = close_partial_json(text).unwrap();
+            assert_eq!(salvaged.value, json!({"name": "Mira"}), "{}", text);
+            assert!(salvaged.missing_sections().contains(&"race"));
+        }
+    }
+
+    #[test]
+    fn cut_after_a_trailing_comma_keeps_the_member_before_it() {
+        let salvaged = close_partial_json(r#"{"name": "Mira", "level": 5,"#).unwrap();
+        assert_eq!(salvaged.value, json!({"name": "Mira", "level": 5}));
+        assert_eq!(salvaged.cut_inside, None);
+
+        let salvaged = close_partial_json(r#"{"name": "Mira", "languages": ["Common", "Elvish", "#).unwrap();
+        assert_eq!(salvaged.value, json!({"name": "Mira", "languages": ["Common", "Elvish"]}));
+        assert_eq!(salvaged.cut_inside.as_deref(), Some("languages"));
+    }
+
+    #[test]
+    fn spells_are_only_regenerated_for_casters() {
+        let fighter = close_partial_json(r#"{"name": "Mira", "class": "Fighter", "level": 5, "backstory": "She"#).unwrap();
+        assert!(!fighter.missing_sections().contains(&"spells"));
+        assert!(fighter.missing_sections().contains(&"features"));
+        assert!(!fighter.missing_sections().contains(&"tool_proficiencies"));
+
+        let wizard = close_partial_json(r#"{"name": "Mira", "class": "Fighter/Wizard", "level": 5, "#).unwrap();
+        assert!(wizard.missing_sections().contains(&"spells"));
+
+        let paladin = |level: u8| format!(r#"{{"name": "Mira", "class": "Paladin", "level": {}, "#, level);
+        assert!(!close_partial_json(&paladin(1)).unwrap().missing_sections().contains(&"spells"));
+        assert!(close_partial_json(&paladin(2)).unwrap().missing_sections().contains(&"spells"));
+    }
+}
@@ -0,0 +1,301 @@
+//! `npcforge reroll`: regenerate one part of a saved NPC.
+//!
+//! The rest of the character goes to the model as fixed context, only the chosen section is
+//! asked for, and the answer is run through the tolerant parser before it replaces that one
+//! field in the file's own JSON. Everything else keeps its bytes, including fields from older
+//! versions or edited by hand, and the previous file is kept as a backup.
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+use crate::{NPC, lenient, library, ollama, output, schema};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Section {
+    Backstory,
+    /// Traits, ideals, bonds and flaws
+    Personality,
+    Appearance,
+    /// The spell list (spellcasting ability, save DC and slots are kept)
+    Spells,
+    /// Armor, weapons, gear and treasure
+    Equipment,
+    Name,
+}
+
+impl Section {
+    fn label(self) -> &'static str {
+        match self {
+            Section::Backstory => "backstory",
+            Section::Personality => "personality",
+            Section::Appearance => "appearance",
+            Section::Spells => "spell list",
+            Section::Equipment => "equipment",
+            Section::Name => "name",
+        }
+    }
+
+    /// Key of the answer object, and of the field it replaces.
+    fn key(self) -> &'static str {
+        match self {
+            Section::Backstory => "backstory",
+            Section::Personality => "personality",
+            Section::Appearance => "appearance",
+            Section::Spells => "spells_known",
+            Section::Equipment => "equipment",
+            Section::Name => "name",
+        }
+    }
+
+    /// What to write, with the JSON shape of the answer.
+    fn instructions(self) -> &'static str {
+        match self {
+            Section::Backstory => {
+                r#"Write a NEW backstory (3-5 paragraphs, separated by blank lines) covering childhood, training, major life events, important relationships and their current situation. It must fit their role, background, personality and appearance.
+{"backstory": "..."}"#
+            }
+            Section::Personality => {
+                r#"Write NEW personality traits, ideals, bonds and flaws that fit their backstory, role and alignment.
+{"personality": {"traits": ["...", "..."], "ideals": "...", "bonds": "...", "flaws": "..."}}"#
+            }
+            Section::Appearance => {
+                r#"Write a NEW physical appearance that fits their species, age and backstory.
+{"appearance": {"age": 30, "height": "5'8\"", "weight": "140 lbs", "eyes": "...", "hair": "...", "skin": "...", "distinguishing_features": ["...", "..."]}}"#
+            }
+            Section::Spells => {
+                r#"Choose a NEW list of spells known or prepared that suits their classes, levels, spell slots, fighting preference and personality. Only use spell levels they have slots for. Use an empty list for levels without spells.
+{"spells_known": {"cantrips": [], "level_1": [], "level_2": [], "level_3": [], "level_4": [], "level_5": [], "level_6": [], "level_7": [], "level_8": [], "level_9": []}}"#
+            }
+            Section::Equipment => {
+                r#"Choose NEW equipment appropriate to their classes, level, role and backstory. Keep the weapons their attacks use.
+{"equipment": {"armor": ["..."], "weapons": ["..."], "gear": ["..."], "treasure": {"gold": 0, "items": ["..."]}}}"#
+            }
+            Section::Name => {
+                r#"Choose a NEW full name that suits their species, background and culture. It must be different from their current name.
+{"name": "..."}"#
+            }
+        }
+    }
+}
+
+/// The NPC as JSON, with the part being regenerated taken out (except the name, which the
+/// model needs to know in order to avoid it).
+fn context_json(npc: &NPC, section: Section) -> Result<Value> {
+    let mut value = serde_json::to_value(npc).context("Failed to serialize NPC to JSON")?;
+    let Value::Object(obj) = &mut value else {
+        bail!("NPC did not serialize to a JSON object");
+    };
+    obj.remove("schema_version");
+    match section {
+        Section::Spells => {
+            if let Some(Value::Object(spells)) = obj.get_mut("spells") {
+                spells.remove("spells_known");
+            }
+        }
+        Section::Name => {}
+        _ => {
+            obj.remove(section.key());
+        }
+    }
+    Ok(value)
+}
+
+fn create_reroll_prompt(context: &Value, section: Section) -> String {
+    format!(
+        "You are a D&D 2024 character generator. Here is an existing character. Everything shown is \
+        FIXED and must stay consistent; you are only replacing their {}.\n\n{}\n\n{}\n\n\
+        Output ONLY valid JSON with exactly that structure (no additional text):",
+        section.label(),
+        serde_json::to_string_pretty(context).unwrap_or_default(),
+        section.instructions()
+    )
+}
+
+/// Ask the model for the section and return its raw JSON value.
+async fn generate_section(prompt: &str, section: Section) -> Result<Value> {
+    let client = ollama::client()?;
+    let output = ollama::generate(&client, prompt, true).await?;
+    let value: Value = serde_json::from_str(&output.text).context("Regenerated section was not valid JSON")?;
+    match value {
+        Value::Object(mut obj) => match obj.remove(section.key()) {
+            Some(value) => Ok(value),
+            // Answered with the section's own fields at the top level
+            None if matches!(section, Section::Personality | Section::Appearance | Section::Spells | Section::Equipment) => {
+                Ok(Value::Object(obj))
+            }
+            None => bail!("The model's answer did not contain \"{}\"", section.key()),
+        },
+        Value::String(_) if matches!(section, Section::Backstory | Section::Name) => Ok(value),
+        _ => bail!("The model's answer was not a JSON object"),
+    }
+}
+
+/// Put `new` in place of the section, parse the result like fresh model output, and move only
+/// that field into `npc`.
+fn replace_section(npc: &mut NPC, section: Section, new: Value, warnings: &mut Vec<String>) -> Result<()> {
+    let mut value = serde_json::to_value(&*npc).context("Failed to serialize NPC to JSON")?;
+    match section {
+        Section::Spells => value["spells"]["spells_known"] = new,
+        _ => value[section.key()] = new,
+    }
+    let parsed = lenient::parse_npc_value(value, warnings)?;
+    match section {
+        Section::Backstory => npc.backstory = parsed.backstory,
+        Section::Personality => npc.personality = parsed.personality,
+        Section::Appearance => npc.appearance = parsed.appearance,
+        Section::Equipment => npc.equipment = parsed.equipment,
+        Section::Name => npc.name = parsed.name,
+        Section::Spells => {
+            if let (Some(spells), Some(parsed)) = (npc.spells.as_mut(), parsed.spells) {
+                spells.spells_known = parsed.spells_known;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Put the rerolled section of `npc` into `original`, the file's text, leaving every other
+/// field as it was. Files NPCForge wrote (pretty-printed, two-space indent) keep their bytes
+/// outside the section; other formatting is normalized to that.
+fn splice_section(original: &str, npc: &NPC, section: Section) -> Result<String> {
+    let mut value: Value = serde_json::from_str(original).context("Saved file is not valid JSON")?;
+    let new = match section {
+        Section::Backstory => serde_json::to_value(&npc.backstory),
+        Section::Personality => serde_json::to_value(&npc.personality),
+        Section::Appearance => serde_json::to_value(&npc.appearance),
+        Section::Equipment => serde_json::to_value(&npc.equipment),
+        Section::Name => serde_json::to_value(&npc.name),
+        Section::Spells => serde_json::to_value(npc.spells.as_ref().map(|spells| &spells.spells_known)),
+    }
+    .context("Failed to serialize NPC to JSON")?;
+
+    let target = match section {
+        Section::Spells => value.get_mut("spells").and_then(Value::as_object_mut),
+        _ => value.as_object_mut(),
+    };
+    let Some(target) = target else {
+        bail!("The saved file has no {} to replace", section.label());
+    };
+    match target.get_mut(section.key()) {
+        Some(field) => *field = new,
+        None => {
+            target.insert(section.key().to_string(), new);
+        }
+    }
+
+    let mut json = serde_json::to_string_pretty(&value).context("Failed to serialize NPC to JSON")?;
+    json.push_str(&original[original.trim_end().len()..]);
+    Ok(json)
+}
+
+/// `npcforge reroll`: regenerate `section` of the NPC saved at `path` and rewrite the file,
+/// keeping the previous version as `<file>.bak`.
+pub async fn reroll_file(path: &Path, section: Section, library: Option<&mut library::Library>) -> Result<()> {
+    println!("=== NPCForge - Rerolling {} ===\n", section.label());
+
+    let original = fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    let (mut npc, _) = schema::load_npc_file(path)?;
+    if section == Section::Spells && npc.spells.is_none() {
+        bail!("{} has no spellcasting to reroll", npc.name);
+    }
+    // Only whitespace can change outside the section, and only in files formatted by hand
+    let reformatted = serde_json::from_str::<Value>(&original)
+        .ok()
+        .and_then(|value| serde_json::to_string_pretty(&value).ok());
+    if reformatted.as_deref() != Some(original.trim_end()) {
+        println!("⚠ {} is not formatted the way NPCForge writes files; its whitespace will be normalized", path.display());
+    }
+
+    let prompt = create_reroll_prompt(&context_json(&npc, section)?, section);
+    println!("Regenerating the {} of {} with Ollama...\n", section.label(), npc.name);
+    let new = generate_section(&prompt, section).await?;
+
+    let mut warnings = Vec::new();
+    let old_name = npc.name.clone();
+    replace_section(&mut npc, section, new, &mut warnings)?;
+
+    let backup = output::rewrite_contents(path, splice_section(&original, &npc, section)?)?;
+
+    if section == Section::Name {
+        println!("✓ Renamed {} to {}", old_name, npc.name);
+    } else {
+        println!("✓ Rerolled the {} of {}", section.label(), npc.name);
+    }
+    println!("✓ Saved to: {}", path.display());
+    println!("  Previous version: {}", backup.display());
+    if !warnings.is_empty() {
+        println!("⚠ Repaired {} problem(s) in the model output:", warnings.len());
+        for warning in &warnings {
+            println!("    - {}", warning);
+        }
+    }
+    if let Some(library) = library {
+        crate::add_to_library(library, &npc, Some(path));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A file in an older shape: no schema version, a field NPCForge doesn't know, saving
+    /// throws as names and only the proficient skills.
+    fn older_file() -> String {
+        let mut value: Value = serde_json::from_str(include_str!("../tests/fixtures/seraphine.json")).unwrap();
+        let obj = value.as_object_mut().unwrap();
+        obj.remove("schema_version");
+        obj.insert("dm_notes".to_string(), json!("Owes the Guild 200 gp"));
+        obj.insert("saving_throws".to_string(), json!(["Dexterity", "Charisma"]));
+        obj.insert("skills".to_string(), json!([{"name": "Deception", "modifier": 10, "proficient": true}]));
+        serde_json::to_string_pretty(&value).unwrap() + "\n"
+    }
+
+    #[test]
+    fn splicing_keeps_the_bytes_outside_the_section() {
+        let original = older_file();
+        let mut npc = schema::upgrade_value(serde_json::from_str(&original).unwrap()).unwrap().0;
+        let old = serde_json::to_string(&npc.backstory).unwrap();
+        npc.backstory = "A new life in the north.".to_string();
+
+        let spliced = splice_section(&original, &npc, Section::Backstory).unwrap();
+        assert_eq!(spliced, original.replace(&old, "\"A new life in the north.\""));
+    }
+
+    #[test]
+    fn splicing_spells_replaces_only_the_spell_list() {
+        let original = older_file();
+        let mut npc = schema::upgrade_value(serde_json::from_str(&original).unwrap()).unwrap().0;
+        npc.spells.as_mut().unwrap().spells_known.level_2 = vec!["Shatter".to_string()];
+
+        let spliced: Value = serde_json::from_str(&splice_section(&original, &npc, Section::Spells).unwrap()).unwrap();
+        let mut expected: Value = serde_json::from_str(&original).unwrap();
+        expected["spells"]["spells_known"]["level_2"] = json!(["Shatter"]);
+        assert_eq!(spliced, expected);
+    }
+
+    #[test]
+    fn replace_section_takes_only_that_section_from_the_answer() {
+        let mut npc: NPC = serde_json::from_str(include_str!("../tests/fixtures/seraphine.json")).unwrap();
+        let name = npc.name.clone();
+        let mut warnings = Vec::new();
+        let answer = json!({"traits": "Quick to laugh", "ideals": "Loyalty", "bonds": ["Her sister", "The Guild"], "flaws": "Greed"});
+        replace_section(&mut npc, Section::Personality, answer, &mut warnings).unwrap();
+
+        assert_eq!(npc.personality.traits, ["Quick to laugh"]);
+        assert_eq!(npc.personality.ideals, "Loyalty");
+        assert_eq!(npc.personality.bonds, "Her sister; The Guild");
+        assert_eq!(npc.name, name);
+        assert_eq!(warnings.len(), 2);
+
+        replace_section(&mut npc, Section::Spells, json!({"cantrips": ["Minor Illusion"]}), &mut warnings).unwrap();
+        let spells = npc.spells.as_ref().unwrap();
+        assert_eq!(spells.spells_known.cantrips, ["Minor Illusion"]);
+        assert!(spells.spells_known.level_1.is_empty());
+        assert_eq!(spells.spell_save_dc, 15);
+    }
+}
@@ -0,0 +1,226 @@
+//! `npcforge roster`: one-line-per-NPC summaries of a directory of saved NPCs, as CSV, TSV or
+//! JSON Lines for spreadsheets and scripts.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::{Value, json};
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{NPC, schema};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum RosterFormat {
+    /// Comma-separated values with a header row
+    Csv,
+    /// Tab-separated values with a header row
+    Tsv,
+    /// One JSON object per line
+    Jsonl,
+    /// Aligned columns for reading in a terminal
+    Table,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Column {
+    Name,
+    /// Race
+    Species,
+    /// Classes with levels, e.g. "Fighter 5 / Rogue 3"
+    Class,
+    Subclass,
+    Level,
+    Alignment,
+    Role,
+    Ac,
+    Hp,
+    /// Spell save DC (empty for non-casters)
+    SaveDc,
+    /// Path of the saved NPC file
+    File,
+}
+
+impl Column {
+    pub const ALL: [Column; 11] = [
+        Column::Name,
+        Column::Species,
+        Column::Class,
+        Column::Subclass,
+        Column::Level,
+        Column::Alignment,
+        Column::Role,
+        Column::Ac,
+        Column::Hp,
+        Column::SaveDc,
+        Column::File,
+    ];
+
+    fn header(self) -> &'static str {
+        match self {
+            Column::Name => "name",
+            Column::Species => "species",
+            Column::Class => "class",
+            Column::Subclass => "subclass",
+            Column::Level => "level",
+            Column::Alignment => "alignment",
+            Column::Role => "role",
+            Column::Ac => "ac",
+            Column::Hp => "hp",
+            Column::SaveDc => "save_dc",
+            Column::File => "file",
+        }
+    }
+}
+
+/// The summary of one NPC file.
+struct Row {
+    npc: NPC,
+    file: PathBuf,
+}
+
+impl Row {
+    fn value(&self, column: Column) -> Value {
+        let npc = &self.npc;
+        match column {
+            Column::Name => json!(npc.name),
+            Column::Species => json!(npc.race),
+            Column::Class => json!(npc.class_summary()),
+            Column::Subclass => json!(npc.subclass),
+            Column::Level => json!(npc.level),
+            Column::Alignment => json!(npc.alignment.to_string()),
+            Column::Role => json!(npc.role),
+            Column::Ac => json!(npc.armor_class),
+            Column::Hp => json!(npc.hit_points.max),
+            Column::SaveDc => json!(npc.spells.as_ref().map(|s| s.spell_save_dc).filter(|dc| *dc > 0)),
+            Column::File => json!(self.file.display().to_string()),
+        }
+    }
+
+    fn text(&self, column: Column) -> String {
+        match self.value(column) {
+            Value::String(s) => s,
+            Value::Null => String::new(),
+            other => other.to_string(),
+        }
+    }
+}
+
+/// Numbers sort numerically, text case-insensitively; empty values go last.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+        _ => a.to_string().cmp(&b.to_string()),
+    }
+}
+
+/// CSV field, quoted when it contains a separator, quote or line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// TSV has no quoting, so tabs and line breaks become spaces.
+fn tsv_field(text: &str) -> String {
+    text.replace(['\t', '\n', '\r'], " ")
+}
+
+fn render(rows: &[Row], columns: &[Column], format: RosterFormat) -> String {
+    let mut out = String::new();
+    match format {
+        RosterFormat::Csv | RosterFormat::Tsv => {
+            let (separator, field): (&str, fn(&str) -> String) = match format {
+                RosterFormat::Csv => (",", csv_field),
+                _ => ("\t", tsv_field),
+            };
+            let header: Vec<&str> = columns.iter().map(|c| c.header()).collect();
+            out.push_str(&header.join(separator));
+            out.push('\n');
+            for row in rows {
+                let cells: Vec<String> = columns.iter().map(|&c| field(&row.text(c))).collect();
+                out.push_str(&cells.join(separator));
+                out.push('\n');
+            }
+        }
+        RosterFormat::Table => {
+            let cells: Vec<Vec<String>> = rows.iter().map(|row| columns.iter().map(|&c| tsv_field(&row.text(c))).collect()).collect();
+            let mut widths: Vec<usize> = columns.iter().map(|c| c.header().len()).collect();
+            for row in &cells {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.chars().count());
+                }
+            }
+            let mut line = |cells: Vec<String>| {
+                let padded: Vec<String> = cells.iter().zip(&widths).map(|(cell, &width)| format!("{:<width$}", cell)).collect();
+                out.push_str(padded.join("  ").trim_end());
+                out.push('\n');
+            };
+            line(columns.iter().map(|c| c.header().to_uppercase()).collect());
+            for row in cells {
+                line(row);
+            }
+        }
+        RosterFormat::Jsonl => {
+            // Written by hand rather than through a `Map` so keys keep the selected column order
+            for row in rows {
+                let fields: Vec<String> =
+                    columns.iter().map(|&c| format!("{}:{}", Value::from(c.header()), row.value(c))).collect();
+                out.push_str(&format!("{{{}}}\n", fields.join(",")));
+            }
+        }
+    }
+    out
+}
+
+/// Summarize every saved NPC under `dir`, writing to `output` or stdout. Files that fail to
+/// load are reported on stderr and left out.
+pub fn write_roster(
+    dir: &Path,
+    format: RosterFormat,
+    columns: &[Column],
+    sort: &[Column],
+    descending: bool,
+    output: Option<&Path>,
+) -> Result<()> {
+    let mut rows = Vec::new();
+    for (file, npc) in schema::load_npc_directory(dir)? {
+        match npc {
+            Ok(npc) => rows.push(Row { npc, file }),
+            Err(e) => eprintln!("✗ {}: {:#}", file.display(), e),
+        }
+    }
+
+    // Stable sort, so rows that tie on every key stay in file order
+    rows.sort_by(|a, b| {
+        let ordering = sort
+            .iter()
+            .map(|&column| compare(&a.value(column), &b.value(column)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal);
+        if descending { ordering.reverse() } else { ordering }
+    });
+
+    let columns = if columns.is_empty() { &Column::ALL[..] } else { columns };
+    let text = render(&rows, columns, format);
+    match output {
+        Some(path) => {
+            fs::write(path, text).context(format!("Failed to write to file: {}", path.display()))?;
+            eprintln!("✓ {} NPC(s) -> {}", rows.len(), path.display());
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+/// `npcforge list`: the saved NPCs under `dir` as a table, by name.
+pub fn list_directory(dir: &Path) -> Result<()> {
+    let columns = [Column::Name, Column::Species, Column::Class, Column::Alignment, Column::Role, Column::File];
+    write_roster(dir, RosterFormat::Table, &columns, &[Column::Name], false, None)
+}
@@ -0,0 +1,335 @@
+//! Versioning of saved NPC files.
+//!
+//! Every file carries a `schema_version`. Files written before the field existed are version 1.
+//! Loading goes through `upgrade_value`, which applies one migration step per version on the raw
+//! JSON, then the tolerant parser and the derived-field completion, so any file ever written by
+//! NPCForge comes back as a current `NPC`.
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::export::is_melee_range;
+use crate::stats::proficiency_bonus_for_level;
+use crate::types::Skill;
+use crate::{NPC, lenient, output};
+
+/// Version written to every new file.
+///
+/// - 1: original format (no `schema_version`); `role` and `fighting_preference` may be absent,
+///   saving throws are a list of names and skills only list proficiencies
+/// - 2: `schema_version` field, role and fighting preference always present, all 18 skills,
+///   numeric saving throws, passive scores, size and normalized alignment/class spellings
+pub const CURRENT_SCHEMA_VERSION: u32 = 2;
+
+pub fn default_schema_version() -> u32 {
+    CURRENT_SCHEMA_VERSION
+}
+
+/// One step in the chain: upgrades an object from version `n` to `n + 1`.
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[i]` upgrades version `i + 1` to `i + 2`.
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2];
+
+/// Mark proficient skills without a proficiency level as Expertise when the stored modifier is
+/// the ability modifier plus twice the proficiency bonus, for sources that only have the numbers.
+pub fn infer_expertise(obj: &mut Map<String, Value>) {
+    let pb = obj.get("proficiency_bonus").and_then(Value::as_i64).or_else(|| {
+        obj.get("level")
+            .and_then(Value::as_u64)
+            .map(|level| proficiency_bonus_for_level(level.min(20) as u8) as i64)
+    });
+    let scores = obj.get("ability_scores").cloned();
+    if let (Some(pb), Some(Value::Object(scores)), Some(Value::Array(skills))) = (pb, scores, obj.get_mut("skills")) {
+        for entry in skills.iter_mut().filter_map(Value::as_object_mut) {
+            let doubled = (|| {
+                let skill: Skill = entry.get("name")?.as_str()?.parse().ok()?;
+                let score = scores.get(&skill.ability().name().to_lowercase())?.as_i64()?;
+                let modifier = entry.get("modifier")?.as_i64()?;
+                Some(modifier == (score - 10).div_euclid(2) + 2 * pb)
+            })();
+            let proficient = entry.get("proficient").and_then(Value::as_bool).unwrap_or(false);
+            if proficient && doubled == Some(true) && !entry.contains_key("proficiency") {
+                entry.insert("proficiency".to_string(), Value::String("expertise".to_string()));
+            }
+        }
+    }
+}
+
+fn migrate_v1_to_v2(obj: &mut Map<String, Value>) {
+    // Files from before the role system were all generated as hired swords
+    obj.entry("role").or_insert_with(|| Value::String("Mercenary".to_string()));
+
+    if !obj.contains_key("fighting_preference") {
+        let ranges: Vec<String> = obj
+            .get("attacks")
+            .and_then(Value::as_array)
+            .map(|attacks| {
+                attacks
+                    .iter()
+                    .filter_map(|a| a.get("range").and_then(Value::as_str))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let melee = ranges.iter().any(|r| is_melee_range(Some(r)));
+        let ranged = ranges.iter().any(|r| !is_melee_range(Some(r)));
+        let preference = match (melee, ranged) {
+            (true, true) => "Versatile",
+            (false, true) => "Ranged",
+            _ => "Melee",
+        };
+        obj.insert("fighting_preference".to_string(), Value::String(preference.to_string()));
+    }
+
+    // Version 1 had no proficiency levels, so Expertise only shows in the numbers
+    infer_expertise(obj);
+
+    // Saving throws were plain names; the typed loader reads those, but spell them out so
+    // the upgraded file is in the new shape even before the modifiers are filled in
+    if let Some(Value::Array(saves)) = obj.get_mut("saving_throws") {
+        for save in saves.iter_mut() {
+            if let Value::String(ability) = save {
+                let mut entry = Map::new();
+                entry.insert("ability".to_string(), Value::String(std::mem::take(ability)));
+                entry.insert("proficient".to_string(), Value::Bool(true));
+                *save = Value::Object(entry);
+            }
+        }
+    }
+}
+
+/// The file's `schema_version`, 1 if it has none. Fails on a version that isn't a whole
+/// number in range rather than reading it as some other version.
+pub fn schema_version_of(value: &Value) -> Result<u32> {
+    match value.get("schema_version") {
+        None => Ok(1),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .filter(|&v| v >= 1)
+            .with_context(|| format!("Invalid schema_version {}", version)),
+    }
+}
+
+/// Upgrade raw NPC JSON of any version to a current `NPC`, with warnings from the tolerant parser.
+pub fn upgrade_value(mut value: Value) -> Result<(NPC, Vec<String>)> {
+    let from = schema_version_of(&value)?;
+    if from > CURRENT_SCHEMA_VERSION {
+        bail!(
+            "File has schema version {}, but this build of NPCForge only understands up to {}",
+            from,
+            CURRENT_SCHEMA_VERSION
+        );
+    }
+    let Value::Object(obj) = &mut value else {
+        bail!("Not an NPC file: expected a JSON object");
+    };
+    for migration in &MIGRATIONS[(from - 1) as usize..] {
+        migration(obj);
+    }
+    obj.insert("schema_version".to_string(), Value::from(CURRENT_SCHEMA_VERSION));
+
+    let mut warnings = Vec::new();
+    let mut npc = lenient::parse_npc_value(value, &mut warnings)?;
+    npc.complete_derived_fields();
+    Ok((npc, warnings))
+}
+
+/// Load an NPC file of any schema version.
+pub fn load_npc_file(path: &Path) -> Result<(NPC, Vec<String>)> {
+    let text = fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    let value: Value = serde_json::from_str(&text).context(format!("{} is not valid JSON", path.display()))?;
+    upgrade_value(value).context(format!("Failed to load NPC from {}", path.display()))
+}
+
+/// Every saved NPC under `dir`, recursively, each with its load result. JSON files that aren't
+/// NPCs (exports, configs) are skipped.
+pub fn load_npc_directory(dir: &Path) -> Result<Vec<(PathBuf, Result<NPC>)>> {
+    let mut npcs = Vec::new();
+    for path in find_json_files(dir)? {
+        let value = fs::read_to_string(&path)
+            .context(format!("Failed to read {}", path.display()))
+            .and_then(|text| serde_json::from_str::<Value>(&text).context(format!("{} is not valid JSON", path.display())));
+        let result = match value {
+            Ok(value) if !looks_like_npc(&value) => continue,
+            Ok(value) => upgrade_value(value).map(|(npc, _)| npc).context(format!("Failed to load NPC from {}", path.display())),
+            Err(e) => Err(e),
+        };
+        npcs.push((path, result));
+    }
+    Ok(npcs)
+}
+
+/// Saved NPCs are JSON objects with at least a name and a class; anything else in the
+/// directory (configs, exports from other tools) is left alone.
+fn looks_like_npc(value: &Value) -> bool {
+    value.get("name").is_some() && value.get("class").is_some() && value.get("ability_scores").is_some()
+}
+
+/// All `.json` files under `dir`, recursively, in a stable order.
+pub fn find_json_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = fs::read_dir(&current).context(format!("Failed to read directory {}", current.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Upgrade every NPC file under `dir` in place, keeping a copy of each original as
+/// `<file>.v<old version>.bak` unless `backup` is off.
+pub fn migrate_directory(dir: &Path, backup: bool, dry_run: bool) -> Result<()> {
+    println!("=== NPCForge - Migrating {} to schema version {} ===\n", dir.display(), CURRENT_SCHEMA_VERSION);
+
+    let mut upgraded = 0;
+    let mut current = 0;
+    let mut failed = 0;
+
+    for path in find_json_files(dir)? {
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("✗ {}: {}", path.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+        let value: Value = match serde_json::from_str(&text) {
+            Ok(value) if looks_like_npc(&value) => value,
+            // Not an NPC file
+            _ => continue,
+        };
+
+        let from = match schema_version_of(&value) {
+            Ok(from) => from,
+            Err(e) => {
+                eprintln!("✗ {}: {:#}", path.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+        if from == CURRENT_SCHEMA_VERSION {
+            current += 1;
+            continue;
+        }
+
+        let (npc, warnings) = match upgrade_value(value) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("✗ {}: {:#}", path.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+
+        if dry_run {
+            println!("• {} would be upgraded from v{}", path.display(), from);
+            upgraded += 1;
+            continue;
+        }
+
+        let result = (|| -> Result<()> {
+            if backup {
+                let backup_path = PathBuf::from(format!("{}.v{}.bak", path.display(), from));
+                fs::copy(&path, &backup_path).context(format!("Failed to back up to {}", backup_path.display()))?;
+            }
+            let json = serde_json::to_string_pretty(&npc).context("Failed to serialize NPC to JSON")?;
+            output::write_atomic(&path, json)
+        })();
+
+        match result {
+            Ok(()) => {
+                println!("✓ {} upgraded from v{}", path.display(), from);
+                if !warnings.is_empty() {
+                    println!("  ⚠ {} field(s) repaired while loading:", warnings.len());
+                    for warning in &warnings {
+                        println!("    - {}", warning);
+                    }
+                }
+                upgraded += 1;
+            }
+            Err(e) => {
+                eprintln!("✗ {}: {:#}", path.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\n=== Summary ===");
+    if dry_run {
+        println!("Would upgrade: {}", upgraded);
+    } else {
+        println!("Upgraded: {}", upgraded);
+    }
+    println!("Already current: {}", current);
+    if failed > 0 {
+        println!("Failed: {}", failed);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProficiencyLevel;
+    use serde_json::json;
+
+    /// The fixture as a version 1 file: no version, role or fighting preference, saving throws
+    /// as names and skills with only the proficiency flag.
+    fn seraphine_v1() -> Value {
+        let mut value: Value = serde_json::from_str(include_str!("../tests/fixtures/seraphine.json")).unwrap();
+        let obj = value.as_object_mut().unwrap();
+        for key in ["schema_version", "role", "fighting_preference", "size", "passive_perception"] {
+            obj.remove(key);
+        }
+        obj.insert("saving_throws".to_string(), json!(["Dexterity", "Charisma"]));
+        obj.insert(
+            "skills".to_string(),
+            json!([
+                {"name": "Deception", "modifier": 10, "proficient": true},
+                {"name": "Persuasion", "modifier": 7, "proficient": true},
+            ]),
+        );
+        value
+    }
+
+    #[test]
+    fn v1_file_is_upgraded_to_v2() {
+        let (npc, _) = upgrade_value(seraphine_v1()).unwrap();
+        assert_eq!(npc.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(npc.role, "Mercenary");
+        assert_eq!(npc.fighting_preference.to_string(), "Versatile");
+        assert_eq!(npc.skills.len(), 18);
+        let proficient: Vec<_> = npc.saving_throws.iter().filter(|s| s.proficient).map(|s| s.ability).collect();
+        assert_eq!(proficient, [crate::Ability::Dexterity, crate::Ability::Charisma]);
+
+        let skill = |name: Skill| npc.skills.iter().find(|s| s.name == name).unwrap();
+        // +10 is Charisma +4 and twice the proficiency bonus of 3
+        assert_eq!(skill(Skill::Deception).proficiency, ProficiencyLevel::Expertise);
+        assert_eq!(skill(Skill::Deception).modifier, 10);
+        assert_eq!(skill(Skill::Persuasion).proficiency, ProficiencyLevel::Proficient);
+        assert_eq!(skill(Skill::Persuasion).modifier, 7);
+    }
+
+    #[test]
+    fn out_of_range_schema_version_is_an_error() {
+        assert_eq!(schema_version_of(&json!({})).unwrap(), 1);
+        assert_eq!(schema_version_of(&json!({"schema_version": 2})).unwrap(), 2);
+        for version in [json!(u32::MAX as u64 + 2), json!(0), json!(-1), json!("2")] {
+            assert!(schema_version_of(&json!({ "schema_version": version })).is_err(), "{}", version);
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use crate::types::{Ability, Class, Size, Skill};
+use crate::{NPC, ProficiencyLevel, SavingThrow, SkillEntry, SpellSlots, SpellsByLevel};
+
+pub fn ability_modifier(score: u8) -> i8 {
+    (score as i16 - 10).div_euclid(2) as i8
+}
+
+pub fn proficiency_bonus_for_level(level: u8) -> i8 {
+    2 + (level.clamp(1, 20) as i8 - 1) / 4
+}
+
+impl NPC {
+    pub fn ability_score(&self, ability: Ability) -> u8 {
+        let s = &self.ability_scores;
+        match ability {
+            Ability::Strength => s.strength,
+            Ability::Dexterity => s.dexterity,
+            Ability::Constitution => s.constitution,
+            Ability::Intelligence => s.intelligence,
+            Ability::Wisdom => s.wisdom,
+            Ability::Charisma => s.charisma,
+        }
+    }
+
+    pub fn ability_mod(&self, ability: Ability) -> i8 {
+        ability_modifier(self.ability_score(ability))
+    }
+
+    /// Levels in `class`, falling back to the total level for single-class characters. A
+    /// multiclass character without `class_levels` has no known split, so that is 0.
+    pub fn levels_in(&self, class: &Class) -> u8 {
+        match &self.class_levels {
+            Some(levels) => levels.get(class).copied().unwrap_or(0),
+            None if self.class_name.0 == [class.clone()] => self.level,
+            None => 0,
+        }
+    }
+
+    /// Levels in spellcasting classes, for "is an Nth-level spellcaster" wording.
+    pub fn caster_level(&self) -> u8 {
+        self.class_name
+            .0
+            .iter()
+            .filter(|class| class.is_spellcaster())
+            .map(|class| self.levels_in(class))
+            .sum()
+    }
+
+    pub fn knows_spell(&self, name: &str) -> bool {
+        self.spells.as_ref().is_some_and(|spells| {
+            spells
+                .spells_known
+                .by_level()
+                .iter()
+                .any(|(_, list)| list.iter().any(|s| s.eq_ignore_ascii_case(name)))
+        })
+    }
+
+    /// "Fighter 5 / Rogue 3" for multiclass characters, "Wizard 10" otherwise.
+    pub fn class_summary(&self) -> String {
+        match &self.class_levels {
+            Some(levels) if !levels.is_empty() => self
+                .class_name
+                .0
+                .iter()
+                .map(|class| format!("{} {}", class, levels.get(class).copied().unwrap_or(0)))
+                .collect::<Vec<_>>()
+                .join(" / "),
+            _ => format!("{} {}", self.class_name, self.level),
+        }
+    }
+
+    /// Fill in everything NPCForge computes rather than trusting the model with: skills,
+    /// saves, passive scores, and size for NPCs that do not state one.
+    pub fn complete_derived_fields(&mut self) {
+        self.complete_proficiencies();
+        if self.size.is_none() {
+            self.size = Some(Size::for_race(&self.race));
+        }
+    }
+
+    fn has_jack_of_all_trades(&self) -> bool {
+        self.levels_in(&Class::Bard) >= 2
+            || self
+                .features
+                .iter()
+                .any(|f| f.name.eq_ignore_ascii_case("Jack of All Trades"))
+    }
+
+    /// Skills an Expertise feature names in its description, e.g. "Expertise: Stealth and
+    /// Sleight of Hand".
+    fn expertise_from_features(&self) -> Vec<Skill> {
+        let descriptions: Vec<String> = self
+            .features
+            .iter()
+            .filter(|f| f.name.to_lowercase().contains("expertise"))
+            .map(|f| format!("{} {}", f.name, f.description).to_lowercase())
+            .collect();
+        Skill::ALL
+            .into_iter()
+            .filter(|skill| descriptions.iter().any(|d| d.contains(&skill.name().to_lowercase())))
+            .collect()
+    }
+
+    /// Rebuild the full skill list, all six saving throws and the passive scores from the
+    /// ability scores and proficiency bonus.
+    ///
+    /// The model only lists the skills and saves it considers notable, and its arithmetic is
+    /// unreliable, so its entries are used for proficiency state only and every modifier is
+    /// recomputed here.
+    pub fn complete_proficiencies(&mut self) {
+        let pb = proficiency_bonus_for_level(self.level);
+        self.proficiency_bonus = pb;
+        let jack = self.has_jack_of_all_trades();
+        let expertise = self.expertise_from_features();
+
+        let listed = std::mem::take(&mut self.skills);
+        self.skills = Skill::ALL
+            .into_iter()
+            .map(|skill| {
+                let base = self.ability_mod(skill.ability());
+                let entry = listed.iter().find(|s| s.name == skill);
+                let mut proficiency = match entry {
+                    Some(s) if s.proficiency != ProficiencyLevel::None => s.proficiency,
+                    // A doubled-looking modifier is not evidence of expertise; only a feature is
+                    Some(s) if s.proficient && expertise.contains(&skill) => ProficiencyLevel::Expertise,
+                    Some(s) if s.proficient => ProficiencyLevel::Proficient,
+                    _ => ProficiencyLevel::None,
+                };
+                if proficiency == ProficiencyLevel::None && jack {
+                    proficiency = ProficiencyLevel::HalfProficient;
+                }
+                SkillEntry {
+                    name: skill,
+                    ability: skill.ability(),
+                    modifier: base + proficiency.bonus(pb),
+                    proficient: proficiency.is_proficient(),
+                    proficiency,
+                }
+            })
+            .collect();
+
+        let listed = std::mem::take(&mut self.saving_throws);
+        self.saving_throws = Ability::ALL
+            .into_iter()
+            .map(|ability| {
+                let proficient = listed.iter().any(|s| s.proficient && s.ability == ability);
+                let base = self.ability_mod(ability);
+                SavingThrow {
+                    ability,
+                    modifier: if proficient { base + pb } else { base },
+                    proficient,
+                }
+            })
+            .collect();
+
+        self.passive_perception = self.passive_score(Skill::Perception);
+        self.passive_insight = self.passive_score(Skill::Insight);
+        self.passive_investigation = self.passive_score(Skill::Investigation);
+    }
+
+    pub fn skill_modifier(&self, skill: Skill) -> i8 {
+        self.skills
+            .iter()
+            .find(|s| s.name == skill)
+            .map(|s| s.modifier)
+            .unwrap_or_else(|| self.ability_mod(skill.ability()))
+    }
+
+    fn passive_score(&self, skill: Skill) -> u8 {
+        (10 + self.skill_modifier(skill) as i16).max(0) as u8
+    }
+}
+
+impl ProficiencyLevel {
+    /// Bonus added on top of the ability modifier. Half proficiency rounds down.
+    pub fn bonus(self, proficiency_bonus: i8) -> i8 {
+        match self {
+            ProficiencyLevel::None => 0,
+            ProficiencyLevel::HalfProficient => proficiency_bonus / 2,
+            ProficiencyLevel::Proficient => proficiency_bonus,
+            ProficiencyLevel::Expertise => proficiency_bonus * 2,
+        }
+    }
+
+    pub fn is_proficient(self) -> bool {
+        matches!(self, ProficiencyLevel::Proficient | ProficiencyLevel::Expertise)
+    }
+}
+
+impl SpellsByLevel {
+    /// `(spell level, spells)` from cantrips (level 0) up to 9th level.
+    pub fn by_level(&self) -> [(u8, &[String]); 10] {
+        [
+            (0, &self.cantrips),
+            (1, &self.level_1),
+            (2, &self.level_2),
+            (3, &self.level_3),
+            (4, &self.level_4),
+            (5, &self.level_5),
+            (6, &self.level_6),
+            (7, &self.level_7),
+            (8, &self.level_8),
+            (9, &self.level_9),
+        ]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_level().iter().all(|(_, spells)| spells.is_empty())
+    }
+
+    /// The list for one spell level, 0 being cantrips. Levels above 9 go to the 9th-level list.
+    pub fn level_mut(&mut self, level: u8) -> &mut Vec<String> {
+        match level {
+            0 => &mut self.cantrips,
+            1 => &mut self.level_1,
+            2 => &mut self.level_2,
+            3 => &mut self.level_3,
+            4 => &mut self.level_4,
+            5 => &mut self.level_5,
+            6 => &mut self.level_6,
+            7 => &mut self.level_7,
+            8 => &mut self.level_8,
+            _ => &mut self.level_9,
+        }
+    }
+}
+
+impl SpellSlots {
+    pub fn slots(&self, level: u8) -> u8 {
+        match level {
+            1 => self.level_1,
+            2 => self.level_2,
+            3 => self.level_3,
+            4 => self.level_4,
+            5 => self.level_5,
+            6 => self.level_6,
+            7 => self.level_7,
+            8 => self.level_8,
+            9 => self.level_9,
+            _ => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Feature;
+    use crate::types::Classes;
+
+    fn seraphine() -> NPC {
+        serde_json::from_str(include_str!("../tests/fixtures/seraphine.json")).unwrap()
+    }
+
+    #[test]
+    fn multiclass_without_class_levels_has_no_levels_per_class() {
+        let mut npc = seraphine();
+        npc.class_levels = None;
+        assert_eq!(npc.levels_in(&Class::Bard), 0);
+        assert_eq!(npc.caster_level(), 0);
+
+        npc.class_name = Classes(vec![Class::Bard]);
+        assert_eq!(npc.levels_in(&Class::Bard), 5);
+        assert_eq!(npc.levels_in(&Class::Warlock), 0);
+    }
+
+    #[test]
+    fn complete_proficiencies_rebuilds_skills_saves_and_passives() {
+        // Bard 3 / Warlock 2: proficiency +3, Jack of All Trades; CHA 18, DEX 14, WIS 10
+        let mut npc = seraphine();
+        let listed = r#"[
+            {"name": "Stealth", "modifier": 99, "proficient": true},
+            {"name": "Deception", "modifier": 4, "proficient": true},
+            {"name": "Persuasion", "modifier": 4, "proficient": true}
+        ]"#;
+        npc.skills = serde_json::from_str(listed).unwrap();
+        npc.saving_throws = serde_json::from_str(r#"["Dexterity", "Charisma"]"#).unwrap();
+        npc.features.push(Feature { name: "Expertise".to_string(), description: "Deception.".to_string() });
+        npc.proficiency_bonus = 0;
+        npc.passive_perception = 0;
+        npc.complete_proficiencies();
+
+        assert_eq!(npc.proficiency_bonus, 3);
+        assert_eq!(npc.skills.len(), 18);
+        let skill = |skill: Skill| npc.skills.iter().find(|s| s.name == skill).unwrap();
+        assert_eq!((skill(Skill::Stealth).modifier, skill(Skill::Stealth).proficiency), (5, ProficiencyLevel::Proficient));
+        assert_eq!((skill(Skill::Deception).modifier, skill(Skill::Deception).proficiency), (10, ProficiencyLevel::Expertise));
+        assert_eq!((skill(Skill::Persuasion).modifier, skill(Skill::Persuasion).proficiency), (7, ProficiencyLevel::Proficient));
+        // Jack of All Trades: half proficiency, rounded down
+        assert_eq!((skill(Skill::Athletics).modifier, skill(Skill::Athletics).proficiency), (0, ProficiencyLevel::HalfProficient));
+
+        let saves: Vec<(Ability, i8, bool)> = npc.saving_throws.iter().map(|s| (s.ability, s.modifier, s.proficient)).collect();
+        assert_eq!(
+            saves,
+            [
+                (Ability::Strength, -1, false),
+                (Ability::Dexterity, 5, true),
+                (Ability::Constitution, 1, false),
+                (Ability::Intelligence, 1, false),
+                (Ability::Wisdom, 0, false),
+                (Ability::Charisma, 7, true),
+            ]
+        );
+        assert_eq!(npc.passive_perception, 11);
+        assert_eq!(npc.passive_insight, 11);
+        assert_eq!(npc.passive_investigation, 12);
+    }
+}
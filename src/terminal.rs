@@ -0,0 +1,305 @@
+//! Full terminal view of an NPC for `npcforge show` and `--show` after generation: colored
+//! section headers, aligned ability and attack tables, and prose wrapped to the terminal width.
+
+use std::fmt::Write;
+use std::io::IsTerminal;
+
+use crate::{NPC, ProficiencyLevel};
+use crate::export::{paragraphs, signed, spell_level_label};
+use crate::types::{Ability, Size};
+
+/// Width used when the output is not a terminal, e.g. piped into a file.
+const DEFAULT_WIDTH: usize = 80;
+
+/// Prose gets hard to read on very wide terminals, and tables break on very narrow ones.
+const MIN_WIDTH: usize = 40;
+const MAX_WIDTH: usize = 100;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const HEADER: &str = "\x1b[1;36m";
+const TITLE: &str = "\x1b[1;33m";
+
+/// Whether to color the output and how wide to wrap it.
+#[derive(Debug, Clone, Copy)]
+pub struct Style {
+    color: bool,
+    width: usize,
+}
+
+impl Style {
+    /// Style for text printed to stdout.
+    pub fn stdout() -> Style {
+        let stdout = std::io::stdout();
+        Style::detect(stdout.is_terminal(), terminal_size::terminal_size_of(&stdout))
+    }
+
+    /// Style for text printed to stderr, where generation progress goes when NPCs stream to stdout.
+    pub fn stderr() -> Style {
+        let stderr = std::io::stderr();
+        Style::detect(stderr.is_terminal(), terminal_size::terminal_size_of(&stderr))
+    }
+
+    /// Color only on a terminal and only when `NO_COLOR` is unset or empty (see no-color.org).
+    fn detect(is_terminal: bool, size: Option<(terminal_size::Width, terminal_size::Height)>) -> Style {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        let width = size.map(|(w, _)| w.0 as usize).unwrap_or(DEFAULT_WIDTH);
+        Style { color: is_terminal && !no_color, width: width.clamp(MIN_WIDTH, MAX_WIDTH) }
+    }
+
+    fn paint(&self, code: &str, text: &str) -> String {
+        if self.color { format!("{}{}{}", code, text, RESET) } else { text.to_string() }
+    }
+}
+
+pub fn render(npc: &NPC, style: Style) -> String {
+    let mut out = String::new();
+    header(&mut out, npc, style);
+    combat(&mut out, npc, style);
+    abilities(&mut out, npc, style);
+    proficiencies(&mut out, npc, style);
+    attacks(&mut out, npc, style);
+    spellcasting(&mut out, npc, style);
+    features(&mut out, npc, style);
+    equipment(&mut out, npc, style);
+    personality(&mut out, npc, style);
+    appearance(&mut out, npc, style);
+    backstory(&mut out, npc, style);
+    out
+}
+
+/// Greedy word wrap. Every line but the first starts with `indent`; the first is assumed to
+/// follow a label of the same width already on the line.
+fn wrap(text: &str, width: usize, indent: usize) -> String {
+    let available = width.saturating_sub(indent).max(10);
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > available {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines.join(&format!("\n{}", " ".repeat(indent)))
+}
+
+fn section(out: &mut String, title: &str, style: Style) {
+    let _ = writeln!(out, "{}", style.paint(HEADER, title));
+    let _ = writeln!(out, "{}", style.paint(DIM, &"─".repeat(title.chars().count().max(3))));
+}
+
+/// A bold label followed by wrapped text that lines up under its first word, or under the
+/// label when the label is too long to leave room for the text.
+fn field(out: &mut String, label: &str, text: &str, style: Style) {
+    let indent = label.chars().count() + 1;
+    let indent = if indent > style.width / 4 { 2 } else { indent };
+    let _ = writeln!(out, "{} {}", style.paint(BOLD, label), wrap(text, style.width, indent));
+}
+
+/// Columns padded to their widest cell, with the header row in bold.
+fn table(out: &mut String, headers: &[&str], rows: &[Vec<String>], style: Style) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<String>| -> String {
+        let padded: Vec<String> = cells.iter().zip(&widths).map(|(cell, &width)| format!("{:<width$}", cell)).collect();
+        padded.join("  ").trim_end().to_string()
+    };
+    let _ = writeln!(out, "  {}", style.paint(BOLD, &line(headers.iter().map(|h| h.to_string()).collect())));
+    for row in rows {
+        let _ = writeln!(out, "  {}", line(row.clone()));
+    }
+}
+
+fn header(out: &mut String, npc: &NPC, style: Style) {
+    let _ = writeln!(out, "{}", style.paint(TITLE, &npc.name));
+    let size = npc.size.unwrap_or_else(|| Size::for_race(&npc.race));
+    let _ = writeln!(out, "{}", style.paint(DIM, &format!("{} {}, {}", size, npc.race, npc.alignment)));
+    let mut class_line = npc.class_summary();
+    if let Some(subclass) = &npc.subclass {
+        let _ = write!(class_line, " ({})", subclass);
+    }
+    let line = format!(
+        "{} · Level {} · {} · {} background · {} fighter",
+        class_line, npc.level, npc.role, npc.background, npc.fighting_preference
+    );
+    let _ = writeln!(out, "{}\n", wrap(&line, style.width, 0));
+}
+
+fn combat(out: &mut String, npc: &NPC, style: Style) {
+    let hp = &npc.hit_points;
+    let stats = [
+        ("AC", npc.armor_class.to_string()),
+        ("HP", format!("{} ({})", hp.max, hp.hit_dice)),
+        ("Speed", format!("{} ft.", npc.speed)),
+        ("Initiative", signed(npc.initiative)),
+        ("Proficiency", signed(npc.proficiency_bonus)),
+    ];
+    let parts: Vec<String> = stats.iter().map(|(label, value)| format!("{} {}", style.paint(BOLD, label), value)).collect();
+    let _ = writeln!(out, "{}\n", parts.join("   "));
+}
+
+fn abilities(out: &mut String, npc: &NPC, style: Style) {
+    let headers: Vec<&str> = Ability::ALL.iter().map(|a| a.abbr()).collect();
+    let scores: Vec<String> = Ability::ALL
+        .iter()
+        .map(|&a| format!("{} ({})", npc.ability_score(a), signed(npc.ability_mod(a))))
+        .collect();
+    let saves: Vec<String> = Ability::ALL
+        .iter()
+        .map(|&a| match npc.saving_throws.iter().find(|s| s.ability == a) {
+            Some(save) if save.proficient => format!("save {}*", signed(save.modifier)),
+            Some(save) => format!("save {}", signed(save.modifier)),
+            None => format!("save {}", signed(npc.ability_mod(a))),
+        })
+        .collect();
+    table(out, &headers, &[scores, saves], style);
+    out.push('\n');
+}
+
+fn proficiencies(out: &mut String, npc: &NPC, style: Style) {
+    let skills: Vec<String> = npc
+        .skills
+        .iter()
+        .filter(|s| s.proficiency.is_proficient())
+        .map(|s| {
+            let expertise = if s.proficiency == ProficiencyLevel::Expertise { " (expertise)" } else { "" };
+            format!("{} {}{}", s.name, signed(s.modifier), expertise)
+        })
+        .collect();
+    if !skills.is_empty() {
+        field(out, "Skills", &skills.join(", "), style);
+    }
+    field(
+        out,
+        "Senses",
+        &format!(
+            "passive Perception {}, passive Insight {}, passive Investigation {}",
+            npc.passive_perception, npc.passive_insight, npc.passive_investigation
+        ),
+        style,
+    );
+    if !npc.languages.is_empty() {
+        field(out, "Languages", &npc.languages.join(", "), style);
+    }
+    if !npc.tool_proficiencies.is_empty() {
+        field(out, "Tools", &npc.tool_proficiencies.join(", "), style);
+    }
+    out.push('\n');
+}
+
+fn attacks(out: &mut String, npc: &NPC, style: Style) {
+    if npc.attacks.is_empty() {
+        return;
+    }
+    section(out, "Attacks", style);
+    let rows: Vec<Vec<String>> = npc
+        .attacks
+        .iter()
+        .map(|attack| {
+            vec![
+                attack.name.clone(),
+                signed(attack.attack_bonus),
+                format!("{} {}", attack.damage, attack.damage_type),
+                attack.range.clone().unwrap_or_else(|| "—".to_string()),
+                attack.properties.join(", "),
+            ]
+        })
+        .collect();
+    table(out, &["Attack", "To Hit", "Damage", "Range", "Properties"], &rows, style);
+    out.push('\n');
+}
+
+fn spellcasting(out: &mut String, npc: &NPC, style: Style) {
+    let Some(spells) = &npc.spells else { return };
+    if spells.spells_known.is_empty() {
+        return;
+    }
+    section(out, "Spellcasting", style);
+    if let Some(ability) = spells.spellcasting_ability {
+        let _ = writeln!(
+            out,
+            "{} (spell save DC {}, {} to hit)",
+            ability,
+            spells.spell_save_dc,
+            signed(spells.spell_attack_bonus)
+        );
+    }
+    for (level, list) in spells.spells_known.by_level() {
+        if list.is_empty() {
+            continue;
+        }
+        field(out, &format!("{}:", spell_level_label(spells, level)), &list.join(", "), style);
+    }
+    out.push('\n');
+}
+
+fn features(out: &mut String, npc: &NPC, style: Style) {
+    if npc.features.is_empty() {
+        return;
+    }
+    section(out, "Features", style);
+    for feature in &npc.features {
+        field(out, &format!("{}.", feature.name), &feature.description, style);
+    }
+    out.push('\n');
+}
+
+fn equipment(out: &mut String, npc: &NPC, style: Style) {
+    let eq = &npc.equipment;
+    section(out, "Equipment", style);
+    for (label, items) in [("Armor", &eq.armor), ("Weapons", &eq.weapons), ("Gear", &eq.gear)] {
+        if !items.is_empty() {
+            field(out, label, &items.join(", "), style);
+        }
+    }
+    let mut treasure = format!("{} gp", eq.treasure.gold);
+    if !eq.treasure.items.is_empty() {
+        let _ = write!(treasure, "; {}", eq.treasure.items.join(", "));
+    }
+    field(out, "Treasure", &treasure, style);
+    out.push('\n');
+}
+
+fn personality(out: &mut String, npc: &NPC, style: Style) {
+    let p = &npc.personality;
+    section(out, "Personality", style);
+    if !p.traits.is_empty() {
+        field(out, "Traits:", &p.traits.join("; "), style);
+    }
+    field(out, "Ideals:", &p.ideals, style);
+    field(out, "Bonds:", &p.bonds, style);
+    field(out, "Flaws:", &p.flaws, style);
+    out.push('\n');
+}
+
+fn appearance(out: &mut String, npc: &NPC, style: Style) {
+    let a = &npc.appearance;
+    section(out, "Appearance", style);
+    let summary = format!(
+        "Age {} · {} · {} · {} eyes · {} hair · {} skin",
+        a.age, a.height, a.weight, a.eyes, a.hair, a.skin
+    );
+    let _ = writeln!(out, "{}", wrap(&summary, style.width, 0));
+    for feature in &a.distinguishing_features {
+        let _ = writeln!(out, "- {}", wrap(feature, style.width, 2));
+    }
+    out.push('\n');
+}
+
+fn backstory(out: &mut String, npc: &NPC, style: Style) {
+    section(out, "Backstory", style);
+    for paragraph in paragraphs(&npc.backstory) {
+        let _ = writeln!(out, "{}\n", wrap(paragraph, style.width, 0));
+    }
+}
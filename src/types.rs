@@ -0,0 +1,699 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Serialize through `Display` and deserialize through the lenient `FromStr`, so the JSON stays
+/// a plain string no matter how the model spelled it.
+macro_rules! string_serde {
+    ($ty:ty) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+/// Lowercase and strip everything but letters, so "Sleight-of-hand" matches "Sleight of Hand".
+pub fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphabetic())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Ability {
+    Strength,
+    Dexterity,
+    Constitution,
+    Intelligence,
+    Wisdom,
+    Charisma,
+}
+
+impl Ability {
+    pub const ALL: [Ability; 6] = [
+        Ability::Strength,
+        Ability::Dexterity,
+        Ability::Constitution,
+        Ability::Intelligence,
+        Ability::Wisdom,
+        Ability::Charisma,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Ability::Strength => "Strength",
+            Ability::Dexterity => "Dexterity",
+            Ability::Constitution => "Constitution",
+            Ability::Intelligence => "Intelligence",
+            Ability::Wisdom => "Wisdom",
+            Ability::Charisma => "Charisma",
+        }
+    }
+
+    pub fn abbr(self) -> &'static str {
+        match self {
+            Ability::Strength => "STR",
+            Ability::Dexterity => "DEX",
+            Ability::Constitution => "CON",
+            Ability::Intelligence => "INT",
+            Ability::Wisdom => "WIS",
+            Ability::Charisma => "CHA",
+        }
+    }
+}
+
+impl fmt::Display for Ability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Ability {
+    type Err = String;
+
+    /// Accepts full names, three-letter abbreviations and, for multiclass casters, the first
+    /// recognizable ability in strings like "Intelligence/Charisma".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(|c: char| !c.is_ascii_alphabetic())
+            .map(normalize)
+            .find_map(|word| {
+                Ability::ALL
+                    .into_iter()
+                    .find(|a| word == normalize(a.name()) || word == normalize(a.abbr()))
+            })
+            .ok_or_else(|| format!("unknown ability: {:?}", s))
+    }
+}
+
+string_serde!(Ability);
+
+/// Deserialize an optional ability where the model (and older files) use "" or null for "none".
+pub fn deserialize_optional_ability<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Ability>, D::Error> {
+    let s = Option::<String>::deserialize(deserializer)?;
+    match s.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(s) => s.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Deserialize a list, dropping entries that do not parse (e.g. a tool listed under skills).
+/// Loading goes through `lenient` first, which moves tools to the tool proficiencies and
+/// reports anything else that is dropped here.
+pub fn deserialize_known_entries<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    let values = Vec::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(values
+        .into_iter()
+        .filter_map(|v| serde_json::from_value(v).ok())
+        .collect())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Skill {
+    Acrobatics,
+    AnimalHandling,
+    Arcana,
+    Athletics,
+    Deception,
+    History,
+    Insight,
+    Intimidation,
+    Investigation,
+    Medicine,
+    Nature,
+    Perception,
+    Performance,
+    Persuasion,
+    Religion,
+    SleightOfHand,
+    Stealth,
+    Survival,
+}
+
+impl Skill {
+    /// All 18 skills in the order they appear on the character sheet.
+    pub const ALL: [Skill; 18] = [
+        Skill::Acrobatics,
+        Skill::AnimalHandling,
+        Skill::Arcana,
+        Skill::Athletics,
+        Skill::Deception,
+        Skill::History,
+        Skill::Insight,
+        Skill::Intimidation,
+        Skill::Investigation,
+        Skill::Medicine,
+        Skill::Nature,
+        Skill::Perception,
+        Skill::Performance,
+        Skill::Persuasion,
+        Skill::Religion,
+        Skill::SleightOfHand,
+        Skill::Stealth,
+        Skill::Survival,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Skill::Acrobatics => "Acrobatics",
+            Skill::AnimalHandling => "Animal Handling",
+            Skill::Arcana => "Arcana",
+            Skill::Athletics => "Athletics",
+            Skill::Deception => "Deception",
+            Skill::History => "History",
+            Skill::Insight => "Insight",
+            Skill::Intimidation => "Intimidation",
+            Skill::Investigation => "Investigation",
+            Skill::Medicine => "Medicine",
+            Skill::Nature => "Nature",
+            Skill::Perception => "Perception",
+            Skill::Performance => "Performance",
+            Skill::Persuasion => "Persuasion",
+            Skill::Religion => "Religion",
+            Skill::SleightOfHand => "Sleight of Hand",
+            Skill::Stealth => "Stealth",
+            Skill::Survival => "Survival",
+        }
+    }
+
+    pub fn ability(self) -> Ability {
+        match self {
+            Skill::Athletics => Ability::Strength,
+            Skill::Acrobatics | Skill::SleightOfHand | Skill::Stealth => Ability::Dexterity,
+            Skill::Arcana | Skill::History | Skill::Investigation | Skill::Nature | Skill::Religion => {
+                Ability::Intelligence
+            }
+            Skill::AnimalHandling | Skill::Insight | Skill::Medicine | Skill::Perception | Skill::Survival => {
+                Ability::Wisdom
+            }
+            Skill::Deception | Skill::Intimidation | Skill::Performance | Skill::Persuasion => Ability::Charisma,
+        }
+    }
+}
+
+impl fmt::Display for Skill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Skill {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The model sometimes appends the ability, e.g. "Stealth (Dex)"
+        let head = s.split('(').next().unwrap_or(s);
+        let word = normalize(head);
+        let alias = match word.as_str() {
+            "sleight" | "sleightofhands" => Some(Skill::SleightOfHand),
+            "animals" | "animalhandeling" => Some(Skill::AnimalHandling),
+            "investigate" => Some(Skill::Investigation),
+            "perceive" => Some(Skill::Perception),
+            "perform" => Some(Skill::Performance),
+            "persuade" => Some(Skill::Persuasion),
+            "intimidate" => Some(Skill::Intimidation),
+            "deceive" => Some(Skill::Deception),
+            _ => None,
+        };
+        alias
+            .or_else(|| Skill::ALL.into_iter().find(|sk| word == normalize(sk.name())))
+            .ok_or_else(|| format!("unknown skill: {:?}", s))
+    }
+}
+
+string_serde!(Skill);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Alignment {
+    LawfulGood,
+    NeutralGood,
+    ChaoticGood,
+    LawfulNeutral,
+    TrueNeutral,
+    ChaoticNeutral,
+    LawfulEvil,
+    NeutralEvil,
+    ChaoticEvil,
+    Unaligned,
+    /// Anything the model wrote that does not map onto the nine alignments
+    Other(String),
+}
+
+impl Alignment {
+    pub const ALL: [Alignment; 9] = [
+        Alignment::LawfulGood,
+        Alignment::NeutralGood,
+        Alignment::ChaoticGood,
+        Alignment::LawfulNeutral,
+        Alignment::TrueNeutral,
+        Alignment::ChaoticNeutral,
+        Alignment::LawfulEvil,
+        Alignment::NeutralEvil,
+        Alignment::ChaoticEvil,
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            Alignment::LawfulGood => "Lawful Good",
+            Alignment::NeutralGood => "Neutral Good",
+            Alignment::ChaoticGood => "Chaotic Good",
+            Alignment::LawfulNeutral => "Lawful Neutral",
+            Alignment::TrueNeutral => "True Neutral",
+            Alignment::ChaoticNeutral => "Chaotic Neutral",
+            Alignment::LawfulEvil => "Lawful Evil",
+            Alignment::NeutralEvil => "Neutral Evil",
+            Alignment::ChaoticEvil => "Chaotic Evil",
+            Alignment::Unaligned => "Unaligned",
+            Alignment::Other(s) => s,
+        }
+    }
+}
+
+impl fmt::Display for Alignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Alignment {
+    type Err = String;
+
+    /// Accepts "CG", "chaotic good", "Chaotic-Good", "True Neutral", "N", "NN" and similar.
+    /// Unrecognized text is kept verbatim as `Other` so no saved file fails to load.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let word = normalize(s);
+        let abbr = match word.as_str() {
+            "lg" => Some(Alignment::LawfulGood),
+            "ng" => Some(Alignment::NeutralGood),
+            "cg" => Some(Alignment::ChaoticGood),
+            "ln" => Some(Alignment::LawfulNeutral),
+            "n" | "tn" | "nn" | "neutral" | "trueneutral" | "neutralneutral" => Some(Alignment::TrueNeutral),
+            "cn" => Some(Alignment::ChaoticNeutral),
+            "le" => Some(Alignment::LawfulEvil),
+            "ne" => Some(Alignment::NeutralEvil),
+            "ce" => Some(Alignment::ChaoticEvil),
+            "u" | "unaligned" | "none" => Some(Alignment::Unaligned),
+            _ => None,
+        };
+        if let Some(a) = abbr {
+            return Ok(a);
+        }
+        // Longest prefix wins, so "Chaotic Neutral (leaning good)" is still Chaotic Neutral
+        if let Some(a) = Alignment::ALL
+            .into_iter()
+            .filter(|a| word.starts_with(&normalize(a.name())))
+            .max_by_key(|a| a.name().len())
+        {
+            return Ok(a);
+        }
+        if s.trim().is_empty() {
+            return Err("empty alignment".to_string());
+        }
+        Ok(Alignment::Other(s.trim().to_string()))
+    }
+}
+
+string_serde!(Alignment);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Class {
+    Artificer,
+    Barbarian,
+    Bard,
+    Cleric,
+    Druid,
+    Fighter,
+    Monk,
+    Paladin,
+    Ranger,
+    Rogue,
+    Sorcerer,
+    Warlock,
+    Wizard,
+    /// Homebrew or third-party classes
+    Other(String),
+}
+
+impl Class {
+    pub const ALL: [Class; 13] = [
+        Class::Artificer,
+        Class::Barbarian,
+        Class::Bard,
+        Class::Cleric,
+        Class::Druid,
+        Class::Fighter,
+        Class::Monk,
+        Class::Paladin,
+        Class::Ranger,
+        Class::Rogue,
+        Class::Sorcerer,
+        Class::Warlock,
+        Class::Wizard,
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            Class::Artificer => "Artificer",
+            Class::Barbarian => "Barbarian",
+            Class::Bard => "Bard",
+            Class::Cleric => "Cleric",
+            Class::Druid => "Druid",
+            Class::Fighter => "Fighter",
+            Class::Monk => "Monk",
+            Class::Paladin => "Paladin",
+            Class::Ranger => "Ranger",
+            Class::Rogue => "Rogue",
+            Class::Sorcerer => "Sorcerer",
+            Class::Warlock => "Warlock",
+            Class::Wizard => "Wizard",
+            Class::Other(s) => s,
+        }
+    }
+
+    pub fn is_spellcaster(&self) -> bool {
+        matches!(
+            self,
+            Class::Wizard | Class::Sorcerer | Class::Warlock | Class::Cleric | Class::Druid | Class::Bard | Class::Artificer
+        )
+    }
+
+    /// Hit die size; homebrew classes are assumed to use a d8.
+    pub fn hit_die(&self) -> u8 {
+        match self {
+            Class::Barbarian => 12,
+            Class::Fighter | Class::Paladin | Class::Ranger => 10,
+            Class::Sorcerer | Class::Wizard => 6,
+            _ => 8,
+        }
+    }
+
+    /// Ability the class casts spells with, including the half casters.
+    pub fn spellcasting_ability(&self) -> Option<Ability> {
+        match self {
+            Class::Artificer | Class::Wizard => Some(Ability::Intelligence),
+            Class::Cleric | Class::Druid | Class::Ranger => Some(Ability::Wisdom),
+            Class::Bard | Class::Paladin | Class::Sorcerer | Class::Warlock => Some(Ability::Charisma),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Class {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let word = normalize(s);
+        if word.is_empty() {
+            return Err("empty class".to_string());
+        }
+        // Tolerate plurals and a stray level, e.g. "Wizards" or "Fighter 5"
+        let singular = word.strip_suffix('s').unwrap_or(&word);
+        Ok(Class::ALL
+            .into_iter()
+            .find(|c| word == normalize(c.name()) || singular == normalize(c.name()))
+            .unwrap_or_else(|| Class::Other(s.trim().to_string())))
+    }
+}
+
+string_serde!(Class);
+
+/// The `class` field: one class, or several joined with "/" for multiclass characters.
+///
+/// Serializes back to the "Fighter/Wizard" form the rest of the tooling expects, and reads
+/// "Fighter / Wizard", "Fighter, Wizard" or a JSON array as well.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Classes(pub Vec<Class>);
+
+impl Classes {
+    pub fn contains(&self, class: &Class) -> bool {
+        self.0.contains(class)
+    }
+}
+
+impl fmt::Display for Classes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.0.iter().map(Class::name).collect();
+        f.write_str(&names.join("/"))
+    }
+}
+
+impl FromStr for Classes {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let classes: Vec<Class> = s
+            .split(['/', ',', '&', '+'])
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        if classes.is_empty() {
+            return Err("empty class".to_string());
+        }
+        Ok(Classes(classes))
+    }
+}
+
+impl Serialize for Classes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Classes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Joined(String),
+            List(Vec<String>),
+        }
+        let joined = match Repr::deserialize(deserializer)? {
+            Repr::Joined(s) => s,
+            Repr::List(list) => list.join("/"),
+        };
+        joined.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DamageType {
+    Acid,
+    Bludgeoning,
+    Cold,
+    Fire,
+    Force,
+    Lightning,
+    Necrotic,
+    Piercing,
+    Poison,
+    Psychic,
+    Radiant,
+    Slashing,
+    Thunder,
+    /// Mixed or unusual damage, e.g. "piercing + fire"
+    Other(String),
+}
+
+impl DamageType {
+    pub const ALL: [DamageType; 13] = [
+        DamageType::Acid,
+        DamageType::Bludgeoning,
+        DamageType::Cold,
+        DamageType::Fire,
+        DamageType::Force,
+        DamageType::Lightning,
+        DamageType::Necrotic,
+        DamageType::Piercing,
+        DamageType::Poison,
+        DamageType::Psychic,
+        DamageType::Radiant,
+        DamageType::Slashing,
+        DamageType::Thunder,
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            DamageType::Acid => "acid",
+            DamageType::Bludgeoning => "bludgeoning",
+            DamageType::Cold => "cold",
+            DamageType::Fire => "fire",
+            DamageType::Force => "force",
+            DamageType::Lightning => "lightning",
+            DamageType::Necrotic => "necrotic",
+            DamageType::Piercing => "piercing",
+            DamageType::Poison => "poison",
+            DamageType::Psychic => "psychic",
+            DamageType::Radiant => "radiant",
+            DamageType::Slashing => "slashing",
+            DamageType::Thunder => "thunder",
+            DamageType::Other(s) => s,
+        }
+    }
+}
+
+impl fmt::Display for DamageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DamageType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let word = match normalize(s).as_str() {
+            "poisonous" => "poison".to_string(),
+            "electric" | "electricity" => "lightning".to_string(),
+            "sonic" => "thunder".to_string(),
+            "blunt" => "bludgeoning".to_string(),
+            other => other.to_string(),
+        };
+        Ok(DamageType::ALL
+            .into_iter()
+            .find(|d| word == d.name())
+            .unwrap_or_else(|| DamageType::Other(s.trim().to_string())))
+    }
+}
+
+string_serde!(DamageType);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Size {
+    Tiny,
+    Small,
+    Medium,
+    Large,
+    Huge,
+    Gargantuan,
+}
+
+impl Size {
+    pub fn name(self) -> &'static str {
+        match self {
+            Size::Tiny => "Tiny",
+            Size::Small => "Small",
+            Size::Medium => "Medium",
+            Size::Large => "Large",
+            Size::Huge => "Huge",
+            Size::Gargantuan => "Gargantuan",
+        }
+    }
+
+    /// Best guess for NPCs saved before size was recorded.
+    pub fn for_race(race: &str) -> Size {
+        let race = normalize(race);
+        let small = ["halfling", "gnome", "goblin", "kobold", "fairy", "owlin", "autognome"];
+        if small.iter().any(|r| race.contains(r)) {
+            Size::Small
+        } else {
+            Size::Medium
+        }
+    }
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Size {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let word = normalize(s);
+        let size = match word.chars().next() {
+            Some('t') => Size::Tiny,
+            Some('s') => Size::Small,
+            Some('m') => Size::Medium,
+            Some('l') => Size::Large,
+            Some('h') => Size::Huge,
+            Some('g') => Size::Gargantuan,
+            _ => return Err(format!("unknown size: {:?}", s)),
+        };
+        // Only accept the initial alone or the full word, not arbitrary text starting with it
+        if word.len() == 1 || word.starts_with(&normalize(size.name())) {
+            Ok(size)
+        } else {
+            Err(format!("unknown size: {:?}", s))
+        }
+    }
+}
+
+string_serde!(Size);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NPC, schema};
+
+    #[test]
+    fn class_parsing_strips_plurals_and_levels() {
+        assert_eq!("Wizards".parse::<Class>().unwrap(), Class::Wizard);
+        assert_eq!("Fighter 5".parse::<Class>().unwrap(), Class::Fighter);
+        assert_eq!("  rOGUE ".parse::<Class>().unwrap(), Class::Rogue);
+        assert_eq!("Blood Hunter".parse::<Class>().unwrap(), Class::Other("Blood Hunter".to_string()));
+        assert!("12".parse::<Class>().is_err());
+        assert_eq!("Fighter 3 / Wizards, Rogue".parse::<Classes>().unwrap().to_string(), "Fighter/Wizard/Rogue");
+    }
+
+    #[test]
+    fn skill_ability_and_alignment_parsing_is_lenient() {
+        assert_eq!("Stealth (Dex)".parse::<Skill>().unwrap(), Skill::Stealth);
+        assert_eq!("sleight-of-hand".parse::<Skill>().unwrap(), Skill::SleightOfHand);
+        assert_eq!("Animal Handeling".parse::<Skill>().unwrap(), Skill::AnimalHandling);
+        assert!("Thieves' Tools".parse::<Skill>().is_err());
+
+        assert_eq!("con".parse::<Ability>().unwrap(), Ability::Constitution);
+        assert_eq!("Intelligence/Charisma".parse::<Ability>().unwrap(), Ability::Intelligence);
+
+        assert_eq!("CG".parse::<Alignment>().unwrap(), Alignment::ChaoticGood);
+        assert_eq!("true-neutral".parse::<Alignment>().unwrap(), Alignment::TrueNeutral);
+        assert_eq!("Chaotic Neutral (leaning good)".parse::<Alignment>().unwrap(), Alignment::ChaoticNeutral);
+        assert_eq!("Mostly harmless".parse::<Alignment>().unwrap(), Alignment::Other("Mostly harmless".to_string()));
+        assert!(" ".parse::<Alignment>().is_err());
+    }
+
+    #[test]
+    fn baseline_file_round_trips() {
+        let raw: serde_json::Value = serde_json::from_str(include_str!("../tests/fixtures/baseline.json")).unwrap();
+        let (npc, warnings) = schema::upgrade_value(raw).unwrap();
+
+        assert_eq!(npc.class_name, Classes(vec![Class::Fighter, Class::Rogue]));
+        assert_eq!(npc.alignment, Alignment::LawfulNeutral);
+        assert_eq!(npc.attacks[0].damage_type, DamageType::Bludgeoning);
+        let proficient: Vec<Skill> = npc.skills.iter().filter(|s| s.proficient).map(|s| s.name).collect();
+        assert_eq!(proficient, [Skill::Athletics, Skill::SleightOfHand, Skill::Stealth]);
+        let saves: Vec<Ability> = npc.saving_throws.iter().filter(|s| s.proficient).map(|s| s.ability).collect();
+        assert_eq!(saves, [Ability::Strength, Ability::Constitution]);
+
+        // What the typed lists can't hold is kept elsewhere or reported, never lost silently
+        assert_eq!(npc.tool_proficiencies, ["Smith's tools", "Thieves' Tools"]);
+        assert!(warnings.contains(&"skills[2]: \"Thieves' Tools\" is a tool, moved to tool_proficiencies".to_string()));
+        assert!(warnings.contains(&"saving_throws[2]: \"Luck\" is not recognized and was dropped".to_string()));
+
+        let saved = serde_json::to_string_pretty(&npc).unwrap();
+        let reloaded: NPC = serde_json::from_str(&saved).unwrap();
+        assert_eq!(serde_json::to_string_pretty(&reloaded).unwrap(), saved);
+    }
+}
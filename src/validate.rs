@@ -0,0 +1,191 @@
+//! `npcforge validate`: check saved NPC files without regenerating them.
+//!
+//! A file passes when it loads without repairs and the numbers NPCForge derives (proficiency
+//! bonus, save DC, skill and save modifiers, class levels) agree with what it stores, which
+//! catches hand edits that changed an ability score or level and nothing else.
+
+use anyhow::{Context, Result, bail};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::export::signed;
+use crate::stats::proficiency_bonus_for_level;
+use crate::types::{Ability, Skill};
+use crate::{NPC, schema};
+
+/// Stored `field` differs from the value NPCForge computes.
+fn mismatch(field: &str, stored: i64, expected: i64) -> String {
+    format!("{}: stored {}, expected {}", field, signed(stored as i8), signed(expected as i8))
+}
+
+/// Rule and arithmetic problems in `npc`, as loaded from `raw`.
+fn check(npc: &NPC, raw: &Value) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if let Some(levels) = &npc.class_levels {
+        let total: u32 = levels.values().map(|&l| l as u32).sum();
+        if total != npc.level as u32 {
+            problems.push(format!("class_levels: add up to {}, but level is {}", total, npc.level));
+        }
+        for class in &npc.class_name.0 {
+            if !levels.contains_key(class) {
+                problems.push(format!("class_levels: no levels given for {}", class));
+            }
+        }
+        for class in levels.keys() {
+            if !npc.class_name.contains(class) {
+                problems.push(format!("class_levels: {} is not one of the NPC's classes", class));
+            }
+        }
+    }
+
+    if npc.hit_points.max == 0 {
+        problems.push("hit_points.max: is 0".to_string());
+    }
+    if npc.hit_points.current > npc.hit_points.max {
+        problems.push(format!("hit_points.current: {} is above the maximum of {}", npc.hit_points.current, npc.hit_points.max));
+    }
+
+    let pb = proficiency_bonus_for_level(npc.level);
+    if let Some(stored) = raw.get("proficiency_bonus").and_then(Value::as_i64)
+        && stored != pb as i64
+    {
+        problems.push(mismatch("proficiency_bonus", stored, pb.into()));
+    }
+
+    if let Some(spells) = &npc.spells
+        && let Some(ability) = spells.spellcasting_ability
+    {
+        let modifier = npc.ability_mod(ability);
+        let dc = 8 + pb + modifier;
+        if spells.spell_save_dc as i8 != dc {
+            problems.push(format!("spells.spell_save_dc: stored {}, expected {}", spells.spell_save_dc, dc));
+        }
+        if spells.spell_attack_bonus != pb + modifier {
+            problems.push(mismatch("spells.spell_attack_bonus", spells.spell_attack_bonus.into(), (pb + modifier).into()));
+        }
+    }
+
+    // Only entries the file lists; older files leave skills and saves out entirely
+    for entry in raw.get("skills").and_then(Value::as_array).into_iter().flatten() {
+        let skill = entry.get("name").and_then(Value::as_str).and_then(|name| name.parse::<Skill>().ok());
+        if let (Some(skill), Some(stored)) = (skill, entry.get("modifier").and_then(Value::as_i64)) {
+            let expected = npc.skill_modifier(skill);
+            if stored != expected as i64 {
+                problems.push(mismatch(&format!("skills.{}", skill.name()), stored, expected.into()));
+            }
+        }
+    }
+    for entry in raw.get("saving_throws").and_then(Value::as_array).into_iter().flatten() {
+        let ability = entry.get("ability").and_then(Value::as_str).and_then(|name| name.parse::<Ability>().ok());
+        if let (Some(ability), Some(stored)) = (ability, entry.get("modifier").and_then(Value::as_i64))
+            && let Some(save) = npc.saving_throws.iter().find(|s| s.ability == ability)
+            && stored != save.modifier as i64
+        {
+            problems.push(mismatch(&format!("saving_throws.{}", ability.name()), stored, save.modifier.into()));
+        }
+    }
+    if let Some(stored) = raw.get("passive_perception").and_then(Value::as_i64)
+        && stored != npc.passive_perception as i64
+    {
+        problems.push(format!("passive_perception: stored {}, expected {}", stored, npc.passive_perception));
+    }
+
+    problems
+}
+
+/// Problems with one file, plus notes that don't make it invalid.
+fn validate_file(path: &Path) -> Result<(Vec<String>, Vec<String>)> {
+    let text = fs::read_to_string(path).context(format!("Failed to read {}", path.display()))?;
+    let raw: Value = serde_json::from_str(&text).context(format!("{} is not valid JSON", path.display()))?;
+
+    let mut notes = Vec::new();
+    let version = schema::schema_version_of(&raw)?;
+    if version < schema::CURRENT_SCHEMA_VERSION {
+        notes.push(format!(
+            "schema version {} (current is {}); `npcforge migrate` upgrades it",
+            version,
+            schema::CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    let (npc, repairs) = schema::upgrade_value(raw.clone()).context("Failed to load NPC")?;
+    let mut problems: Vec<String> = repairs.into_iter().map(|r| format!("{} (repaired when loading)", r)).collect();
+    problems.extend(check(&npc, &raw));
+    Ok((problems, notes))
+}
+
+/// Validate each file, reporting every problem, and fail if any file has one.
+pub fn validate_files(files: &[PathBuf]) -> Result<()> {
+    let mut valid = 0;
+    let mut invalid = 0;
+    for file in files {
+        match validate_file(file) {
+            Ok((problems, notes)) => {
+                if problems.is_empty() {
+                    println!("✓ {}", file.display());
+                    valid += 1;
+                } else {
+                    println!("✗ {} ({} problem(s))", file.display(), problems.len());
+                    for problem in &problems {
+                        println!("  - {}", problem);
+                    }
+                    invalid += 1;
+                }
+                for note in &notes {
+                    println!("  ⚠ {}", note);
+                }
+            }
+            Err(e) => {
+                println!("✗ {}: {:#}", file.display(), e);
+                invalid += 1;
+            }
+        }
+    }
+
+    println!("\n=== Summary ===");
+    println!("Valid: {}", valid);
+    if invalid > 0 {
+        println!("Invalid: {}", invalid);
+        bail!("{} file(s) failed validation", invalid);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(raw: Value) -> Vec<String> {
+        let (npc, repairs) = schema::upgrade_value(raw.clone()).unwrap();
+        assert!(repairs.is_empty(), "{:?}", repairs);
+        check(&npc, &raw)
+    }
+
+    fn seraphine() -> Value {
+        serde_json::from_str(include_str!("../tests/fixtures/seraphine.json")).unwrap()
+    }
+
+    #[test]
+    fn fixture_is_valid() {
+        assert_eq!(problems(seraphine()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn hand_edited_numbers_are_reported() {
+        let mut raw = seraphine();
+        // Jack of All Trades gives half of PB 3, rounded down, on top of Dexterity's +2
+        raw["skills"][0]["modifier"] = 5.into();
+        raw["spells"]["spell_save_dc"] = 14.into();
+        raw["class_levels"]["Warlock"] = 3.into();
+        assert_eq!(
+            problems(raw),
+            [
+                "class_levels: add up to 6, but level is 5",
+                "spells.spell_save_dc: stored 14, expected 15",
+                "skills.Acrobatics: stored +5, expected +3",
+            ]
+        );
+    }
+}
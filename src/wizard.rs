@@ -0,0 +1,349 @@
+//! `npcforge new`: choose the generation options step by step from lists instead of flags.
+//!
+//! Every answer is checked as it is typed, the class levels go through the same checks as
+//! the command-line flags, and the finished request is shown (with the equivalent command
+//! line) before anything is generated.
+
+use anyhow::{Result, bail};
+use dialoguer::theme::{ColorfulTheme, SimpleTheme, Theme};
+use dialoguer::{Confirm, Input, Select};
+use std::io::IsTerminal;
+
+use crate::GenerateArgs;
+use crate::concept::SPECIES;
+use crate::types::{Alignment, Class};
+
+/// Occupations offered for the role, after the default and "random".
+const ROLES: [&str; 16] = [
+    "Soldier", "Guard", "Merchant", "Innkeeper", "Blacksmith", "Scholar", "Priest", "Noble", "Sailor",
+    "Pirate", "Smuggler", "Thief", "Spy", "Hunter", "Healer", "Hermit",
+];
+
+/// Ask for a whole number in `range`, re-asking until the answer fits.
+fn ask_number(theme: &dyn Theme, prompt: &str, range: std::ops::RangeInclusive<u8>, default: u8) -> Result<u8> {
+    let (low, high) = (*range.start(), *range.end());
+    let answer = Input::<u8>::with_theme(theme)
+        .with_prompt(format!("{} ({}-{})", prompt, low, high))
+        .default(default.clamp(low, high))
+        .validate_with(move |n: &u8| -> Result<(), String> {
+            if (low..=high).contains(n) { Ok(()) } else { Err(format!("Enter a number from {} to {}", low, high)) }
+        })
+        .interact_text()?;
+    Ok(answer)
+}
+
+/// Ask for a line of text that must not be empty.
+fn ask_text(theme: &dyn Theme, prompt: &str) -> Result<String> {
+    let answer = Input::<String>::with_theme(theme)
+        .with_prompt(prompt)
+        .validate_with(|text: &String| -> Result<(), &str> {
+            if text.trim().is_empty() { Err("Enter something, or press Ctrl-C to quit") } else { Ok(()) }
+        })
+        .interact_text()?;
+    Ok(answer.trim().to_string())
+}
+
+fn select(theme: &dyn Theme, prompt: &str, items: &[String]) -> Result<usize> {
+    Ok(Select::with_theme(theme).with_prompt(prompt).items(items).default(0).interact()?)
+}
+
+fn ask_species(theme: &dyn Theme) -> Result<Option<String>> {
+    let mut species: Vec<&str> = SPECIES.to_vec();
+    species.sort_unstable();
+    let mut items = vec!["Random".to_string()];
+    items.extend(species.iter().map(|s| s.to_string()));
+    items.push("Other (type it)".to_string());
+
+    let pick = select(theme, "Species", &items)?;
+    Ok(match pick {
+        0 => None,
+        _ if pick == items.len() - 1 => Some(ask_text(theme, "Species")?),
+        _ => Some(species[pick - 1].to_string()),
+    })
+}
+
+/// Up to three different official classes; empty leaves the class to the model.
+fn ask_classes(theme: &dyn Theme) -> Result<Vec<Class>> {
+    let mut classes: Vec<Class> = Vec::new();
+    while classes.len() < 3 {
+        let available: Vec<Class> = Class::ALL.into_iter().filter(|c| !classes.contains(c)).collect();
+        let (prompt, first) = if classes.is_empty() {
+            ("Class", "Random")
+        } else {
+            ("Multiclass into another class?", "No, that's all")
+        };
+        let mut items = vec![first.to_string()];
+        items.extend(available.iter().map(|c| c.name().to_string()));
+        match select(theme, prompt, &items)? {
+            0 => break,
+            pick => classes.push(available[pick - 1].clone()),
+        }
+    }
+    Ok(classes)
+}
+
+/// Levels the next of `classes` classes may take, after the ones in `chosen`: whatever keeps the
+/// total at 20 while every class after it still gets a level of its own.
+fn class_level_range(classes: usize, chosen: &[u8]) -> std::ops::RangeInclusive<u8> {
+    let used: u8 = chosen.iter().sum();
+    let left = (classes - chosen.len() - 1) as u8;
+    1..=20 - used - left
+}
+
+/// Fill in the level options for the chosen classes.
+fn ask_levels(theme: &dyn Theme, classes: &[Class], args: &mut GenerateArgs) -> Result<()> {
+    args.level = None;
+    (args.lvl1, args.lvl2, args.lvl3) = (None, None, None);
+    (args.low, args.high) = (1, 10);
+
+    if classes.len() > 1 {
+        let items = [
+            "Set the levels of each class".to_string(),
+            "Set a total level and let the model split it".to_string(),
+            "Let the model choose".to_string(),
+        ];
+        match select(theme, "Levels", &items)? {
+            0 => {
+                let mut levels = Vec::new();
+                for class in classes {
+                    let range = class_level_range(classes.len(), &levels);
+                    levels.push(ask_number(theme, &format!("{} levels", class.name()), range, 1)?);
+                }
+                args.lvl1 = levels.first().copied();
+                args.lvl2 = levels.get(1).copied();
+                args.lvl3 = levels.get(2).copied();
+            }
+            1 => args.level = Some(ask_number(theme, "Total level", classes.len() as u8..=20, 5)?),
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    let items = [
+        "Random, 1-10".to_string(),
+        "A specific level".to_string(),
+        "Random within a range".to_string(),
+    ];
+    match select(theme, "Level", &items)? {
+        1 => args.level = Some(ask_number(theme, "Level", 1..=20, 5)?),
+        2 => {
+            args.low = ask_number(theme, "Lowest level", 1..=20, 1)?;
+            args.high = ask_number(theme, "Highest level", args.low..=20, 10)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn ask_alignment(theme: &dyn Theme) -> Result<Option<String>> {
+    let mut items = vec!["Random".to_string()];
+    items.extend(Alignment::ALL.iter().map(|a| a.name().to_string()));
+    Ok(match select(theme, "Alignment", &items)? {
+        0 => None,
+        pick => Some(items[pick].clone()),
+    })
+}
+
+fn ask_role(theme: &dyn Theme) -> Result<String> {
+    let mut items = vec!["Mercenary".to_string(), "Random".to_string()];
+    items.extend(ROLES.iter().map(|r| r.to_string()));
+    items.push("Other (type it)".to_string());
+    let pick = select(theme, "Role", &items)?;
+    Ok(match pick {
+        1 => "random".to_string(),
+        _ if pick == items.len() - 1 => ask_text(theme, "Role")?,
+        _ => items[pick].clone(),
+    })
+}
+
+/// `(melee, ranged)` flags for the chosen fighting preference.
+fn ask_fighting(theme: &dyn Theme) -> Result<(bool, bool)> {
+    let items = [
+        "No preference".to_string(),
+        "Melee".to_string(),
+        "Ranged".to_string(),
+        "Versatile (both)".to_string(),
+    ];
+    Ok(match select(theme, "Fighting preference", &items)? {
+        1 => (true, false),
+        2 => (false, true),
+        3 => (true, true),
+        _ => (false, false),
+    })
+}
+
+fn fighting_label(args: &GenerateArgs) -> &'static str {
+    match (args.melee, args.ranged) {
+        (true, true) => "Versatile",
+        (true, false) => "Melee",
+        (false, true) => "Ranged",
+        (false, false) => "No preference",
+    }
+}
+
+/// The chosen options in plain words.
+fn summary(args: &GenerateArgs) -> String {
+    let random = || "Random".to_string();
+    let classes: Vec<&str> = args.class.as_deref().map(|c| c.split(',').collect()).unwrap_or_default();
+    let levels = [args.lvl1, args.lvl2, args.lvl3];
+    let class = if classes.is_empty() {
+        random()
+    } else if args.lvl1.is_some() {
+        let split: Vec<String> = classes.iter().zip(levels).map(|(c, l)| format!("{} {}", c, l.unwrap_or(1))).collect();
+        split.join(" / ")
+    } else {
+        classes.join(" / ")
+    };
+    let level = match (args.level, args.lvl1) {
+        (_, Some(_)) => levels.iter().flatten().sum::<u8>().to_string(),
+        (Some(level), None) if classes.len() > 1 => format!("{} in total, split by the model", level),
+        (Some(level), None) => level.to_string(),
+        (None, None) => format!("Random, {}-{}", args.low, args.high),
+    };
+    let lines = [
+        ("Species", args.race.clone().unwrap_or_else(random)),
+        ("Class", class),
+        ("Level", level),
+        ("Alignment", args.alignment.clone().unwrap_or_else(random)),
+        ("Role", if args.role == "random" { random() } else { args.role.clone() }),
+        ("Fighting", fighting_label(args).to_string()),
+        ("Name", args.name.clone().unwrap_or_else(random)),
+        ("Count", args.count.to_string()),
+    ];
+    lines.iter().map(|(label, value)| format!("  {:<10} {}\n", format!("{}:", label), value)).collect()
+}
+
+/// The flags that make the same request, so it can be repeated without the wizard.
+fn command_line(args: &GenerateArgs) -> String {
+    let quote = |value: &str| {
+        if value.contains(|c: char| c.is_whitespace() || c == '\'' || c == '"') {
+            format!("\"{}\"", value.replace('"', "\\\""))
+        } else {
+            value.to_string()
+        }
+    };
+    let mut parts = vec!["npcforge".to_string()];
+    let mut push = |flag: &str, value: String| parts.push(format!("{} {}", flag, quote(&value)));
+    if let Some(name) = &args.name {
+        push("--name", name.clone());
+    } else if args.count != 1 {
+        push("-n", args.count.to_string());
+    }
+    if let Some(race) = &args.race {
+        push("--race", race.clone());
+    }
+    if let Some(class) = &args.class {
+        push("--class", class.clone());
+    }
+    if let Some(level) = args.level {
+        push("--level", level.to_string());
+    }
+    for (flag, level) in [("--lvl1", args.lvl1), ("--lvl2", args.lvl2), ("--lvl3", args.lvl3)] {
+        if let Some(level) = level {
+            push(flag, level.to_string());
+        }
+    }
+    if args.low != 1 {
+        push("--low", args.low.to_string());
+    }
+    if args.high != 10 {
+        push("--high", args.high.to_string());
+    }
+    if let Some(alignment) = &args.alignment {
+        push("--alignment", alignment.clone());
+    }
+    if args.role != "Mercenary" {
+        push("--role", args.role.clone());
+    }
+    if args.melee {
+        parts.push("--melee".to_string());
+    }
+    if args.ranged {
+        parts.push("--ranged".to_string());
+    }
+    parts.join(" ")
+}
+
+/// Ask for the generation options, starting from `args` (which carries the output flags given
+/// to `new`). Returns `None` if the user decides not to generate.
+pub fn build_request(mut args: GenerateArgs) -> Result<Option<GenerateArgs>> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        bail!("`npcforge new` needs an interactive terminal; pass the options as flags instead (see `npcforge --help`)");
+    }
+    let colorful = ColorfulTheme::default();
+    let theme: &dyn Theme = if std::env::var_os("NO_COLOR").is_some() { &SimpleTheme } else { &colorful };
+
+    println!("=== NPCForge - New NPC ===");
+    println!("Pick from the lists with the arrow keys and Enter (Ctrl-C quits).\n");
+
+    args.race = ask_species(theme)?;
+    let classes = ask_classes(theme)?;
+    args.class = (!classes.is_empty()).then(|| classes.iter().map(Class::name).collect::<Vec<_>>().join(","));
+    ask_levels(theme, &classes, &mut args)?;
+    // The choices above can't break these rules, but the generator checks them the same way
+    if let Err(message) = crate::check_levels(&args) {
+        bail!(message);
+    }
+    args.alignment = ask_alignment(theme)?;
+    args.role = ask_role(theme)?;
+    (args.melee, args.ranged) = ask_fighting(theme)?;
+
+    let name = Input::<String>::with_theme(theme)
+        .with_prompt("Name (leave empty for a random one)")
+        .allow_empty(true)
+        .interact_text()?;
+    args.name = Some(name.trim().to_string()).filter(|n| !n.is_empty());
+    args.count = match args.name {
+        Some(_) => 1,
+        None => ask_number(theme, "How many NPCs", 1..=25, 1)?,
+    };
+
+    println!("\n=== Request ===");
+    print!("{}", summary(&args));
+    println!("\nSame as: {}\n", command_line(&args));
+
+    let generate = Confirm::with_theme(theme).with_prompt("Generate now?").default(true).interact()?;
+    Ok(generate.then_some(args))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn args(flags: &[&str]) -> GenerateArgs {
+        crate::Cli::try_parse_from(std::iter::once("npcforge").chain(flags.iter().copied())).unwrap().generate
+    }
+
+    #[test]
+    fn class_levels_leave_room_for_the_later_classes() {
+        assert_eq!(class_level_range(1, &[]), 1..=20);
+        assert_eq!(class_level_range(3, &[]), 1..=18);
+        assert_eq!(class_level_range(3, &[17]), 1..=2);
+        assert_eq!(class_level_range(3, &[17, 2]), 1..=1);
+    }
+
+    #[test]
+    fn multiclass_answers_pass_the_level_checks() {
+        // The most the wizard allows for three classes
+        let mut request = args(&["--class", "Fighter,Wizard,Rogue"]);
+        (request.lvl1, request.lvl2, request.lvl3) = (Some(18), Some(1), Some(1));
+        assert_eq!(crate::check_levels(&request), Ok(Some(vec![18, 1, 1])));
+        assert!(summary(&request).contains("Fighter 18 / Wizard 1 / Rogue 1\n  Level:     20\n"));
+        assert_eq!(command_line(&request), "npcforge --class Fighter,Wizard,Rogue --lvl1 18 --lvl2 1 --lvl3 1");
+
+        // A total split by the model
+        let mut request = args(&["--class", "Fighter,Wizard"]);
+        request.level = Some(2);
+        assert_eq!(crate::check_levels(&request), Ok(None));
+        assert!(summary(&request).contains("2 in total, split by the model"));
+    }
+
+    #[test]
+    fn command_line_repeats_the_request() {
+        let request = args(&["--race", "Half-Elf", "--role", "Town Guard", "--low", "3", "--melee", "-n", "4"]);
+        assert_eq!(command_line(&request), "npcforge -n 4 --race Half-Elf --low 3 --role \"Town Guard\" --melee");
+        assert_eq!(args(&[]).role, "Mercenary");
+        assert_eq!(command_line(&args(&[])), "npcforge");
+    }
+}
@@ -0,0 +1,221 @@
+//! Checks for the VTT exporters, run on `tests/fixtures/seraphine.json`:
+//!
+//! - the structure each importer reads is present: Fantasy Grounds' `<root>/<npc>/<id-NNNNN>`
+//!   record with its typed fields, abilities and actions, and the Roll20 character's `attribs`
+//!   with the NPC sheet attributes and one `repeating_npcaction` row per attack;
+//! - the output still matches the checked-in snapshot byte for byte. The snapshots come from
+//!   these exporters, so they only catch unintended changes; after an intentional format
+//!   change, regenerate them with `npcforge export tests/fixtures/seraphine.json --to <format>`.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const FIXTURE: &str = "tests/fixtures/seraphine.json";
+
+const ABILITIES: [&str; 6] = ["strength", "dexterity", "constitution", "intelligence", "wisdom", "charisma"];
+
+/// Export the fixture to `format` and return the written file.
+fn export(format: &str, extension: &str) -> String {
+    // Tests run in parallel, and two of them may export the same format
+    static RUN: AtomicUsize = AtomicUsize::new(0);
+    let run = RUN.fetch_add(1, Ordering::Relaxed);
+    let out_dir: PathBuf =
+        std::env::temp_dir().join(format!("npcforge-conformance-{}-{}-{}", format, std::process::id(), run));
+    let status = Command::new(env!("CARGO_BIN_EXE_npcforge"))
+        .args(["export", FIXTURE, "--to", format, "--out-dir"])
+        .arg(&out_dir)
+        .status()
+        .expect("failed to run npcforge");
+    assert!(status.success(), "npcforge export --to {} failed", format);
+
+    let actual = fs::read_to_string(out_dir.join(format!("seraphine.{}", extension))).expect("export was not written");
+    let _ = fs::remove_dir_all(&out_dir);
+    actual
+}
+
+fn check_snapshot(format: &str, extension: &str) {
+    let actual = export(format, extension);
+    let file_name = format!("seraphine.{}", extension);
+    let expected = fs::read_to_string(Path::new("tests/fixtures").join(&file_name)).expect("missing fixture");
+    assert_eq!(actual, expected, "{} export differs from tests/fixtures/{}", format, file_name);
+}
+
+/// Names of the fixture's attacks, in order.
+fn fixture_attacks() -> Vec<String> {
+    let npc: Value = serde_json::from_str(&fs::read_to_string(FIXTURE).expect("missing fixture")).unwrap();
+    npc["attacks"].as_array().unwrap().iter().map(|a| a["name"].as_str().unwrap().to_string()).collect()
+}
+
+/// Just enough of an XML element tree to check the Fantasy Grounds layout.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: BTreeMap<String, String>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn child(&self, name: &str) -> &Element {
+        self.children
+            .iter()
+            .find(|c| c.name == name)
+            .unwrap_or_else(|| panic!("<{}> has no <{}>", self.name, name))
+    }
+
+    /// A `<name type="...">` field, checking its type and, for numbers, its value.
+    fn field(&self, name: &str, kind: &str) -> &str {
+        let field = self.child(name);
+        assert_eq!(field.attributes.get("type").map(String::as_str), Some(kind), "<{}> type", name);
+        if kind == "number" {
+            assert!(field.text.parse::<i32>().is_ok(), "<{}> is not a number: {:?}", name, field.text);
+        }
+        &field.text
+    }
+}
+
+/// Parse `xml` into its root element, failing on unbalanced or mismatched tags.
+fn parse_xml(xml: &str) -> Element {
+    let mut stack = vec![Element::default()];
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        stack.last_mut().unwrap().text.push_str(&rest[..start]);
+        let end = rest[start..].find('>').expect("unterminated tag") + start;
+        let tag = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+
+        if tag.starts_with('?') {
+            continue;
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            let element = stack.pop().unwrap();
+            assert_eq!(element.name, name, "mismatched closing tag");
+            stack.last_mut().expect("closing tag without an opening one").children.push(element);
+            continue;
+        }
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let (name, mut attrs) = tag.split_once(' ').unwrap_or((tag, ""));
+        let mut element = Element { name: name.to_string(), ..Element::default() };
+        while let Some((key, value)) = attrs.trim_start().split_once("=\"") {
+            let (value, after) = value.split_once('"').expect("unterminated attribute");
+            element.attributes.insert(key.to_string(), value.to_string());
+            attrs = after;
+        }
+        if self_closing {
+            stack.last_mut().unwrap().children.push(element);
+        } else {
+            stack.push(element);
+        }
+    }
+    assert_eq!(stack.len(), 1, "unclosed <{}>", stack.last().unwrap().name);
+    let mut document = stack.pop().unwrap();
+    assert_eq!(document.children.len(), 1, "expected a single root element");
+    document.children.pop().unwrap()
+}
+
+fn is_record_id(name: &str) -> bool {
+    name.strip_prefix("id-").is_some_and(|n| n.len() == 5 && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+#[test]
+fn fantasy_grounds_export_has_npc_record_structure() {
+    let root = parse_xml(&export("fantasy-grounds", "fg.xml"));
+    assert_eq!(root.name, "root");
+    for attribute in ["version", "release"] {
+        assert!(root.attributes.contains_key(attribute), "<root> has no {} attribute", attribute);
+    }
+
+    let records = &root.child("npc").children;
+    assert_eq!(records.len(), 1);
+    let npc = &records[0];
+    assert!(is_record_id(&npc.name), "NPC record is <{}>, not <id-NNNNN>", npc.name);
+
+    assert_eq!(npc.field("name", "string"), "Seraphine Voss");
+    for name in ["size", "type", "alignment", "hd", "speed"] {
+        assert!(!npc.field(name, "string").is_empty(), "<{}> is empty", name);
+    }
+    npc.field("ac", "number");
+    npc.field("hp", "number");
+
+    let abilities = npc.child("abilities");
+    for ability in ABILITIES {
+        let ability = abilities.child(ability);
+        ability.field("score", "number");
+        ability.field("bonus", "number");
+    }
+
+    let actions = &npc.child("actions").children;
+    for action in actions {
+        assert!(is_record_id(&action.name), "action record is <{}>, not <id-NNNNN>", action.name);
+        assert!(action.field("desc", "string").contains("to hit"), "action <desc> has no attack roll");
+    }
+    let names: Vec<&str> = actions.iter().map(|a| a.field("name", "string")).collect();
+    assert_eq!(names, fixture_attacks());
+}
+
+#[test]
+fn roll20_export_has_npc_attributes_and_actions() {
+    let character: Value = serde_json::from_str(&export("roll20", "roll20.json")).expect("export is not JSON");
+    assert_eq!(character["name"], "Seraphine Voss");
+    assert!(character["abilities"].is_array(), "no abilities list");
+
+    let mut attribs: BTreeMap<&str, &Value> = BTreeMap::new();
+    for attrib in character["attribs"].as_array().expect("no attribs list") {
+        for key in ["name", "current", "max", "id"] {
+            assert!(attrib[key].is_string(), "attrib field {} is not a string: {}", key, attrib);
+        }
+        let name = attrib["name"].as_str().unwrap();
+        assert!(attribs.insert(name, attrib).is_none(), "attrib {} is set twice", name);
+    }
+    let current = |name: &str| -> &str {
+        attribs.get(name).unwrap_or_else(|| panic!("no {} attrib", name))["current"].as_str().unwrap()
+    };
+    let number = |name: &str| current(name).parse::<i32>().unwrap_or_else(|_| panic!("{} is not a number", name));
+
+    assert_eq!(current("npc"), "1", "not flagged as an NPC sheet");
+    assert_eq!(current("npc_name"), "Seraphine Voss");
+    number("npc_ac");
+    assert!(attribs["hp"]["max"].as_str().unwrap().parse::<i32>().is_ok(), "hp has no max");
+    assert!(!current("npc_speed").is_empty());
+    for ability in ABILITIES {
+        number(ability);
+        number(&format!("{}_mod", ability));
+    }
+
+    // Row ids are Roll20's "-" + 19 characters, shared by every field of the row
+    let mut rows: Vec<&str> = Vec::new();
+    for name in attribs.keys() {
+        if let Some(row) = name.strip_prefix("repeating_npcaction_").and_then(|rest| rest.strip_suffix("_name")) {
+            assert!(row.starts_with('-') && row.len() == 20, "bad row id {}", row);
+            rows.push(row);
+        }
+    }
+    let mut names = Vec::new();
+    for row in rows {
+        let field = |suffix: &str| format!("repeating_npcaction_{}_{}", row, suffix);
+        assert_eq!(current(&field("attack_flag")), "on");
+        assert!(["Melee", "Ranged"].contains(&current(&field("attack_type"))));
+        number(&field("attack_tohit"));
+        assert!(!current(&field("attack_damage")).is_empty());
+        names.push(current(&field("name")).to_string());
+    }
+    let mut expected = fixture_attacks();
+    expected.sort();
+    names.sort();
+    assert_eq!(names, expected);
+}
+
+#[test]
+fn fantasy_grounds_export_matches_snapshot() {
+    check_snapshot("fantasy-grounds", "fg.xml");
+}
+
+#[test]
+fn roll20_export_matches_snapshot() {
+    check_snapshot("roll20", "roll20.json");
+}